@@ -0,0 +1,18 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="tilesheet_animated" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <tileoffset x="2" y="-4"/>
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="3" type="water" probability="0.5">
+  <objectgroup draworder="index" id="2">
+   <object id="1" x="0" y="16" width="32" height="16"/>
+   <object id="2" x="4" y="4">
+    <polygon points="0,0 8,0 8,8"/>
+   </object>
+  </objectgroup>
+  <animation>
+   <frame tileid="3" duration="200"/>
+   <frame tileid="4" duration="150"/>
+   <frame tileid="5" duration="200"/>
+  </animation>
+ </tile>
+</tileset>
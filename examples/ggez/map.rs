@@ -19,7 +19,10 @@ impl MapHandler {
         let mut tileset_image_cache = HashMap::new();
         for ts in map.tilesets().iter() {
             if let Some(image) = &ts.image {
-                let img = graphics::Image::from_path(ctx, &image.source)?;
+                let img = match &image.source {
+                    tiled::ImageSource::File(path) => graphics::Image::from_path(ctx, path)?,
+                    tiled::ImageSource::Embedded(data) => graphics::Image::from_bytes(ctx, data)?,
+                };
 
                 tileset_image_cache.insert(ts.name.clone(), img);
             }
@@ -34,8 +34,12 @@ fn main() {
                 println!(
                     "Image layer with {}",
                     match &layer.image {
-                        Some(img) =>
-                            format!("an image with source = {}", img.source.to_string_lossy()),
+                        Some(img) => match &img.source {
+                            tiled::ImageSource::File(path) =>
+                                format!("an image with source = {}", path.to_string_lossy()),
+                            tiled::ImageSource::Embedded(data) =>
+                                format!("an embedded image of {} bytes", data.len()),
+                        },
                         None => "no image".to_owned(),
                     }
                 )
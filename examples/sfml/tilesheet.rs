@@ -20,6 +20,8 @@ impl Tilesheet {
         let texture = {
             let texture_path = &tileset_image
                 .source
+                .path()
+                .expect("tileset image should be stored in a file")
                 .to_str()
                 .expect("obtaining valid UTF-8 path");
             Texture::from_file(texture_path).unwrap()
@@ -1,10 +1,15 @@
 //! Structures related to tile animations.
 
+use std::io::Write;
+
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field, json_object, JsonObject};
 use crate::{
     error::{Error, Result},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
 };
 
 /// A structure describing a [frame] of a [TMX tile animation].
@@ -30,6 +35,14 @@ impl Frame {
         );
         Ok(Frame { tile_id, duration })
     }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(frame: &JsonObject) -> Result<Frame> {
+        Ok(Frame {
+            tile_id: json_field(frame, "tileid")?,
+            duration: json_field(frame, "duration")?,
+        })
+    }
 }
 
 pub(crate) fn parse_animation(
@@ -44,3 +57,34 @@ pub(crate) fn parse_animation(
     });
     Ok(animation)
 }
+
+pub(crate) fn write_animation(writer: &mut XmlWriter<impl Write>, frames: &[Frame]) -> Result<()> {
+    writer.start("animation", &[])?;
+    for frame in frames {
+        writer.empty(
+            "frame",
+            &[
+                ("tileid", frame.tile_id.to_string()),
+                ("duration", frame.duration.to_string()),
+            ],
+        )?;
+    }
+    writer.end()
+}
+
+#[cfg(feature = "json")]
+pub(crate) fn parse_animation_json(frames: &[serde_json::Value]) -> Result<Vec<Frame>> {
+    frames
+        .iter()
+        .map(|frame| Frame::from_json(json_object(frame)?))
+        .collect()
+}
+
+#[cfg(feature = "json")]
+pub(crate) fn write_animation_json(frames: &[Frame]) -> serde_json::Value {
+    frames
+        .iter()
+        .map(|frame| serde_json::json!({ "tileid": frame.tile_id, "duration": frame.duration }))
+        .collect::<Vec<_>>()
+        .into()
+}
@@ -57,9 +57,17 @@ pub enum Error {
     CsvDecodingError(CsvDecodingError),
     /// An error occurred when parsing an XML file, such as a TMX or TSX file.
     XmlDecodingError(xml::reader::Error),
-    #[cfg(feature = "world")]
+    /// An error occurred when writing an XML file, such as a TMX or TSX file.
+    XmlEncodingError(xml::writer::Error),
+    /// An error occurred when compressing tile data using the
+    /// [flate2](https://github.com/alexcrichton/flate2-rs) or zstd crates.
+    CompressingError(std::io::Error),
+    #[cfg(feature = "json")]
     /// An error occurred when attempting to deserialize a JSON file.
     JsonDecodingError(serde_json::Error),
+    #[cfg(feature = "json")]
+    /// An error occurred when attempting to serialize a JSON file.
+    JsonEncodingError(serde_json::Error),
     #[cfg(feature = "world")]
     /// Filename does not match any pattern in the world file.
     NoMatchFound {
@@ -82,7 +90,8 @@ pub enum Error {
     },
     /// There was an invalid tile in the map parsed.
     InvalidTileFound,
-    /// Unknown encoding or compression format or invalid combination of both (for tile layers)
+    /// Unknown encoding or compression format or invalid combination of both (for tile layers and
+    /// embedded images)
     InvalidEncodingFormat {
         /// The `encoding` attribute of the tile layer data, if any.
         encoding: Option<String>,
@@ -118,6 +127,11 @@ pub enum Error {
     },
     /// There was an invalid tileset in the map parsed.
     InvalidTileset(InvalidTilesetError),
+    /// The data being written can't be represented in the format it is being written to.
+    UnsupportedFeature {
+        /// A description of the data that couldn't be written.
+        description: String,
+    },
 }
 
 /// A result with an error variant of [`crate::Error`].
@@ -131,8 +145,12 @@ impl fmt::Display for Error {
             Error::Base64DecodingError(e) => write!(fmt, "{}", e),
             Error::CsvDecodingError(e) => write!(fmt, "{}", e),
             Error::XmlDecodingError(e) => write!(fmt, "{}", e),
-            #[cfg(feature = "world")]
+            Error::XmlEncodingError(e) => write!(fmt, "{}", e),
+            Error::CompressingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "json")]
             Error::JsonDecodingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "json")]
+            Error::JsonEncodingError(e) => write!(fmt, "{}", e),
             #[cfg(feature = "world")]
             Error::NoMatchFound { path } => {
                 write!(fmt, "No match found for path: '{}'", path)
@@ -176,6 +194,8 @@ impl fmt::Display for Error {
             Error::InvalidObjectData{description} =>
                 write!(fmt, "Invalid object data: {}", description),
             Error::InvalidTileset(e) => write!(fmt, "{}", e),
+            Error::UnsupportedFeature { description } =>
+                write!(fmt, "Unsupported feature: {}", description),
         }
     }
 }
@@ -186,6 +206,8 @@ impl std::error::Error for Error {
             Error::DecompressingError(e) => Some(e as &dyn std::error::Error),
             Error::Base64DecodingError(e) => Some(e as &dyn std::error::Error),
             Error::XmlDecodingError(e) => Some(e as &dyn std::error::Error),
+            Error::XmlEncodingError(e) => Some(e as &dyn std::error::Error),
+            Error::CompressingError(e) => Some(e as &dyn std::error::Error),
             Error::ResourceLoadingError { err, .. } => Some(err.as_ref()),
             _ => None,
         }
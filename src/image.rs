@@ -1,22 +1,61 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field, json_field_opt, json_parse_opt, JsonObject};
 use crate::{
     error::{Error, Result},
     properties::Color,
     util::*,
+    write::{relative_path, XmlWriter},
 };
 
-/// A reference to an image stored somewhere within the filesystem.
+/// Where the data of an [`Image`] comes from.
+#[derive(PartialEq, Eq, Clone)]
+pub enum ImageSource {
+    /// The image is stored in a separate file. Holds the **uncanonicalized** filepath of the image,
+    /// starting from the path given to load the file this image is in. See the example in
+    /// [`Image::source`] for more details.
+    File(PathBuf),
+    /// The image is embedded in the file it is in, as a `<data>` element. Holds the decoded image
+    /// data, which is usually in the format given by [`Image::format`].
+    Embedded(Vec<u8>),
+}
+
+impl ImageSource {
+    /// Returns the path of the image file, or [`None`] if the image is embedded.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ImageSource::File(path) => Some(path),
+            ImageSource::Embedded(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for ImageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageSource::File(path) => f.debug_tuple("File").field(path).finish(),
+            ImageSource::Embedded(data) => f
+                .debug_tuple("Embedded")
+                .field(&format_args!("{} bytes", data.len()))
+                .finish(),
+        }
+    }
+}
+
+/// An image, either stored somewhere within the filesystem or embedded in the file it is in.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Image {
-    /// The **uncanonicalized** filepath of the image, starting from the path given to load the file
-    /// this image is in. See the example for more details.
+    /// Where the image is stored.
     ///
     /// ## Note
-    /// The crate does not currently support embedded images (Even though Tiled
-    /// does not allow creating maps with embedded image data, the TMX format does; [source])
+    /// Even though Tiled does not allow creating maps with embedded image data, the TMX format
+    /// does ([source]). JSON files can only reference image files.
     ///
     /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
     ///
@@ -42,7 +81,7 @@ pub struct Image {
     /// // Image layer has an image with the source attribute set to "../tilesheet.png"
     /// // Given the information we gave to the `parse_file` function, the image source should be
     /// // "assets/folder/../tilesheet.png". The filepath is not canonicalized.
-    /// let image_source = &image_layer.image.as_ref().unwrap().source;
+    /// let image_source = image_layer.image.as_ref().unwrap().source.path().unwrap();
     ///
     /// assert_eq!(
     ///     image_source,
@@ -57,8 +96,10 @@ pub struct Image {
     /// ```
     /// Check the assets/tiled_relative_paths.tmx file at the crate root to see the structure of the
     /// file this example is referring to.
-    // TODO: Embedded images
-    pub source: PathBuf,
+    pub source: ImageSource,
+    /// The format of the image data, given as a file extension such as `png`. Mostly used by
+    /// embedded images.
+    pub format: Option<String>,
     /// The width in pixels of the image.
     pub width: i32,
     /// The height in pixels of the image.
@@ -73,22 +114,123 @@ impl Image {
         attrs: Vec<OwnedAttribute>,
         path_relative_to: impl AsRef<Path>,
     ) -> Result<Image> {
-        let (c, (s, w, h)) = get_attrs!(
+        let (c, f, s, (w, h)) = get_attrs!(
             for v in attrs {
                 Some("trans") => trans ?= v.parse(),
-                "source" => source = v,
+                Some("format") => format = v,
+                Some("source") => source = v,
                 "width" => width ?= v.parse::<i32>(),
                 "height" => height ?= v.parse::<i32>(),
             }
-            (trans, (source, width, height))
+            (trans, format, source, (width, height))
         );
 
-        parse_tag!(parser, "image", {});
+        let mut data = None;
+        parse_tag!(parser, "image", {
+            "data" => |attrs: Vec<OwnedAttribute>| {
+                let encoding = get_attrs!(
+                    for v in attrs {
+                        Some("encoding") => encoding = v,
+                    }
+                    encoding
+                );
+                if encoding.as_deref() != Some("base64") {
+                    return Err(Error::InvalidEncodingFormat { encoding, compression: None });
+                }
+                data = Some(parse_base64(parser)?);
+                Ok(())
+            },
+        });
+
+        let source = match (s, data) {
+            (_, Some(data)) => ImageSource::Embedded(data),
+            (Some(s), None) => ImageSource::File(path_relative_to.as_ref().join(s)),
+            (None, None) => {
+                return Err(Error::MalformedAttributes(
+                    "image has neither a source nor embedded data".to_owned(),
+                ))
+            }
+        };
+
         Ok(Image {
-            source: path_relative_to.as_ref().join(s),
+            source,
+            format: f,
             width: w,
             height: h,
             transparent_colour: c,
         })
     }
+
+    /// JSON files store images inline, through the `image`, `imagewidth`, `imageheight` and
+    /// `transparentcolor` members of the object that owns them.
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        owner: &JsonObject,
+        path_relative_to: impl AsRef<Path>,
+    ) -> Result<Option<Image>> {
+        let source = match json_field_opt::<String>(owner, "image")? {
+            Some(source) if !source.is_empty() => source,
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Image {
+            source: ImageSource::File(path_relative_to.as_ref().join(source)),
+            format: None,
+            width: json_field(owner, "imagewidth")?,
+            height: json_field(owner, "imageheight")?,
+            transparent_colour: json_parse_opt(owner, "transparentcolor")?,
+        }))
+    }
+
+    /// Writes this image as an `<image>` element, with its source relative to `base_path`.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        base_path: &Path,
+    ) -> Result<()> {
+        let mut attrs = Vec::new();
+        if let Some(format) = &self.format {
+            attrs.push(("format", format.clone()));
+        }
+        if let ImageSource::File(path) = &self.source {
+            attrs.push(("source", relative_path(base_path, path)));
+        }
+        if let Some(trans) = self.transparent_colour {
+            // Tiled writes the transparent color without the leading '#'.
+            attrs.push(("trans", trans.to_string()[1..].to_owned()));
+        }
+        attrs.push(("width", self.width.to_string()));
+        attrs.push(("height", self.height.to_string()));
+
+        writer.start("image", &attrs)?;
+        if let ImageSource::Embedded(data) = &self.source {
+            writer.start("data", &[("encoding", "base64".to_owned())])?;
+            writer.characters(&encode_base64(data))?;
+            writer.end()?;
+        }
+        writer.end()
+    }
+
+    /// Writes this image into the `image`, `imagewidth`, `imageheight` and `transparentcolor`
+    /// members of the JSON object that owns it, with its source relative to `base_path`.
+    ///
+    /// Fails for embedded images, since JSON files can't contain them.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, owner: &mut JsonObject, base_path: &Path) -> Result<()> {
+        let path = match &self.source {
+            ImageSource::File(path) => path,
+            ImageSource::Embedded(_) => {
+                return Err(Error::UnsupportedFeature {
+                    description: "embedded images can't be written to JSON files".to_owned(),
+                })
+            }
+        };
+        owner.insert("image".to_owned(), relative_path(base_path, path).into());
+        owner.insert("imagewidth".to_owned(), self.width.into());
+        owner.insert("imageheight".to_owned(), self.height.into());
+        if let Some(trans) = self.transparent_colour {
+            owner.insert("transparentcolor".to_owned(), trans.to_string().into());
+        }
+        Ok(())
+    }
 }
@@ -1,11 +1,15 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, io::Write, path::Path, sync::Arc};
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_array, json_object, JsonObject};
 use crate::{
     error::Result,
     layers::{LayerData, LayerTag},
-    properties::{parse_properties, Properties},
+    properties::{parse_properties, write_properties, Properties},
     util::*,
-    Error, Layer, MapTilesetGid, ResourceCache, ResourceReader, Tileset,
+    write::{WriteContext, XmlWriter},
+    CameraRect, Color, Error, Layer, LayerType, MapTilesetGid, Project, ResourceCache,
+    ResourceReader, Tileset, XmlElement,
 };
 
 /// The raw data of a [`GroupLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -15,17 +19,20 @@ pub struct GroupLayerData {
 }
 
 impl GroupLayerData {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         infinite: bool,
         map_path: &Path,
         tilesets: &[MapTilesetGid],
         for_tileset: Option<Arc<Tileset>>,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
-    ) -> Result<(Self, Properties)> {
+    ) -> Result<(Self, Properties, Vec<(usize, XmlElement)>)> {
         let mut properties = HashMap::new();
         let mut layers = Vec::new();
+        let mut unknown_elements = Vec::new();
         parse_tag!(parser, "group", {
             "layer" => |attrs| {
                 layers.push(LayerData::new(
@@ -35,7 +42,9 @@ impl GroupLayerData {
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
+                    for_tileset.as_ref().cloned(),
+                    preserve_unknown,
+                    reader,
                     cache
                 )?);
                 Ok(())
@@ -48,7 +57,9 @@ impl GroupLayerData {
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
+                    for_tileset.as_ref().cloned(),
+                    preserve_unknown,
+                    reader,
                     cache
                 )?);
                 Ok(())
@@ -61,7 +72,9 @@ impl GroupLayerData {
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
+                    for_tileset.as_ref().cloned(),
+                    preserve_unknown,
+                    reader,
                     cache
                 )?);
                 Ok(())
@@ -74,7 +87,9 @@ impl GroupLayerData {
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
+                    for_tileset.as_ref().cloned(),
+                    preserve_unknown,
+                    reader,
                     cache
                 )?);
                 Ok(())
@@ -83,8 +98,93 @@ impl GroupLayerData {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown_elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
-        Ok((Self { layers }, properties))
+        Ok((Self { layers }, properties, unknown_elements))
+    }
+
+    /// Writes this layer as a `<group>` element, given the attributes common to all layers.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        attrs: Vec<(&str, String)>,
+        properties: &Properties,
+        unknown_elements: &[(usize, XmlElement)],
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        writer.start_with_unknown("group", &attrs, unknown_elements)?;
+        write_properties(writer, properties)?;
+        for layer in &self.layers {
+            layer.write_xml(writer, ctx)?;
+        }
+        writer.end()
+    }
+
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        for layer in &mut self.layers {
+            layer.apply_project(project);
+        }
+    }
+
+    /// The raw data of the layers within this group.
+    pub(crate) fn layer_data(&self) -> &[LayerData] {
+        &self.layers
+    }
+
+    pub(crate) fn layer_data_mut(&mut self) -> &mut [LayerData] {
+        &mut self.layers
+    }
+
+    pub(crate) fn max_ids(&self) -> (u32, u32) {
+        self.layers
+            .iter()
+            .map(LayerData::max_ids)
+            .fold((0, 0), |(l1, o1), (l2, o2)| (l1.max(l2), o1.max(o2)))
+    }
+}
+
+#[cfg(feature = "json")]
+impl GroupLayerData {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_json(
+        group: &JsonObject,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+        for_tileset: Option<Arc<Tileset>>,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Self> {
+        let mut layers = Vec::new();
+        for layer in json_array(group, "layers")? {
+            layers.push(LayerData::from_json(
+                json_object(layer)?,
+                infinite,
+                map_path,
+                tilesets,
+                for_tileset.as_ref().cloned(),
+                preserve_unknown,
+                reader,
+                cache,
+            )?);
+        }
+        Ok(Self { layers })
+    }
+
+    /// Adds the members specific to group layers to a JSON layer.
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) -> Result<()> {
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.to_json(ctx).map(serde_json::Value::Object))
+            .collect::<Result<Vec<_>>>()?;
+        layer.insert("layers".to_owned(), layers.into());
+        Ok(())
     }
 }
 
@@ -92,8 +192,9 @@ map_wrapper!(
     #[doc = "A group layer, used to organize the layers of the map in a hierarchy."]
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#group)."]
     #[doc = "## Note"]
-    #[doc = "In Tiled, the properties of the group layer recursively affect child layers.
-    Implementing this behavior is left up to the user of this library."]
+    #[doc = "In Tiled, the offset, opacity, visibility, tint color and parallax factor of a group
+    layer recursively affect its child layers. [`Map::leaf_layers`](crate::Map::leaf_layers) lists
+    the layers of a map along with these values combined."]
     GroupLayer => GroupLayerData
 );
 
@@ -136,3 +237,84 @@ impl<'map> GroupLayer<'map> {
             .map(|data| Layer::new(self.map, data))
     }
 }
+
+/// A layer that isn't a group layer, along with the values of its properties that are affected by
+/// the group layers it is in, combined the way Tiled does it. See [`Map::leaf_layers`].
+///
+/// [`Map::leaf_layers`]: crate::Map::leaf_layers
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EffectiveLayer<'map> {
+    /// The layer itself, whose own properties don't take its group layers into account.
+    pub layer: Layer<'map>,
+    /// The sum of the X offsets of the layer and its group layers, in pixels.
+    pub offset_x: f32,
+    /// The sum of the Y offsets of the layer and its group layers, in pixels.
+    pub offset_y: f32,
+    /// The product of the opacities of the layer and its group layers.
+    pub opacity: f32,
+    /// Whether the layer and all of its group layers are visible.
+    pub visible: bool,
+    /// The product of the tint colors of the layer and its group layers, with each channel
+    /// multiplied separately, or [`None`] if none of them are tinted.
+    pub tint_color: Option<Color>,
+    /// The product of the X parallax factors of the layer and its group layers.
+    pub parallax_x: f32,
+    /// The product of the Y parallax factors of the layer and its group layers.
+    pub parallax_y: f32,
+}
+
+impl<'map> EffectiveLayer<'map> {
+    /// Returns where the image of an image layer is drawn, using its effective offset and parallax
+    /// factor. See [`Layer::image_placements`].
+    pub fn image_placements(&self, camera: CameraRect) -> Vec<(f32, f32)> {
+        self.layer.image_placements_with(
+            (self.offset_x, self.offset_y),
+            (self.parallax_x, self.parallax_y),
+            camera,
+        )
+    }
+
+    /// Combines the properties of a layer with those of the group layer it is in, if any.
+    fn new(layer: Layer<'map>, parent: Option<&Self>) -> Self {
+        let own = Self {
+            layer,
+            offset_x: layer.offset_x,
+            offset_y: layer.offset_y,
+            opacity: layer.opacity,
+            visible: layer.visible,
+            tint_color: layer.tint_color,
+            parallax_x: layer.parallax_x,
+            parallax_y: layer.parallax_y,
+        };
+        match parent {
+            Some(parent) => Self {
+                offset_x: parent.offset_x + own.offset_x,
+                offset_y: parent.offset_y + own.offset_y,
+                opacity: parent.opacity * own.opacity,
+                visible: parent.visible && own.visible,
+                tint_color: match (parent.tint_color, own.tint_color) {
+                    (Some(a), Some(b)) => Some(a.multiply(b)),
+                    (a, b) => a.or(b),
+                },
+                parallax_x: parent.parallax_x * own.parallax_x,
+                parallax_y: parent.parallax_y * own.parallax_y,
+                ..own
+            },
+            None => own,
+        }
+    }
+
+    /// Appends a layer to `leaves` if it isn't a group layer, or the layers within it otherwise,
+    /// depth first.
+    pub(crate) fn collect(layer: Layer<'map>, parent: Option<&Self>, leaves: &mut Vec<Self>) {
+        let effective = Self::new(layer, parent);
+        match layer.layer_type() {
+            LayerType::Group(group) => {
+                for child in group.layers() {
+                    Self::collect(child, Some(&effective), leaves);
+                }
+            }
+            _ => leaves.push(effective),
+        }
+    }
+}
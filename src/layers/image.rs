@@ -1,9 +1,15 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Write, path::Path};
 
+use xml::attribute::OwnedAttribute;
+
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field_opt, JsonObject};
 use crate::{
     parse_properties,
-    util::{map_wrapper, parse_tag, XmlEventResult},
-    Error, Image, Properties, Result,
+    properties::write_properties,
+    util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
+    write::{WriteContext, XmlWriter},
+    Error, Image, Properties, Result, XmlElement,
 };
 
 /// The raw data of an [`ImageLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -11,15 +17,29 @@ use crate::{
 pub struct ImageLayerData {
     /// The single image this layer contains, if it exists.
     pub image: Option<Image>,
+    /// Whether the image is repeated along the X axis.
+    pub repeat_x: bool,
+    /// Whether the image is repeated along the Y axis.
+    pub repeat_y: bool,
 }
 
 impl ImageLayerData {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
+        attrs: Vec<OwnedAttribute>,
         map_path: &Path,
-    ) -> Result<(Self, Properties)> {
+        preserve_unknown: bool,
+    ) -> Result<(Self, Properties, Vec<(usize, XmlElement)>)> {
+        let (repeat_x, repeat_y) = get_attrs!(
+            for v in attrs {
+                Some("repeatx") => repeat_x = v == "1",
+                Some("repeaty") => repeat_y = v == "1",
+            }
+            (repeat_x, repeat_y)
+        );
         let mut image: Option<Image> = None;
         let mut properties = HashMap::new();
+        let mut unknown_elements = Vec::new();
 
         let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
 
@@ -32,8 +52,124 @@ impl ImageLayerData {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown_elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
-        Ok((ImageLayerData { image }, properties))
+        Ok((
+            ImageLayerData {
+                image,
+                repeat_x: repeat_x.unwrap_or(false),
+                repeat_y: repeat_y.unwrap_or(false),
+            },
+            properties,
+            unknown_elements,
+        ))
+    }
+
+    /// Writes this layer as an `<imagelayer>` element, given the attributes common to all layers.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+        unknown_elements: &[(usize, XmlElement)],
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        if self.repeat_x {
+            attrs.push(("repeatx", "1".to_owned()));
+        }
+        if self.repeat_y {
+            attrs.push(("repeaty", "1".to_owned()));
+        }
+        writer.start_with_unknown("imagelayer", &attrs, unknown_elements)?;
+        write_properties(writer, properties)?;
+        if let Some(image) = &self.image {
+            image.write_xml(writer, ctx.base_path)?;
+        }
+        writer.end()
+    }
+
+    /// Returns the positions of the copies of this layer's image that overlap the camera, if the
+    /// image is placed at `position`, relative to the top-left corner of the camera.
+    pub(crate) fn placements(&self, position: (f32, f32), camera: CameraRect) -> Vec<(f32, f32)> {
+        let image = match &self.image {
+            Some(image) => image,
+            None => return Vec::new(),
+        };
+        let xs = axis_positions(
+            position.0,
+            image.width as f32,
+            self.repeat_x,
+            (camera.x, camera.x + camera.width),
+        );
+        let ys = axis_positions(
+            position.1,
+            image.height as f32,
+            self.repeat_y,
+            (camera.y, camera.y + camera.height),
+        );
+        ys.iter()
+            .flat_map(|y| xs.iter().map(move |x| (x - camera.x, y - camera.y)))
+            .collect()
+    }
+}
+
+/// Returns where copies of an image starting at `start` with the given size are placed along an
+/// axis so that they overlap the `view` range.
+fn axis_positions(start: f32, size: f32, repeat: bool, view: (f32, f32)) -> Vec<f32> {
+    if repeat && size > 0.0 {
+        let first = start + ((view.0 - start) / size).floor() * size;
+        (0..)
+            .map(|i| first + i as f32 * size)
+            .take_while(|position| *position < view.1)
+            .collect()
+    } else if start < view.1 && start + size > view.0 {
+        vec![start]
+    } else {
+        Vec::new()
+    }
+}
+
+/// An area of a map shown by a camera, in pixels. See [`Layer::image_placements`](crate::Layer::image_placements).
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CameraRect {
+    /// The X coordinate of the left edge of the area.
+    pub x: f32,
+    /// The Y coordinate of the top edge of the area.
+    pub y: f32,
+    /// The width of the area.
+    pub width: f32,
+    /// The height of the area.
+    pub height: f32,
+}
+
+#[cfg(feature = "json")]
+impl ImageLayerData {
+    pub(crate) fn from_json(layer: &JsonObject, map_path: &Path) -> Result<Self> {
+        let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
+
+        Ok(ImageLayerData {
+            image: Image::from_json(layer, path_relative_to)?,
+            repeat_x: json_field_opt(layer, "repeatx")?.unwrap_or(false),
+            repeat_y: json_field_opt(layer, "repeaty")?.unwrap_or(false),
+        })
+    }
+
+    /// Adds the members specific to image layers to a JSON layer.
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) -> Result<()> {
+        if self.repeat_x {
+            layer.insert("repeatx".to_owned(), true.into());
+        }
+        if self.repeat_y {
+            layer.insert("repeaty".to_owned(), true.into());
+        }
+        match &self.image {
+            Some(image) => image.write_json(layer, ctx.base_path),
+            None => Ok(()),
+        }
     }
 }
 
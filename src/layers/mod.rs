@@ -1,10 +1,21 @@
-use std::{path::Path, sync::Arc};
+use std::{io::Write, path::Path, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
-    error::Result, properties::Properties, util::*, Color, Map, MapTilesetGid, ResourceCache,
-    ResourceReader, Tileset,
+    error::Result,
+    properties::Properties,
+    util::*,
+    write::{WriteContext, XmlWriter},
+    Color, Map, MapTilesetGid, ObjectData, Project, ResourceCache, ResourceReader, Tileset,
+    UnknownXml,
+};
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{json_field, json_field_opt, JsonObject},
+    properties::{parse_properties_json, write_properties_json},
+    write::json_f32,
+    Error,
 };
 
 mod image;
@@ -32,6 +43,18 @@ pub(crate) enum LayerTag {
     Group,
 }
 
+impl LayerTag {
+    /// The attributes read by the parser of this type of layer rather than by [`LayerData::new`].
+    fn specific_attributes(self) -> &'static [&'static str] {
+        match self {
+            LayerTag::Tiles => &["width", "height"],
+            LayerTag::Objects => &["color", "draworder"],
+            LayerTag::Image => &["repeatx", "repeaty"],
+            LayerTag::Group => &[],
+        }
+    }
+}
+
 /// The raw data of a [`Layer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Clone, PartialEq, Debug)]
 pub struct LayerData {
@@ -40,6 +63,8 @@ pub struct LayerData {
     id: u32,
     /// Whether this layer should be visible or not.
     pub visible: bool,
+    /// Whether this layer is locked in the editor, preventing it from being changed.
+    pub locked: bool,
     /// The layer's x offset (in pixels).
     pub offset_x: f32,
     /// The layer's y offset (in pixels).
@@ -56,6 +81,9 @@ pub struct LayerData {
     pub properties: Properties,
     /// The layer's type, which is arbitrarily setby the user.
     pub user_type: Option<String>,
+    /// The attributes and elements of the layer's element that aren't recognized, if they were
+    /// preserved when loading it.
+    pub unknown: UnknownXml,
     layer_type: LayerDataType,
 }
 
@@ -75,6 +103,7 @@ impl LayerData {
         map_path: &Path,
         tilesets: &[MapTilesetGid],
         for_tileset: Option<Arc<Tileset>>,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Self> {
@@ -82,6 +111,7 @@ impl LayerData {
             opacity,
             tint_color,
             visible,
+            locked,
             offset_x,
             offset_y,
             parallax_x,
@@ -90,11 +120,13 @@ impl LayerData {
             id,
             user_type,
             user_class,
+            unknown_attrs,
         ) = get_attrs!(
             for v in attrs {
                 Some("opacity") => opacity ?= v.parse(),
                 Some("tintcolor") => tint_color ?= v.parse(),
                 Some("visible") => visible ?= v.parse().map(|x:i32| x == 1),
+                Some("locked") => locked ?= v.parse().map(|x:i32| x == 1),
                 Some("offsetx") => offset_x ?= v.parse(),
                 Some("offsety") => offset_y ?= v.parse(),
                 Some("parallaxx") => parallax_x ?= v.parse(),
@@ -104,46 +136,59 @@ impl LayerData {
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
             }
-            (opacity, tint_color, visible, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
+            unknown => unknown_attrs,
+            (opacity, tint_color, visible, locked, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class, unknown_attrs)
         );
 
-        let (ty, properties) = match tag {
+        let unknown_attrs = unknown_attrs
+            .into_iter()
+            .filter(|(name, _)| !tag.specific_attributes().contains(&name.as_str()))
+            .collect();
+        let mut unknown = UnknownXml::with_attributes(unknown_attrs, preserve_unknown);
+
+        let (ty, properties, unknown_elements) = match tag {
             LayerTag::Tiles => {
-                let (ty, properties) = TileLayerData::new(parser, attrs, infinite, tilesets)?;
-                (LayerDataType::Tiles(ty), properties)
+                let (ty, properties, elements) =
+                    TileLayerData::new(parser, attrs, infinite, tilesets, preserve_unknown)?;
+                (LayerDataType::Tiles(ty), properties, elements)
             }
             LayerTag::Objects => {
-                let (ty, properties) = ObjectLayerData::new(
+                let (ty, properties, elements) = ObjectLayerData::new(
                     parser,
                     attrs,
                     Some(tilesets),
                     for_tileset,
                     map_path.parent().ok_or(crate::Error::PathIsNotFile)?,
+                    preserve_unknown,
                     reader,
                     cache,
                 )?;
-                (LayerDataType::Objects(ty), properties)
+                (LayerDataType::Objects(ty), properties, elements)
             }
             LayerTag::Image => {
-                let (ty, properties) = ImageLayerData::new(parser, map_path)?;
-                (LayerDataType::Image(ty), properties)
+                let (ty, properties, elements) =
+                    ImageLayerData::new(parser, attrs, map_path, preserve_unknown)?;
+                (LayerDataType::Image(ty), properties, elements)
             }
             LayerTag::Group => {
-                let (ty, properties) = GroupLayerData::new(
+                let (ty, properties, elements) = GroupLayerData::new(
                     parser,
                     infinite,
                     map_path,
                     tilesets,
                     for_tileset,
+                    preserve_unknown,
                     reader,
                     cache,
                 )?;
-                (LayerDataType::Group(ty), properties)
+                (LayerDataType::Group(ty), properties, elements)
             }
         };
+        unknown.elements = unknown_elements;
 
         Ok(Self {
             visible: visible.unwrap_or(true),
+            locked: locked.unwrap_or(false),
             offset_x: offset_x.unwrap_or(0.0),
             offset_y: offset_y.unwrap_or(0.0),
             parallax_x: parallax_x.unwrap_or(1.0),
@@ -154,11 +199,250 @@ impl LayerData {
             id: id.unwrap_or(0),
             user_type: user_type.or(user_class),
             properties,
+            unknown,
             layer_type: ty,
         })
     }
 }
 
+impl LayerData {
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let mut attrs = Vec::new();
+        if self.id != 0 {
+            attrs.push(("id", self.id.to_string()));
+        }
+        attrs.push(("name", self.name.clone()));
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        if !self.visible {
+            attrs.push(("visible", "0".to_owned()));
+        }
+        if self.locked {
+            attrs.push(("locked", "1".to_owned()));
+        }
+        if self.opacity != 1.0 {
+            attrs.push(("opacity", self.opacity.to_string()));
+        }
+        if let Some(tint_color) = self.tint_color {
+            attrs.push(("tintcolor", tint_color.to_string()));
+        }
+        if self.offset_x != 0.0 {
+            attrs.push(("offsetx", self.offset_x.to_string()));
+        }
+        if self.offset_y != 0.0 {
+            attrs.push(("offsety", self.offset_y.to_string()));
+        }
+        if self.parallax_x != 1.0 {
+            attrs.push(("parallaxx", self.parallax_x.to_string()));
+        }
+        if self.parallax_y != 1.0 {
+            attrs.push(("parallaxy", self.parallax_y.to_string()));
+        }
+        self.unknown.extend_attributes(&mut attrs);
+
+        let (properties, elements) = (&self.properties, &self.unknown.elements);
+        match &self.layer_type {
+            LayerDataType::Tiles(data) => data.write_xml(writer, attrs, properties, elements, ctx),
+            LayerDataType::Objects(data) => {
+                data.write_xml(writer, attrs, properties, elements, ctx)
+            }
+            LayerDataType::Image(data) => data.write_xml(writer, attrs, properties, elements, ctx),
+            LayerDataType::Group(data) => data.write_xml(writer, attrs, properties, elements, ctx),
+        }
+    }
+
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        project.apply(&mut self.properties);
+        match &mut self.layer_type {
+            LayerDataType::Objects(data) => data.apply_project(project),
+            LayerDataType::Group(data) => data.apply_project(project),
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => {}
+        }
+    }
+
+    /// The raw data of the layers within this layer if it is a group layer, or an empty slice
+    /// otherwise.
+    pub(crate) fn sublayers(&self) -> &[LayerData] {
+        match &self.layer_type {
+            LayerDataType::Group(data) => data.layer_data(),
+            _ => &[],
+        }
+    }
+
+    /// The raw data of the layers within this layer if it is a group layer, or an empty slice
+    /// otherwise, for modifying them.
+    pub(crate) fn sublayers_mut(&mut self) -> &mut [LayerData] {
+        match &mut self.layer_type {
+            LayerDataType::Group(data) => data.layer_data_mut(),
+            _ => &mut [],
+        }
+    }
+
+    /// The raw data of this layer if it is an object layer.
+    pub(crate) fn object_layer_data(&self) -> Option<&ObjectLayerData> {
+        match &self.layer_type {
+            LayerDataType::Objects(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The raw data of this layer if it is an object layer, for modifying it.
+    pub(crate) fn object_layer_data_mut(&mut self) -> Option<&mut ObjectLayerData> {
+        match &mut self.layer_type {
+            LayerDataType::Objects(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    /// The raw data of the objects in this layer if it is an object layer, or an empty slice
+    /// otherwise.
+    pub(crate) fn object_data(&self) -> &[ObjectData] {
+        match &self.layer_type {
+            LayerDataType::Objects(data) => data.object_data(),
+            _ => &[],
+        }
+    }
+
+    /// Returns the greatest layer ID and object ID used by this layer and any layers within it.
+    pub(crate) fn max_ids(&self) -> (u32, u32) {
+        let (layer_id, object_id) = match &self.layer_type {
+            LayerDataType::Objects(data) => (
+                0,
+                data.object_data().iter().map(|o| o.id()).max().unwrap_or(0),
+            ),
+            LayerDataType::Group(data) => data.max_ids(),
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => (0, 0),
+        };
+        (self.id.max(layer_id), object_id)
+    }
+}
+
+#[cfg(feature = "json")]
+impl LayerData {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_json(
+        layer: &JsonObject,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+        for_tileset: Option<Arc<Tileset>>,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Self> {
+        let ty = match json_field::<String>(layer, "type")?.as_str() {
+            "tilelayer" => {
+                LayerDataType::Tiles(TileLayerData::from_json(layer, infinite, tilesets)?)
+            }
+            "objectgroup" => LayerDataType::Objects(ObjectLayerData::from_json(
+                layer,
+                Some(tilesets),
+                for_tileset,
+                map_path.parent().ok_or(Error::PathIsNotFile)?,
+                preserve_unknown,
+                reader,
+                cache,
+            )?),
+            "imagelayer" => LayerDataType::Image(ImageLayerData::from_json(layer, map_path)?),
+            "group" => LayerDataType::Group(GroupLayerData::from_json(
+                layer,
+                infinite,
+                map_path,
+                tilesets,
+                for_tileset,
+                preserve_unknown,
+                reader,
+                cache,
+            )?),
+            other => {
+                return Err(Error::MalformedAttributes(format!(
+                    "Unknown layer type '{}'",
+                    other
+                )))
+            }
+        };
+
+        Ok(Self {
+            visible: json_field_opt(layer, "visible")?.unwrap_or(true),
+            locked: json_field_opt(layer, "locked")?.unwrap_or(false),
+            offset_x: json_field_opt(layer, "offsetx")?.unwrap_or(0.0),
+            offset_y: json_field_opt(layer, "offsety")?.unwrap_or(0.0),
+            parallax_x: json_field_opt(layer, "parallaxx")?.unwrap_or(1.0),
+            parallax_y: json_field_opt(layer, "parallaxy")?.unwrap_or(1.0),
+            opacity: json_field_opt(layer, "opacity")?.unwrap_or(1.0),
+            tint_color: json_field_opt(layer, "tintcolor")?,
+            name: json_field_opt(layer, "name")?.unwrap_or_default(),
+            id: json_field_opt(layer, "id")?.unwrap_or(0),
+            user_type: json_field_opt(layer, "class")?,
+            properties: parse_properties_json(layer)?,
+            unknown: UnknownXml::default(),
+            layer_type: ty,
+        })
+    }
+
+    pub(crate) fn to_json(&self, ctx: &WriteContext) -> Result<JsonObject> {
+        let mut layer = JsonObject::new();
+        if self.id != 0 {
+            layer.insert("id".to_owned(), self.id.into());
+        }
+        layer.insert("name".to_owned(), self.name.clone().into());
+        if let Some(user_type) = &self.user_type {
+            layer.insert("class".to_owned(), user_type.clone().into());
+        }
+        layer.insert("visible".to_owned(), self.visible.into());
+        if self.locked {
+            layer.insert("locked".to_owned(), true.into());
+        }
+        layer.insert("opacity".to_owned(), json_f32(self.opacity));
+        if let Some(tint_color) = self.tint_color {
+            layer.insert("tintcolor".to_owned(), tint_color.to_string().into());
+        }
+        if self.offset_x != 0.0 {
+            layer.insert("offsetx".to_owned(), json_f32(self.offset_x));
+        }
+        if self.offset_y != 0.0 {
+            layer.insert("offsety".to_owned(), json_f32(self.offset_y));
+        }
+        if self.parallax_x != 1.0 {
+            layer.insert("parallaxx".to_owned(), json_f32(self.parallax_x));
+        }
+        if self.parallax_y != 1.0 {
+            layer.insert("parallaxy".to_owned(), json_f32(self.parallax_y));
+        }
+        write_properties_json(&mut layer, &self.properties);
+
+        let layer_type = match &self.layer_type {
+            LayerDataType::Tiles(data) => {
+                data.write_json(&mut layer, ctx)?;
+                "tilelayer"
+            }
+            LayerDataType::Objects(data) => {
+                data.write_json(&mut layer, ctx);
+                "objectgroup"
+            }
+            LayerDataType::Image(data) => {
+                data.write_json(&mut layer, ctx)?;
+                "imagelayer"
+            }
+            LayerDataType::Group(data) => {
+                data.write_json(&mut layer, ctx)?;
+                "group"
+            }
+        };
+        layer.insert("type".to_owned(), layer_type.into());
+        Ok(layer)
+    }
+}
+
 map_wrapper!(
     #[doc = "A generic map layer, accessed via [`Map::layers()`]."]
     Layer => LayerData
@@ -238,6 +522,115 @@ impl<'map> Layer<'map> {
             _ => None,
         }
     }
+
+    /// Returns the group layers this layer is in, starting with the outermost one. Top-level
+    /// layers aren't in any group layer, so the list is empty for them.
+    ///
+    /// The group layers are returned as [`Layer`]s so that their names and other common properties
+    /// are available; Use [`Layer::as_group_layer`] to access their other layers.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// let layer = map.layer_by_path("group-2/group-3/tile-3").unwrap();
+    ///
+    /// let parents: Vec<&str> = layer
+    ///     .parents()
+    ///     .iter()
+    ///     .map(|group| group.name.as_str())
+    ///     .collect();
+    /// assert_eq!(parents, vec!["group-2", "group-3"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parents(&self) -> Vec<Layer<'map>> {
+        let mut parents = Vec::new();
+        find_parents(self.map.layers(), self.data, &mut parents);
+        parents
+    }
+
+    /// Returns where the image of an image layer is drawn for a camera showing the given area of
+    /// the map, as positions of the top-left corner of the image relative to the top-left corner of
+    /// the camera. The image is repeated as needed to cover the camera along the axes it repeats
+    /// on, and only copies that overlap the camera are returned.
+    ///
+    /// The positions include the layer's offset and parallax scrolling. Following Tiled, a layer
+    /// is moved by `(1 - parallax) * (center - origin)` along each axis, where `center` is the
+    /// center of the camera and `origin` the map's parallax origin.
+    ///
+    /// Returns an empty list for layers that aren't image layers or don't have an image.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{CameraRect, Loader};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_image_layers.tmx")?;
+    /// let layer = map.get_layer(1).unwrap();
+    ///
+    /// let camera = CameraRect {
+    ///     x: 100.0,
+    ///     y: 0.0,
+    ///     width: 320.0,
+    ///     height: 240.0,
+    /// };
+    /// // The layer's image isn't repeated and is at the origin of the map.
+    /// assert_eq!(layer.image_placements(camera), vec![(-100.0, 0.0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn image_placements(&self, camera: CameraRect) -> Vec<(f32, f32)> {
+        self.image_placements_with(
+            (self.data.offset_x, self.data.offset_y),
+            (self.data.parallax_x, self.data.parallax_y),
+            camera,
+        )
+    }
+
+    /// Works out the placements of the image of this layer as if it had the given offset and
+    /// parallax factor. See [`Layer::image_placements`].
+    pub(crate) fn image_placements_with(
+        &self,
+        offset: (f32, f32),
+        parallax: (f32, f32),
+        camera: CameraRect,
+    ) -> Vec<(f32, f32)> {
+        let data = match &self.data.layer_type {
+            LayerDataType::Image(data) => data,
+            _ => return Vec::new(),
+        };
+        let center_x = camera.x + camera.width / 2.0;
+        let center_y = camera.y + camera.height / 2.0;
+        let x = offset.0 + (1.0 - parallax.0) * (center_x - self.map.parallax_origin_x);
+        let y = offset.1 + (1.0 - parallax.1) * (center_y - self.map.parallax_origin_y);
+        data.placements((x, y), camera)
+    }
+}
+
+/// Looks for the layer with the given data among `layers` and the layers within them, pushing the
+/// group layers it is found in to `parents`. Returns whether it was found.
+fn find_parents<'map>(
+    layers: impl Iterator<Item = Layer<'map>>,
+    target: &LayerData,
+    parents: &mut Vec<Layer<'map>>,
+) -> bool {
+    for layer in layers {
+        // Layers are compared by address, since different layers may hold the same data.
+        if std::ptr::eq(layer.data, target) {
+            return true;
+        }
+        if let Some(group) = layer.as_group_layer() {
+            parents.push(layer);
+            if find_parents(group.layers(), target, parents) {
+                return true;
+            }
+            parents.pop();
+        }
+    }
+    false
 }
 
 /// Represents some kind of map layer.
@@ -1,12 +1,16 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, fmt, io::Write, path::Path, str::FromStr, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_array, json_field_opt, json_object, json_parse_opt, JsonObject};
 use crate::{
     parse_properties,
+    properties::write_properties,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, ResourceReader,
-    Result, Tileset,
+    write::{WriteContext, XmlWriter},
+    Color, Error, Layer, MapTilesetGid, Object, ObjectData, Project, Properties, ResourceCache,
+    ResourceReader, Result, Tileset, XmlElement,
 };
 
 /// Raw data referring to a map object layer or tile collision data.
@@ -15,11 +19,15 @@ pub struct ObjectLayerData {
     objects: Vec<ObjectData>,
     /// The color used in the editor to display objects in this layer.
     pub colour: Option<Color>,
+    /// The order in which the objects of this layer are drawn. See
+    /// [`ObjectLayer::objects_in_draw_order`].
+    pub draw_order: DrawOrder,
 }
 
 impl ObjectLayerData {
     /// If it is known that there are no objects with tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -27,28 +35,112 @@ impl ObjectLayerData {
         for_tileset: Option<Arc<Tileset>>,
         // path_relative_to is a directory to which all other files are relative to
         path_relative_to: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
-    ) -> Result<(ObjectLayerData, Properties)> {
-        let c = get_attrs!(
+    ) -> Result<(ObjectLayerData, Properties, Vec<(usize, XmlElement)>)> {
+        let (c, draw_order) = get_attrs!(
             for v in attrs {
                 Some("color") => color ?= v.parse(),
+                Some("draworder") => draw_order ?= v.parse::<DrawOrder>(),
             }
-            color
+            (color, draw_order)
         );
         let mut objects = Vec::new();
         let mut properties = HashMap::new();
+        let mut unknown_elements = Vec::new();
         parse_tag!(parser, "objectgroup", {
             "object" => |attrs| {
-                objects.push(ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, reader, cache)?);
+                objects.push(ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, preserve_unknown, reader, cache)?);
                 Ok(())
             },
             "properties" => |_| {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown_elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
-        Ok((ObjectLayerData { objects, colour: c }, properties))
+        Ok((
+            ObjectLayerData {
+                objects,
+                colour: c,
+                draw_order: draw_order.unwrap_or_default(),
+            },
+            properties,
+            unknown_elements,
+        ))
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        layer: &JsonObject,
+        tilesets: Option<&[MapTilesetGid]>,
+        for_tileset: Option<Arc<Tileset>>,
+        // path_relative_to is a directory to which all other files are relative to
+        path_relative_to: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<ObjectLayerData> {
+        let mut objects = Vec::new();
+        for object in json_array(layer, "objects")? {
+            objects.push(ObjectData::from_json(
+                json_object(object)?,
+                tilesets,
+                for_tileset.as_ref().cloned(),
+                path_relative_to,
+                preserve_unknown,
+                reader,
+                cache,
+            )?);
+        }
+        Ok(ObjectLayerData {
+            objects,
+            colour: json_field_opt(layer, "color")?,
+            draw_order: json_parse_opt(layer, "draworder")?.unwrap_or_default(),
+        })
+    }
+
+    /// Writes this layer as an `<objectgroup>` element, given the attributes common to all layers.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+        unknown_elements: &[(usize, XmlElement)],
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        if let Some(colour) = self.colour {
+            attrs.push(("color", colour.to_string()));
+        }
+        if self.draw_order != DrawOrder::default() {
+            attrs.push(("draworder", self.draw_order.to_string()));
+        }
+        writer.start_with_unknown("objectgroup", &attrs, unknown_elements)?;
+        write_properties(writer, properties)?;
+        for object in &self.objects {
+            object.write_xml(writer, ctx)?;
+        }
+        writer.end()
+    }
+
+    /// Adds the members specific to object layers to a JSON layer.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) {
+        if let Some(colour) = self.colour {
+            layer.insert("color".to_owned(), colour.to_string().into());
+        }
+        layer.insert("draworder".to_owned(), self.draw_order.to_string().into());
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| object.to_json(ctx).into())
+            .collect::<Vec<serde_json::Value>>();
+        layer.insert("objects".to_owned(), objects.into());
     }
 
     /// Returns the data belonging to the objects contained within the layer, in the order they were
@@ -57,6 +149,16 @@ impl ObjectLayerData {
     pub fn object_data(&self) -> &[ObjectData] {
         self.objects.as_ref()
     }
+
+    pub(crate) fn push_object(&mut self, object: ObjectData) {
+        self.objects.push(object);
+    }
+
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        for object in &mut self.objects {
+            object.apply_project(project);
+        }
+    }
 }
 
 map_wrapper!(
@@ -64,6 +166,28 @@ map_wrapper!(
     ObjectLayer => ObjectLayerData);
 
 impl<'map> ObjectLayer<'map> {
+    /// Returns the layer this object layer is, for access to its name and the other data common
+    /// to all layers.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    ///
+    /// let object_layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    /// assert_eq!(object_layer.layer().name, "Object Layer 1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layer(&self) -> Layer<'map> {
+        // Object layers are only ever obtained from a layer of their map.
+        self.map
+            .layer_with_objects(self.data)
+            .expect("object layer not found in its map")
+    }
+
     /// Obtains the object corresponding to the index given.
     pub fn get_object(&self, idx: usize) -> Option<Object<'map>> {
         self.data
@@ -106,4 +230,93 @@ impl<'map> ObjectLayer<'map> {
             .iter()
             .map(move |object| Object::new(map, object))
     }
+
+    /// Returns an iterator over the objects present in this layer, in the order they are drawn in
+    /// according to the layer's [`draw_order`](ObjectLayerData::draw_order).
+    ///
+    /// With [`DrawOrder::TopDown`], objects are sorted by their Y coordinate, keeping the order
+    /// they were declared in for objects at the same height. Tile objects are sorted by the bottom
+    /// edge of their area, which is their Y coordinate unless their tileset has an
+    /// [`object_alignment`](crate::Tileset::object_alignment) that doesn't anchor them at the
+    /// bottom, while other objects are sorted by their top edge.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// for object in layer.objects_in_draw_order() {
+    ///     // Draw the object.
+    /// #   let _ = object;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn objects_in_draw_order(&self) -> impl ExactSizeIterator<Item = Object<'map>> + 'map {
+        let mut objects: Vec<Object<'map>> = self.objects().collect();
+        if self.data.draw_order == DrawOrder::TopDown {
+            // The sort is stable, so objects at the same height stay in index order.
+            objects.sort_by(|a, b| {
+                a.draw_order_y()
+                    .partial_cmp(&b.draw_order_y())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+        objects.into_iter()
+    }
+}
+
+/// The order in which the objects of an [`ObjectLayer`] are drawn.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum DrawOrder {
+    /// Objects are drawn from top to bottom, sorted by their Y coordinate.
+    #[default]
+    TopDown,
+    /// Objects are drawn in the order they appear in the layer.
+    Index,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`DrawOrder`] that is not valid.
+pub struct DrawOrderError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for DrawOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse draw order, valid options are `topdown`, `index` \
+        but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for DrawOrderError {}
+
+impl FromStr for DrawOrder {
+    type Err = DrawOrderError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "topdown" => Ok(DrawOrder::TopDown),
+            "index" => Ok(DrawOrder::Index),
+            _ => Err(DrawOrderError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for DrawOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawOrder::TopDown => write!(f, "topdown"),
+            DrawOrder::Index => write!(f, "index"),
+        }
+    }
 }
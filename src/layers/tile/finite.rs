@@ -1,11 +1,18 @@
+use std::io::Write;
+
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     util::{get_attrs, map_wrapper, XmlEventResult},
-    LayerTile, LayerTileData, MapTilesetGid, Result,
+    write::{WriteContext, XmlWriter},
+    LayerTile, LayerTileData, MapTilesetGid, Result, TileDataEncoding,
 };
 
-use super::util::parse_data_line;
+use super::util::{data_attrs, parse_data_line, write_data_line};
+#[cfg(feature = "json")]
+use super::util::{data_json, parse_data_json};
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field, JsonObject};
 
 /// The raw data of a [`FiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone, Default)]
@@ -14,6 +21,8 @@ pub struct FiniteTileLayerData {
     height: u32,
     /// The tiles are arranged in rows.
     tiles: Vec<Option<LayerTileData>>,
+    /// The way the tile data was stored in the file this layer was loaded from.
+    pub encoding: TileDataEncoding,
 }
 
 impl std::fmt::Debug for FiniteTileLayerData {
@@ -53,15 +62,70 @@ impl FiniteTileLayerData {
             (encoding, compression)
         );
 
-        let tiles = parse_data_line(e, c, parser, tilesets)?;
+        let encoding = TileDataEncoding::from_attrs(e.as_deref(), c.as_deref())?;
+        let tiles = parse_data_line(encoding, parser, tilesets)?;
 
         Ok(Self {
             width,
             height,
             tiles,
+            encoding,
         })
     }
 
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        layer: &JsonObject,
+        data: &serde_json::Value,
+        encoding: TileDataEncoding,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<Self> {
+        let width = json_field(layer, "width")?;
+        let height = json_field(layer, "height")?;
+
+        let tiles = parse_data_json(encoding, data, tilesets)?;
+
+        Ok(Self {
+            width,
+            height,
+            tiles,
+            encoding,
+        })
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let encoding = ctx.tile_encoding(self.encoding);
+        writer.start("data", &data_attrs(encoding))?;
+        write_data_line(
+            writer,
+            encoding,
+            self.tiles.iter().map(Option::as_ref),
+            self.width,
+            ctx,
+        )?;
+        writer.end()
+    }
+
+    /// Adds the `width`, `height` and `data` members of a JSON tile layer.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) -> Result<()> {
+        layer.insert("width".to_owned(), self.width.into());
+        layer.insert("height".to_owned(), self.height.into());
+        layer.insert(
+            "data".to_owned(),
+            data_json(
+                ctx.tile_encoding(self.encoding),
+                self.tiles.iter().map(Option::as_ref),
+                ctx,
+            )?,
+        );
+        Ok(())
+    }
+
     /// Obtains the tile data present at the position given.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
@@ -90,4 +154,22 @@ impl<'map> FiniteTileLayer<'map> {
             .get_tile_data(x, y)
             .map(|data| LayerTile::new(self.map(), data))
     }
+
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions, in
+    /// the order Tiled draws them in. See
+    /// [`TileLayer::tiles_in_render_order`](crate::TileLayer::tiles_in_render_order).
+    pub fn tiles_in_render_order(
+        &self,
+    ) -> impl Iterator<Item = (i32, i32, LayerTile<'map>)> + 'map {
+        let (map, data) = (self.map, self.data);
+        let (width, height) = (data.width as i32, data.height as i32);
+        let mut tiles: Vec<(i32, i32, &'map LayerTileData)> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter_map(|(x, y)| data.get_tile_data(x, y).map(|tile| (x, y, tile)))
+            .collect();
+        tiles.sort_unstable_by(|a, b| map.compare_render_positions((a.0, a.1), (b.0, b.1)));
+        tiles
+            .into_iter()
+            .map(move |(x, y, tile)| (x, y, LayerTile::new(map, tile)))
+    }
 }
@@ -1,18 +1,25 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     util::{floor_div, get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, LayerTile, LayerTileData, MapTilesetGid, Result,
+    write::{WriteContext, XmlWriter},
+    Error, LayerTile, LayerTileData, MapTilesetGid, Result, TileDataEncoding,
 };
 
-use super::util::parse_data_line;
+use super::util::{data_attrs, parse_data_line, write_data_line};
+#[cfg(feature = "json")]
+use super::util::{data_json, parse_data_json};
+#[cfg(feature = "json")]
+use crate::parse::json::{json_array, json_field, json_object, JsonObject};
 
 /// The raw data of a [`InfiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone)]
 pub struct InfiniteTileLayerData {
     chunks: HashMap<(i32, i32), ChunkData>,
+    /// The way the tile data was stored in the file this layer was loaded from.
+    pub encoding: TileDataEncoding,
 }
 
 impl std::fmt::Debug for InfiniteTileLayerData {
@@ -34,31 +41,89 @@ impl InfiniteTileLayerData {
             }
             (encoding, compression)
         );
+        let encoding = TileDataEncoding::from_attrs(e.as_deref(), c.as_deref())?;
 
         let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
         parse_tag!(parser, "data", {
             "chunk" => |attrs| {
-                let chunk = InternalChunk::new(parser, attrs, e.clone(), c.clone(), tilesets)?;
-                for x in chunk.x..chunk.x + chunk.width as i32 {
-                    for y in chunk.y..chunk.y + chunk.height as i32 {
-                        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
-                        let relative_pos = (x - chunk_pos.0 * ChunkData::WIDTH as i32, y - chunk_pos.1 * ChunkData::HEIGHT as i32);
-                        let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
-                        let internal_pos = (x - chunk.x, y - chunk.y);
-                        let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
-
-                        if internal_index >= chunk.tiles.len() {
-                            return Err(Error::InvalidTileFound);
-                        }
-
-                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles[internal_index];
-                    }
-                }
-                Ok(())
+                let chunk = InternalChunk::new(parser, attrs, encoding, tilesets)?;
+                chunk.merge_into(&mut chunks)
             }
         });
 
-        Ok(Self { chunks })
+        Ok(Self { chunks, encoding })
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        layer: &JsonObject,
+        encoding: TileDataEncoding,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<Self> {
+        let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
+        for chunk in json_array(layer, "chunks")? {
+            InternalChunk::from_json(json_object(chunk)?, encoding, tilesets)?
+                .merge_into(&mut chunks)?;
+        }
+
+        Ok(Self { chunks, encoding })
+    }
+
+    /// Writes the layer's `<data>` element, with one `<chunk>` per chunk of this layer.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let mut chunks: Vec<_> = self.chunk_data().collect();
+        chunks.sort_by_key(|&((x, y), _)| (y, x));
+
+        let encoding = ctx.tile_encoding(self.encoding);
+        writer.start("data", &data_attrs(encoding))?;
+        for ((x, y), chunk) in chunks {
+            writer.start(
+                "chunk",
+                &[
+                    ("x", (x * ChunkData::WIDTH as i32).to_string()),
+                    ("y", (y * ChunkData::HEIGHT as i32).to_string()),
+                    ("width", ChunkData::WIDTH.to_string()),
+                    ("height", ChunkData::HEIGHT.to_string()),
+                ],
+            )?;
+            write_data_line(
+                writer,
+                encoding,
+                chunk.tiles.iter().map(Option::as_ref),
+                ChunkData::WIDTH,
+                ctx,
+            )?;
+            writer.end()?;
+        }
+        writer.end()
+    }
+
+    /// Adds the `chunks` member of a JSON tile layer, with one chunk per chunk of this layer.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) -> Result<()> {
+        let mut chunks: Vec<_> = self.chunk_data().collect();
+        chunks.sort_by_key(|&((x, y), _)| (y, x));
+
+        let encoding = ctx.tile_encoding(self.encoding);
+        let chunks = chunks
+            .into_iter()
+            .map(|((x, y), chunk)| {
+                let data = data_json(encoding, chunk.tiles.iter().map(Option::as_ref), ctx)?;
+                Ok(serde_json::json!({
+                    "x": x * ChunkData::WIDTH as i32,
+                    "y": y * ChunkData::HEIGHT as i32,
+                    "width": ChunkData::WIDTH,
+                    "height": ChunkData::HEIGHT,
+                    "data": data,
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        layer.insert("chunks".to_owned(), chunks.into());
+        Ok(())
     }
 
     /// Obtains the tile data present at the position given.
@@ -187,8 +252,7 @@ impl InternalChunk {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
-        encoding: Option<String>,
-        compression: Option<String>,
+        encoding: TileDataEncoding,
         tilesets: &[MapTilesetGid],
     ) -> Result<Self> {
         let (x, y, width, height) = get_attrs!(
@@ -201,7 +265,7 @@ impl InternalChunk {
             (x, y, width, height)
         );
 
-        let tiles = parse_data_line(encoding, compression, parser, tilesets)?;
+        let tiles = parse_data_line(encoding, parser, tilesets)?;
 
         Ok(InternalChunk {
             x,
@@ -211,6 +275,56 @@ impl InternalChunk {
             tiles,
         })
     }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        chunk: &JsonObject,
+        encoding: TileDataEncoding,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<Self> {
+        let x = json_field(chunk, "x")?;
+        let y = json_field(chunk, "y")?;
+        let width = json_field(chunk, "width")?;
+        let height = json_field(chunk, "height")?;
+        let data = chunk
+            .get("data")
+            .ok_or_else(|| Error::MalformedAttributes("Missing attribute: data".to_owned()))?;
+
+        let tiles = parse_data_json(encoding, data, tilesets)?;
+
+        Ok(InternalChunk {
+            x,
+            y,
+            width,
+            height,
+            tiles,
+        })
+    }
+
+    /// Copies the tiles of this chunk into the fixed-size chunks of an infinite layer.
+    fn merge_into(&self, chunks: &mut HashMap<(i32, i32), ChunkData>) -> Result<()> {
+        for x in self.x..self.x + self.width as i32 {
+            for y in self.y..self.y + self.height as i32 {
+                let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+                let relative_pos = (
+                    x - chunk_pos.0 * ChunkData::WIDTH as i32,
+                    y - chunk_pos.1 * ChunkData::HEIGHT as i32,
+                );
+                let chunk_index =
+                    (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
+                let internal_pos = (x - self.x, y - self.y);
+                let internal_index = (internal_pos.0 + internal_pos.1 * self.width as i32) as usize;
+
+                if internal_index >= self.tiles.len() {
+                    return Err(Error::InvalidTileFound);
+                }
+
+                chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] =
+                    self.tiles[internal_index];
+            }
+        }
+        Ok(())
+    }
 }
 
 map_wrapper!(
@@ -275,6 +389,39 @@ impl<'map> InfiniteTileLayer<'map> {
             .map(move |(pos, chunk)| (*pos, Chunk::new(map, chunk)))
     }
 
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions, in
+    /// the order Tiled draws them in. See
+    /// [`TileLayer::tiles_in_render_order`](crate::TileLayer::tiles_in_render_order).
+    ///
+    /// Since the tiles are stored in chunks, they are all gathered and sorted beforehand.
+    pub fn tiles_in_render_order(
+        &self,
+    ) -> impl Iterator<Item = (i32, i32, LayerTile<'map>)> + 'map {
+        let map: &'map crate::Map = self.map;
+        let mut tiles: Vec<(i32, i32, &'map LayerTileData)> = self
+            .data
+            .chunks
+            .iter()
+            .flat_map(|(&(chunk_x, chunk_y), chunk)| {
+                chunk
+                    .tiles
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, tile)| {
+                        let x = chunk_x * ChunkData::WIDTH as i32
+                            + (index % ChunkData::WIDTH as usize) as i32;
+                        let y = chunk_y * ChunkData::HEIGHT as i32
+                            + (index / ChunkData::WIDTH as usize) as i32;
+                        tile.as_ref().map(|tile| (x, y, tile))
+                    })
+            })
+            .collect();
+        tiles.sort_unstable_by(|a, b| map.compare_render_positions((a.0, a.1), (b.0, b.1)));
+        tiles
+            .into_iter()
+            .map(move |(x, y, tile)| (x, y, LayerTile::new(map, tile)))
+    }
+
     /// Obtains a chunk by its position. To obtain the position of the chunk that contains a tile,
     /// use [`ChunkData::tile_to_chunk_pos()`].
     #[inline]
@@ -1,13 +1,20 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     parse_properties,
+    properties::write_properties,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
+    write::{WriteContext, XmlWriter},
+    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset, XmlElement,
 };
 
+#[cfg(feature = "json")]
+use self::util::data_attrs;
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field_opt, JsonObject};
+
 mod finite;
 mod infinite;
 mod util;
@@ -15,6 +22,74 @@ mod util;
 pub use finite::*;
 pub use infinite::*;
 
+/// The way the tile data of a [`TileLayer`] is stored in its file.
+///
+/// This has no effect on the contents of the layer, but is kept so that it can be written back
+/// the same way it was read.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TileDataEncoding {
+    /// Comma-separated GIDs (or a plain array of GIDs in JSON files).
+    #[default]
+    Csv,
+    /// Base64-encoded little-endian GIDs, optionally compressed.
+    Base64(Option<TileDataCompression>),
+    /// One `<tile gid="..."/>` element per tile. This legacy format is only found in TMX files;
+    /// JSON files store it as a plain array of GIDs instead.
+    Xml,
+}
+
+/// The compression applied to base64-encoded tile data. See [`TileDataEncoding`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TileDataCompression {
+    /// Compressed using zlib.
+    Zlib,
+    /// Compressed using gzip.
+    Gzip,
+    /// Compressed using Zstandard. Can only be decoded or encoded with the `zstd` feature enabled.
+    Zstd,
+}
+
+impl TileDataEncoding {
+    pub(crate) fn from_attrs(encoding: Option<&str>, compression: Option<&str>) -> Result<Self> {
+        let invalid = || Error::InvalidEncodingFormat {
+            encoding: encoding.map(str::to_owned),
+            compression: compression.map(str::to_owned),
+        };
+        let parsed_compression = match compression {
+            None => None,
+            Some("zlib") => Some(TileDataCompression::Zlib),
+            Some("gzip") => Some(TileDataCompression::Gzip),
+            Some("zstd") => Some(TileDataCompression::Zstd),
+            Some(_) => return Err(invalid()),
+        };
+        match (encoding, parsed_compression) {
+            (Some("csv"), None) => Ok(Self::Csv),
+            (Some("base64"), compression) => Ok(Self::Base64(compression)),
+            (None, None) => Ok(Self::Xml),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// The value of the `encoding` attribute corresponding to this encoding, if any.
+    pub(crate) fn encoding_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Csv => Some("csv"),
+            Self::Base64(_) => Some("base64"),
+            Self::Xml => None,
+        }
+    }
+
+    /// The value of the `compression` attribute corresponding to this encoding, if any.
+    pub(crate) fn compression_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Base64(Some(TileDataCompression::Zlib)) => Some("zlib"),
+            Self::Base64(Some(TileDataCompression::Gzip)) => Some("gzip"),
+            Self::Base64(Some(TileDataCompression::Zstd)) => Some("zstd"),
+            _ => None,
+        }
+    }
+}
+
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct LayerTileData {
@@ -80,6 +155,22 @@ impl LayerTileData {
             })
         }
     }
+
+    /// Turns this tile back into a [`Gid`] plus its flipping bits, given the first GID of the
+    /// tileset it is in.
+    pub(crate) fn to_bits(&self, first_gid: Gid) -> u32 {
+        let mut bits = first_gid.0 + self.id;
+        if self.flip_h {
+            bits |= Self::FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if self.flip_v {
+            bits |= Self::FLIPPED_VERTICALLY_FLAG;
+        }
+        if self.flip_d {
+            bits |= Self::FLIPPED_DIAGONALLY_FLAG;
+        }
+        bits
+    }
 }
 
 /// The raw data of a [`TileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -99,7 +190,8 @@ impl TileLayerData {
         attrs: Vec<OwnedAttribute>,
         infinite: bool,
         tilesets: &[MapTilesetGid],
-    ) -> Result<(Self, Properties)> {
+        preserve_unknown: bool,
+    ) -> Result<(Self, Properties, Vec<(usize, XmlElement)>)> {
         let (width, height) = get_attrs!(
             for v in attrs {
                 "width" => width ?= v.parse::<u32>(),
@@ -109,6 +201,7 @@ impl TileLayerData {
         );
         let mut result = Self::Finite(Default::default());
         let mut properties = HashMap::new();
+        let mut unknown_elements = Vec::new();
         parse_tag!(parser, "layer", {
             "data" => |attrs| {
                 if infinite {
@@ -122,9 +215,85 @@ impl TileLayerData {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown_elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
+        });
+
+        Ok((result, properties, unknown_elements))
+    }
+
+    /// Writes this layer as a `<layer>` element, given the attributes common to all layers.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+        unknown_elements: &[(usize, XmlElement)],
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let (width, height) = match self {
+            Self::Finite(data) => (data.width(), data.height()),
+            Self::Infinite(_) => ctx.map_size,
+        };
+        attrs.push(("width", width.to_string()));
+        attrs.push(("height", height.to_string()));
+
+        writer.start_with_unknown("layer", &attrs, unknown_elements)?;
+        write_properties(writer, properties)?;
+        match self {
+            Self::Finite(data) => data.write_xml(writer, ctx)?,
+            Self::Infinite(data) => data.write_xml(writer, ctx)?,
+        }
+        writer.end()
+    }
+
+    /// Adds the members specific to tile layers to a JSON layer.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, layer: &mut JsonObject, ctx: &WriteContext) -> Result<()> {
+        let encoding = ctx.tile_encoding(match self {
+            Self::Finite(data) => data.encoding,
+            Self::Infinite(data) => data.encoding,
         });
+        for (name, value) in data_attrs(encoding) {
+            layer.insert(name.to_owned(), value.into());
+        }
+        match self {
+            Self::Finite(data) => data.write_json(layer, ctx),
+            Self::Infinite(data) => {
+                layer.insert("width".to_owned(), ctx.map_size.0.into());
+                layer.insert("height".to_owned(), ctx.map_size.1.into());
+                data.write_json(layer, ctx)
+            }
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        layer: &JsonObject,
+        infinite: bool,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<Self> {
+        let encoding = json_field_opt::<String>(layer, "encoding")?;
+        let compression = json_field_opt::<String>(layer, "compression")?;
+        // JSON files omit the encoding when using plain arrays and use an empty string when the
+        // data is not compressed.
+        let encoding = TileDataEncoding::from_attrs(
+            Some(encoding.as_deref().unwrap_or("csv")),
+            compression.as_deref().filter(|c| !c.is_empty()),
+        )?;
 
-        Ok((result, properties))
+        if infinite {
+            InfiniteTileLayerData::from_json(layer, encoding, tilesets).map(Self::Infinite)
+        } else {
+            match layer.get("data") {
+                Some(data) => FiniteTileLayerData::from_json(layer, data, encoding, tilesets)
+                    .map(Self::Finite),
+                None => Ok(Self::Finite(Default::default())),
+            }
+        }
     }
 }
 
@@ -174,6 +343,47 @@ impl<'map> TileLayer<'map> {
         }
     }
 
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions, in
+    /// the order Tiled draws them in.
+    ///
+    /// This depends on the map's [`orientation`](crate::Map::orientation):
+    /// - Orthogonal maps follow their [`render_order`](crate::Map::render_order).
+    /// - Isometric maps are drawn back to front, one diagonal of the grid at a time.
+    /// - Staggered and hexagonal maps are drawn row by row. If they are staggered along the X
+    ///   axis, the columns of each row that are shifted down are drawn after the other ones.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{LayerType, Loader};
+    /// #
+    /// # fn main() {
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_base64_zlib.tmx")
+    /// #     .unwrap();
+    /// # let layer = match map.get_layer(0).unwrap().layer_type() {
+    /// #     LayerType::Tiles(layer) => layer,
+    /// #     _ => panic!("Layer #0 is not a tile layer"),
+    /// # };
+    /// #
+    /// for (x, y, tile) in layer.tiles_in_render_order() {
+    ///     println!("At ({}, {}): tile {}", x, y, tile.id());
+    /// }
+    /// # }
+    /// ```
+    pub fn tiles_in_render_order(
+        &self,
+    ) -> impl Iterator<Item = (i32, i32, LayerTile<'map>)> + 'map {
+        // Only one of the two is present; This avoids boxing the iterator.
+        let (finite, infinite) = match self {
+            TileLayer::Finite(finite) => (Some(finite.tiles_in_render_order()), None),
+            TileLayer::Infinite(infinite) => (None, Some(infinite.tiles_in_render_order())),
+        };
+        finite
+            .into_iter()
+            .flatten()
+            .chain(infinite.into_iter().flatten())
+    }
+
     /// The width of this layer, if finite, or `None` if infinite.
     ///
     /// ## Example
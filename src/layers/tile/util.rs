@@ -1,56 +1,213 @@
-use std::{convert::TryInto, io::Read};
+use std::{
+    convert::TryInto,
+    io::{Read, Write},
+};
 
-use base64::Engine;
 use xml::reader::XmlEvent;
 
-use crate::{util::XmlEventResult, CsvDecodingError, Error, LayerTileData, MapTilesetGid, Result};
+use crate::{
+    util::{encode_base64, get_attrs, parse_base64, XmlEventResult},
+    write::{WriteContext, XmlWriter},
+    CsvDecodingError, Error, LayerTileData, MapTilesetGid, Result, TileDataCompression,
+    TileDataEncoding,
+};
 
 pub(crate) fn parse_data_line(
-    encoding: Option<String>,
-    compression: Option<String>,
+    encoding: TileDataEncoding,
     parser: &mut impl Iterator<Item = XmlEventResult>,
     tilesets: &[MapTilesetGid],
 ) -> Result<Vec<Option<LayerTileData>>> {
-    match (encoding.as_deref(), compression.as_deref()) {
-        (Some("csv"), None) => decode_csv(parser, tilesets),
+    match encoding {
+        TileDataEncoding::Csv => decode_csv(parser, tilesets),
 
-        (Some("base64"), None) => parse_base64(parser).map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("zlib")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))))
+        TileDataEncoding::Base64(compression) => parse_base64(parser)
+            .and_then(|data| decompress(&data, compression))
             .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("gzip")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        #[cfg(feature = "zstd")]
-        (Some("base64"), Some("zstd")) => parse_base64(parser)
-            .and_then(|data| process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])))
+
+        TileDataEncoding::Xml => decode_xml(parser, tilesets),
+    }
+}
+
+/// Decodes the `data` member of a JSON tile layer or chunk, which is either an array of GIDs or
+/// a base64 string.
+#[cfg(feature = "json")]
+pub(crate) fn parse_data_json(
+    encoding: TileDataEncoding,
+    data: &serde_json::Value,
+    tilesets: &[MapTilesetGid],
+) -> Result<Vec<Option<LayerTileData>>> {
+    use crate::util::decode_base64;
+    use serde_json::Value;
+    use std::convert::TryFrom;
+
+    match (encoding, data) {
+        (TileDataEncoding::Csv, Value::Array(gids)) => gids
+            .iter()
+            .map(|gid| {
+                gid.as_u64()
+                    .and_then(|bits| u32::try_from(bits).ok())
+                    .map(|bits| LayerTileData::from_bits(bits, tilesets))
+                    .ok_or(Error::InvalidTileFound)
+            })
+            .collect(),
+
+        (TileDataEncoding::Base64(compression), Value::String(data)) => decode_base64(data)
+            .and_then(|data| decompress(&data, compression))
             .map(|v| convert_to_tiles(&v, tilesets)),
 
-        _ => Err(Error::InvalidEncodingFormat {
-            encoding,
-            compression,
+        (encoding, _) => Err(Error::InvalidEncodingFormat {
+            encoding: encoding.encoding_name().map(str::to_owned),
+            compression: encoding.compression_name().map(str::to_owned),
         }),
     }
 }
 
-fn parse_base64(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<Vec<u8>> {
-    for next in parser {
-        match next.map_err(Error::XmlDecodingError)? {
-            XmlEvent::Characters(s) => {
-                return base64::engine::GeneralPurpose::new(
-                    &base64::alphabet::STANDARD,
-                    base64::engine::general_purpose::PAD,
-                )
-                .decode(s.trim().as_bytes())
-                .map_err(Error::Base64DecodingError);
-            }
-            XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
-                return Ok(Vec::new());
+/// Writes the contents of a `<data>` or `<chunk>` element holding the given tiles, which are
+/// arranged in rows of `width` tiles.
+pub(crate) fn write_data_line<'a>(
+    writer: &mut XmlWriter<impl Write>,
+    encoding: TileDataEncoding,
+    tiles: impl Iterator<Item = Option<&'a LayerTileData>>,
+    width: u32,
+    ctx: &WriteContext,
+) -> Result<()> {
+    let gids = tile_gids(tiles, ctx);
+    match encoding {
+        TileDataEncoding::Csv => {
+            let rows: Vec<String> = gids
+                .chunks(width.max(1) as usize)
+                .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                .collect();
+            writer.characters(&format!("\n{}\n", rows.join(",\n")))
+        }
+        TileDataEncoding::Base64(compression) => writer.characters(&format!(
+            "\n{}\n",
+            encode_gids(&gids, compression, ctx.compression_level)?
+        )),
+        TileDataEncoding::Xml => {
+            for gid in gids {
+                if gid == 0 {
+                    writer.empty("tile", &[])?;
+                } else {
+                    writer.empty("tile", &[("gid", gid.to_string())])?;
+                }
             }
-            _ => {}
+            Ok(())
         }
     }
-    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
+}
+
+/// Converts the given tiles into the `data` member of a JSON tile layer or chunk, which is either
+/// an array of GIDs or a base64 string.
+#[cfg(feature = "json")]
+pub(crate) fn data_json<'a>(
+    encoding: TileDataEncoding,
+    tiles: impl Iterator<Item = Option<&'a LayerTileData>>,
+    ctx: &WriteContext,
+) -> Result<serde_json::Value> {
+    let gids = tile_gids(tiles, ctx);
+    Ok(match encoding {
+        TileDataEncoding::Csv | TileDataEncoding::Xml => gids.into(),
+        TileDataEncoding::Base64(compression) => {
+            encode_gids(&gids, compression, ctx.compression_level)?.into()
+        }
+    })
+}
+
+fn tile_gids<'a>(
+    tiles: impl Iterator<Item = Option<&'a LayerTileData>>,
+    ctx: &WriteContext,
+) -> Vec<u32> {
+    tiles
+        .map(|tile| tile.map_or(0, |tile| tile.to_bits(ctx.first_gids[tile.tileset_index()])))
+        .collect()
+}
+
+/// Returns the `encoding` and `compression` attributes of the `<data>` element of a tile layer,
+/// which are also the members JSON tile layers use.
+pub(crate) fn data_attrs(encoding: TileDataEncoding) -> Vec<(&'static str, String)> {
+    let mut attrs = Vec::new();
+    if let Some(encoding_name) = encoding.encoding_name() {
+        attrs.push(("encoding", encoding_name.to_owned()));
+    }
+    if let Some(compression) = encoding.compression_name() {
+        attrs.push(("compression", compression.to_owned()));
+    }
+    attrs
+}
+
+fn encode_gids(
+    gids: &[u32],
+    compression: Option<TileDataCompression>,
+    level: i32,
+) -> Result<String> {
+    let data: Vec<u8> = gids.iter().flat_map(|gid| gid.to_le_bytes()).collect();
+    compress(&data, compression, level).map(|data| encode_base64(&data))
+}
+
+fn decompress(data: &[u8], compression: Option<TileDataCompression>) -> Result<Vec<u8>> {
+    match compression {
+        None => Ok(data.to_vec()),
+        Some(TileDataCompression::Zlib) => {
+            process_decoder(Ok(flate2::bufread::ZlibDecoder::new(data)))
+        }
+        Some(TileDataCompression::Gzip) => {
+            process_decoder(Ok(flate2::bufread::GzDecoder::new(data)))
+        }
+        #[cfg(feature = "zstd")]
+        Some(TileDataCompression::Zstd) => {
+            process_decoder(zstd::stream::read::Decoder::with_buffer(data))
+        }
+        #[cfg(not(feature = "zstd"))]
+        Some(TileDataCompression::Zstd) => Err(Error::InvalidEncodingFormat {
+            encoding: Some("base64".to_owned()),
+            compression: Some("zstd".to_owned()),
+        }),
+    }
+}
+
+/// Compresses `data` at the given level, where -1 stands for the default level of the
+/// compression method.
+fn compress(data: &[u8], compression: Option<TileDataCompression>, level: i32) -> Result<Vec<u8>> {
+    let flate2_level = match level {
+        -1 => flate2::Compression::default(),
+        level => flate2::Compression::new(level.clamp(0, 9) as u32),
+    };
+    match compression {
+        None => Ok(data.to_vec()),
+        Some(TileDataCompression::Zlib) => process_encoder(
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2_level),
+            data,
+            flate2::write::ZlibEncoder::finish,
+        ),
+        Some(TileDataCompression::Gzip) => process_encoder(
+            flate2::write::GzEncoder::new(Vec::new(), flate2_level),
+            data,
+            flate2::write::GzEncoder::finish,
+        ),
+        #[cfg(feature = "zstd")]
+        Some(TileDataCompression::Zstd) => {
+            // zstd uses 0 for its default level.
+            let level = if level == -1 { 0 } else { level };
+            zstd::stream::encode_all(data, level).map_err(Error::CompressingError)
+        }
+        #[cfg(not(feature = "zstd"))]
+        Some(TileDataCompression::Zstd) => Err(Error::InvalidEncodingFormat {
+            encoding: Some("base64".to_owned()),
+            compression: Some("zstd".to_owned()),
+        }),
+    }
+}
+
+fn process_encoder<E: Write>(
+    mut encoder: E,
+    data: &[u8],
+    finish: impl FnOnce(E) -> std::io::Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    encoder
+        .write_all(data)
+        .and_then(|_| finish(encoder))
+        .map_err(Error::CompressingError)
 }
 
 fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
@@ -92,6 +249,35 @@ fn decode_csv(
     Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
 }
 
+fn decode_xml(
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+    tilesets: &[MapTilesetGid],
+) -> Result<Vec<Option<LayerTileData>>> {
+    let mut tiles = Vec::new();
+    for next in parser {
+        match next.map_err(Error::XmlDecodingError)? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name == "tile" => {
+                let bits = get_attrs!(
+                    for v in attributes {
+                        Some("gid") => gid ?= v.parse::<u32>(),
+                    }
+                    gid
+                );
+                tiles.push(LayerTileData::from_bits(bits.unwrap_or(0), tilesets));
+            }
+            XmlEvent::EndElement { name, .. }
+                if name.local_name == "data" || name.local_name == "chunk" =>
+            {
+                return Ok(tiles);
+            }
+            _ => {}
+        }
+    }
+    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
+}
+
 fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Vec<Option<LayerTileData>> {
     data.chunks_exact(4)
         .map(|chunk| {
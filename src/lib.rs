@@ -14,14 +14,17 @@ mod loader;
 mod map;
 mod objects;
 mod parse;
+mod project;
 mod properties;
 mod reader;
 mod template;
 mod tile;
 mod tileset;
+mod unknown;
 mod util;
 #[cfg(feature = "world")]
 mod world;
+mod write;
 
 pub use animation::*;
 pub use cache::*;
@@ -31,10 +34,12 @@ pub use layers::*;
 pub use loader::*;
 pub use map::*;
 pub use objects::*;
+pub use project::*;
 pub use properties::*;
 pub use reader::*;
 pub use template::*;
 pub use tile::*;
 pub use tileset::*;
+pub use unknown::*;
 #[cfg(feature = "world")]
 pub use world::*;
@@ -1,8 +1,8 @@
-use std::path::Path;
+use std::{path::Path, sync::Arc};
 
 use crate::{
-    DefaultResourceCache, FilesystemResourceReader, Map, ResourceCache, ResourceReader, Result,
-    Tileset,
+    DefaultResourceCache, FilesystemResourceReader, Map, Project, ResourceCache, ResourceReader,
+    Result, Template, Tileset,
 };
 
 #[cfg(feature = "world")]
@@ -20,6 +20,9 @@ use crate::World;
 /// This type is used for loading operations because they require a [`ResourceCache`] for
 /// intermediate artifacts, so using a type for creation can ensure that the cache is reused if
 /// loading more than one object is required.
+///
+/// Optionally, it can also hold a [`Project`], which is used to fill in the values of the class
+/// and enum properties of the maps and tilesets it loads. See [`Loader::set_project`].
 #[derive(Debug, Clone, Default)]
 pub struct Loader<
     Cache: ResourceCache = DefaultResourceCache,
@@ -27,6 +30,8 @@ pub struct Loader<
 > {
     cache: Cache,
     reader: Reader,
+    project: Option<Project>,
+    preserve_unknown_xml: bool,
 }
 
 impl Loader {
@@ -36,6 +41,8 @@ impl Loader {
         Self {
             cache: DefaultResourceCache::new(),
             reader: FilesystemResourceReader::new(),
+            project: None,
+            preserve_unknown_xml: false,
         }
     }
 }
@@ -71,8 +78,8 @@ impl<Reader: ResourceReader> Loader<DefaultResourceCache, Reader> {
     /// let map = loader.load_tmx_map("/my-map.tmx")?;
     ///
     /// assert_eq!(
-    ///     map.tilesets()[0].image.as_ref().unwrap().source,
-    ///     Path::new("/tilesheet.png")
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.path(),
+    ///     Some(Path::new("/tilesheet.png"))
     /// );
     ///
     /// # Ok(())
@@ -82,6 +89,8 @@ impl<Reader: ResourceReader> Loader<DefaultResourceCache, Reader> {
         Self {
             cache: DefaultResourceCache::new(),
             reader,
+            project: None,
+            preserve_unknown_xml: false,
         }
     }
 }
@@ -151,15 +160,20 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// let map = loader.load_tmx_map("/my-map.tmx")?;
     ///
     /// assert_eq!(
-    ///     map.tilesets()[0].image.as_ref().unwrap().source,
-    ///     Path::new("/tilesheet.png")
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.path(),
+    ///     Some(Path::new("/tilesheet.png"))
     /// );
     ///
     /// # Ok(())
     /// # }
     /// ```
     pub fn with_cache_and_reader(cache: Cache, reader: Reader) -> Self {
-        Self { cache, reader }
+        Self {
+            cache,
+            reader,
+            project: None,
+            preserve_unknown_xml: false,
+        }
     }
 
     /// Parses a file hopefully containing a Tiled map and tries to parse it. All external files
@@ -169,7 +183,38 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     ///
     /// [internal loader cache]: Loader::cache()
     pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
-        crate::parse::xml::parse_map(path.as_ref(), &mut self.reader, &mut self.cache)
+        let mut map = crate::parse::xml::parse_map(
+            path.as_ref(),
+            self.preserve_unknown_xml,
+            &mut self.reader,
+            &mut self.cache,
+        )?;
+        if let Some(project) = &self.project {
+            map.apply_project(project);
+        }
+        Ok(map)
+    }
+
+    /// Parses a file hopefully containing a Tiled map in the JSON format and tries to parse it.
+    /// All external files will be loaded relative to the path given.
+    ///
+    /// The resulting [`Map`] is the same as the one [`Loader::load_tmx_map`] would return for the
+    /// equivalent TMX file. All intermediate objects such as map tilesets will be stored in the
+    /// [internal loader cache].
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "json")]
+    pub fn load_tmj_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let mut map = crate::parse::json::parse_map(
+            path.as_ref(),
+            self.preserve_unknown_xml,
+            &mut self.reader,
+            &mut self.cache,
+        )?;
+        if let Some(project) = &self.project {
+            map.apply_project(project);
+        }
+        Ok(map)
     }
 
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
@@ -182,7 +227,113 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut self.reader, &mut self.cache)
+        let mut tileset = crate::parse::xml::parse_tileset(
+            path.as_ref(),
+            self.preserve_unknown_xml,
+            &mut self.reader,
+            &mut self.cache,
+        )?;
+        if let Some(project) = &self.project {
+            tileset.apply_project(project);
+        }
+        Ok(tileset)
+    }
+
+    /// Parses a file hopefully containing a Tiled tileset in the JSON format and tries to parse
+    /// it. All external files will be loaded relative to the path given.
+    ///
+    /// This is the JSON counterpart of [`Loader::load_tsx_tileset`], and as such, the tileset
+    /// will **not** be cached inside the internal [`ResourceCache`] either.
+    #[cfg(feature = "json")]
+    pub fn load_tsj_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let mut tileset = crate::parse::json::parse_tileset(
+            path.as_ref(),
+            self.preserve_unknown_xml,
+            &mut self.reader,
+            &mut self.cache,
+        )?;
+        if let Some(project) = &self.project {
+            tileset.apply_project(project);
+        }
+        Ok(tileset)
+    }
+
+    /// Loads the map, tileset or template at the given path, such as one a file property points
+    /// to, telling which one it is from its file extension. Returns [`None`] if the extension isn't
+    /// one of a Tiled map, tileset or template, so that properties pointing to other kinds of
+    /// files can be skipped.
+    ///
+    /// Maps and tilesets are loaded the same way as with [`Loader::load_tmx_map`] and
+    /// [`Loader::load_tsx_tileset`], or their JSON counterparts. Templates are shared with the maps
+    /// that use them through the [internal loader cache], and like maps and tilesets, have their
+    /// properties filled in by the project given to [`Loader::set_project`], if any.
+    ///
+    /// Paths of file properties are relative to the file that contains them; Use
+    /// [`Map::resolve_file_property`] and its tileset and template counterparts to get the path to
+    /// give to this function.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_linked_file(&mut self, path: impl AsRef<Path>) -> Result<Option<LinkedFile>> {
+        let path = path.as_ref();
+        let file = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tmx") => LinkedFile::Map(self.load_tmx_map(path)?),
+            Some("tsx") => LinkedFile::Tileset(self.load_tsx_tileset(path)?),
+            Some("tx") => LinkedFile::Template(self.load_template(path)?),
+            #[cfg(feature = "json")]
+            Some("tmj") => LinkedFile::Map(self.load_tmj_map(path)?),
+            #[cfg(feature = "json")]
+            Some("tsj") => LinkedFile::Tileset(self.load_tsj_tileset(path)?),
+            #[cfg(feature = "json")]
+            Some("tj") => LinkedFile::Template(self.load_template(path)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(file))
+    }
+
+    /// Loads a template the same way as the ones used by maps, filling in its properties if a
+    /// project is set.
+    fn load_template(&mut self, path: &Path) -> Result<Arc<Template>> {
+        let template = crate::objects::load_template(
+            path,
+            self.preserve_unknown_xml,
+            &mut self.reader,
+            &mut self.cache,
+        )?;
+        Ok(match &self.project {
+            Some(project) => template.with_project(project),
+            None => template,
+        })
+    }
+
+    /// Parses a file hopefully containing a Tiled project (`.tiled-project`).
+    ///
+    /// The returned [`Project`] holds the custom property types defined in the project. It isn't
+    /// used by the loader unless given to [`Loader::set_project`].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// let mut loader = Loader::new();
+    /// let project = loader.load_project("assets/tiled_project.tiled-project")?;
+    /// loader.set_project(Some(project));
+    ///
+    /// // The map only sets the `health` member of its `stats` property, the rest come from the
+    /// // defaults of the `Stats` class.
+    /// let map = loader.load_tmx_map("assets/tiled_project_classes.tmx")?;
+    /// let stats = match map.properties.get("stats") {
+    ///     Some(PropertyValue::ClassValue { properties, .. }) => properties,
+    ///     _ => panic!("stats should be a class property"),
+    /// };
+    /// assert_eq!(stats.get("health"), Some(&PropertyValue::IntValue(50)));
+    /// assert_eq!(stats.get("enabled"), Some(&PropertyValue::BoolValue(true)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn load_project(&mut self, path: impl AsRef<Path>) -> Result<Project> {
+        crate::project::parse_project(path.as_ref(), &mut self.reader)
     }
 
     #[cfg(feature = "world")]
@@ -197,6 +348,40 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
         crate::world::parse_world(path.as_ref(), &mut self.reader)
     }
 
+    /// Returns the project used to fill in class and enum property values, if any.
+    pub fn project(&self) -> Option<&Project> {
+        self.project.as_ref()
+    }
+
+    /// Sets the project used to fill in class and enum property values.
+    ///
+    /// Tiled only saves the members of class properties that differ from their defaults. When a
+    /// project is set, the missing members of every class property in the maps and tilesets
+    /// loaded afterwards are filled in according to the classes defined in the project, as
+    /// described in [`Project::fill_class_defaults`]. The names of the values of their enum
+    /// properties are also decoded, as described in [`Project::decode_enums`].
+    pub fn set_project(&mut self, project: Option<Project>) {
+        self.project = project;
+    }
+
+    /// Returns true if unknown XML is recorded when loading files. See
+    /// [`Loader::set_preserve_unknown_xml`].
+    pub fn preserves_unknown_xml(&self) -> bool {
+        self.preserve_unknown_xml
+    }
+
+    /// Sets whether the attributes and elements of TMX and TSX files that aren't recognized by
+    /// this crate are recorded when loading them. Disabled by default.
+    ///
+    /// When enabled, they are stored in the [`UnknownXml`](crate::UnknownXml) of the [`Map`],
+    /// [`LayerData`](crate::LayerData), [`Tileset`], [`TileData`](crate::TileData) and
+    /// [`ObjectData`](crate::ObjectData) they belong to, and emitted again unchanged by the
+    /// writers, so that tools rewriting files don't lose any data. Note that tilesets and templates
+    /// already in the cache are not loaded again.
+    pub fn set_preserve_unknown_xml(&mut self, preserve: bool) {
+        self.preserve_unknown_xml = preserve;
+    }
+
     /// Returns a reference to the loader's internal [`ResourceCache`].
     pub fn cache(&self) -> &Cache {
         &self.cache
@@ -222,3 +407,17 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
         (self.cache, self.reader)
     }
 }
+
+/// A Tiled file loaded by [`Loader::load_linked_file`].
+// Values are only returned by the loader to be matched on right away, so there is no point in
+// boxing the bigger variants.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkedFile {
+    /// A map, loaded from a TMX or TMJ file.
+    Map(Map),
+    /// A tileset, loaded from a TSX or TSJ file.
+    Tileset(Tileset),
+    /// A template, loaded from a TX or TJ file.
+    Template(Arc<Template>),
+}
@@ -1,8 +1,10 @@
 //! Structures related to Tiled maps.
 
 use std::{
+    cmp::Ordering,
     collections::HashMap,
     fmt,
+    io::Write,
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
@@ -13,12 +15,31 @@ use xml::attribute::OwnedAttribute;
 use crate::{
     error::{Error, Result},
     layers::{LayerData, LayerTag},
-    properties::{parse_properties, Color, Properties},
+    properties::{parse_properties, write_properties, Color, Properties, PropertyValue},
     tileset::Tileset,
     util::{get_attrs, parse_tag, XmlEventResult},
-    EmbeddedParseResultType, Layer, ResourceCache, ResourceReader,
+    write::{relative_path, WriteContext, XmlWriter},
+    EffectiveLayer, Layer, ObjectData, Project, ResourceCache, ResourceReader, TileDataEncoding,
+    UnknownXml, XmlElement,
+};
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{
+        json_array, json_field, json_field_opt, json_object, json_parse, json_parse_opt, JsonObject,
+    },
+    properties::{parse_properties_json, write_properties_json},
+    write::{json_f32, write_json_document},
 };
 
+mod adjacency;
+pub use adjacency::*;
+mod coordinates;
+mod index;
+pub use index::LocatedObject;
+use index::MapIndex;
+mod pathfinding;
+pub use pathfinding::*;
+
 pub(crate) struct MapTilesetGid {
     pub first_gid: Gid,
     pub tileset: Arc<Tileset>,
@@ -28,10 +49,16 @@ pub(crate) struct MapTilesetGid {
 #[derive(PartialEq, Clone)]
 pub struct Map {
     version: String,
+    /// The version of Tiled that was used to save this map, if it was recorded.
+    pub tiled_version: Option<String>,
     /// The path first used in a [`ResourceReader`] to load this map.
     pub source: PathBuf,
     /// The way tiles are laid out in the map.
     pub orientation: Orientation,
+    /// The order in which the tiles of the map's tile layers are rendered. Only orthogonal maps
+    /// follow it. See
+    /// [`TileLayer::tiles_in_render_order`](crate::TileLayer::tiles_in_render_order).
+    pub render_order: RenderOrder,
     /// Width of the map, in tiles.
     ///
     /// ## Note
@@ -62,10 +89,21 @@ pub struct Map {
     pub stagger_axis: StaggerAxis,
     /// The stagger index of Hexagonal/Staggered map.
     pub stagger_index: StaggerIndex,
+    /// The X coordinate of the point at which layers are shown at their actual position,
+    /// regardless of their parallax factor. See
+    /// [`Layer::image_placements`](crate::Layer::image_placements).
+    pub parallax_origin_x: f32,
+    /// The Y coordinate of the point at which layers are shown at their actual position,
+    /// regardless of their parallax factor.
+    pub parallax_origin_y: f32,
     /// The tilesets present on this map.
     tilesets: Vec<Arc<Tileset>>,
+    /// The first GID of each tileset, in the same order as `tilesets`.
+    tileset_first_gids: Vec<Gid>,
     /// The layers present in this map.
     layers: Vec<LayerData>,
+    /// Where each layer and object is in `layers`, for looking them up by ID or path.
+    index: MapIndex,
     /// The custom properties of this map.
     pub properties: Properties,
     /// The background color of this map, if any.
@@ -73,25 +111,42 @@ pub struct Map {
     infinite: bool,
     /// The type of the map, which is arbitrary and set by the user.
     pub user_type: Option<String>,
+    /// The compression level to use when writing compressed tile layer data, where -1 stands for
+    /// the default level of the compression method. Levels go from 0 to 9 for zlib and gzip, and
+    /// from 1 to 22 for zstd.
+    pub compression_level: i32,
+    next_layer_id: u32,
+    next_object_id: u32,
+    /// The attributes and elements of the `<map>` element that aren't recognized, if they were
+    /// preserved when loading it.
+    pub unknown: UnknownXml,
 }
 
 impl fmt::Debug for Map {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Map")
             .field("version", &self.version)
+            .field("tiled_version", &self.tiled_version)
             .field("orientation", &self.orientation)
+            .field("render_order", &self.render_order)
             .field("width", &self.width)
             .field("height", &self.height)
             .field("tile_width", &self.tile_width)
             .field("tile_height", &self.tile_height)
             .field("stagger_axis", &self.stagger_axis)
             .field("stagger_index", &self.stagger_index)
+            .field("parallax_origin_x", &self.parallax_origin_x)
+            .field("parallax_origin_y", &self.parallax_origin_y)
             .field("tilesets", &format!("{} tilesets", self.tilesets.len()))
             .field("layers", &format!("{} layers", self.layers.len()))
             .field("properties", &self.properties)
             .field("background_color", &self.background_color)
             .field("infinite", &self.infinite)
             .field("user_type", &self.user_type)
+            .field("compression_level", &self.compression_level)
+            .field("next_layer_id", &self.next_layer_id)
+            .field("next_object_id", &self.next_object_id)
+            .field("unknown", &self.unknown)
             .finish()
     }
 }
@@ -109,6 +164,163 @@ impl Map {
     pub fn infinite(&self) -> bool {
         self.infinite
     }
+
+    /// The ID that the next layer added to this map should get. Equivalent to the map file's
+    /// `nextlayerid` attribute.
+    ///
+    /// This is never lower than one past the greatest layer ID in the map, even if the file says
+    /// otherwise or doesn't specify it at all.
+    pub fn next_layer_id(&self) -> u32 {
+        self.next_layer_id
+    }
+
+    /// The ID that the next object added to this map should get. Equivalent to the map file's
+    /// `nextobjectid` attribute.
+    ///
+    /// This is never lower than one past the greatest object ID in the map, even if the file says
+    /// otherwise or doesn't specify it at all.
+    pub fn next_object_id(&self) -> u32 {
+        self.next_object_id
+    }
+
+    /// Reserves a new layer ID that isn't used within this map and returns it.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// assert_eq!(map.allocate_layer_id(), 3);
+    /// assert_eq!(map.allocate_layer_id(), 4);
+    /// assert_eq!(map.next_layer_id(), 5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn allocate_layer_id(&mut self) -> u32 {
+        let id = self.next_layer_id;
+        self.next_layer_id += 1;
+        id
+    }
+
+    /// Reserves a new object ID that isn't used within this map and returns it.
+    pub fn allocate_object_id(&mut self) -> u32 {
+        let id = self.next_object_id;
+        self.next_object_id += 1;
+        id
+    }
+
+    /// Inserts a top-level layer at position `index`, shifting all the layers after it.
+    ///
+    /// If the layer's ID is 0, it is given a new one from [`Map::allocate_layer_id`]. Since the
+    /// tiles in a layer refer to the tilesets of the map they were loaded with, the layer should
+    /// come from this map or from one with the same tilesets.
+    ///
+    /// ## Panics
+    /// Panics if `index` is greater than the number of top-level layers.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    ///
+    /// // Move the bottom layer to the top.
+    /// let layer = map.remove_layer(0).unwrap();
+    /// map.insert_layer(1, layer);
+    /// assert_eq!(map.get_layer(1).unwrap().name, "Tile Layer 1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_layer(&mut self, index: usize, mut layer: LayerData) {
+        if layer.id() == 0 {
+            layer.set_id(self.allocate_layer_id());
+        }
+        self.layers.insert(index, layer);
+        self.update_layers();
+    }
+
+    /// Removes the top-level layer at position `index` and returns it, shifting all the layers
+    /// after it. Returns [`None`] if there is no such layer.
+    pub fn remove_layer(&mut self, index: usize) -> Option<LayerData> {
+        if index >= self.layers.len() {
+            return None;
+        }
+        let layer = self.layers.remove(index);
+        self.update_layers();
+        Some(layer)
+    }
+
+    /// Adds an object to the end of the object layer with the given ID, which may be within a
+    /// group layer, and returns the object's ID.
+    ///
+    /// If the object's ID is 0, it is given a new one from [`Map::allocate_object_id`]. Returns
+    /// [`None`] and drops the object if there is no object layer with the given ID. Like with
+    /// [`Map::insert_layer`], tile objects should come from a map with the same tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, ObjectData};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    ///
+    /// let mut object = ObjectData::default();
+    /// object.name = "spawn".to_owned();
+    /// let id = map.push_object(2, object).unwrap();
+    /// assert_eq!(id, 4);
+    /// assert_eq!(map.object_by_id(id).unwrap().object.name, "spawn");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn push_object(&mut self, layer_id: u32, mut object: ObjectData) -> Option<u32> {
+        let position = self.index.layer_position(layer_id)?;
+        let (first, rest) = position.split_first()?;
+        let mut data = self.layers.get_mut(*first)?;
+        for &index in rest {
+            data = data.sublayers_mut().get_mut(index)?;
+        }
+        let objects = data.object_layer_data_mut()?;
+
+        if object.id() == 0 {
+            object.set_id(self.next_object_id);
+        }
+        let id = object.id();
+        objects.push_object(object);
+        self.update_layers();
+        Some(id)
+    }
+
+    /// Brings the index of layers and objects and the next layer and object IDs up to date after
+    /// the layers have been modified.
+    fn update_layers(&mut self) {
+        self.index = MapIndex::new(&self.layers);
+        let (next_layer_id, next_object_id) = next_ids(
+            &self.layers,
+            Some(self.next_layer_id),
+            Some(self.next_object_id),
+        );
+        self.next_layer_id = next_layer_id;
+        self.next_object_id = next_object_id;
+    }
+
+    /// Returns the path a file property of this map or of anything within it points to, relative
+    /// to the same directory as [`Map::source`], or [`None`] if the value isn't a file property or
+    /// is unset.
+    ///
+    /// The path can be given to [`Loader::load_linked_file`](crate::Loader::load_linked_file) to
+    /// load the map, tileset or template it points to.
+    ///
+    /// ## Note
+    /// Properties of external tilesets and of the tiles within them are relative to the tileset
+    /// instead. Use [`Tileset::resolve_file_property`] for them. Likewise, properties that objects
+    /// inherit from their template are relative to the template, which
+    /// [`Object::resolve_file_property`](crate::Object::resolve_file_property) takes into account.
+    pub fn resolve_file_property(&self, value: &PropertyValue) -> Option<PathBuf> {
+        value.file_path(&self.source)
+    }
 }
 
 impl Map {
@@ -118,6 +330,30 @@ impl Map {
         self.tilesets.as_ref()
     }
 
+    /// Get the first GID of the tileset at the given index.
+    pub(crate) fn tileset_first_gid(&self, index: usize) -> Gid {
+        self.tileset_first_gids[index]
+    }
+
+    /// Compares two tile positions by the order Tiled draws them in for this map's orientation.
+    pub(crate) fn compare_render_positions(&self, a: (i32, i32), b: (i32, i32)) -> Ordering {
+        match self.orientation {
+            Orientation::Orthogonal => self.render_order.compare(a, b),
+            // Back to front: each diagonal of the grid is a row on screen, drawn left to right.
+            Orientation::Isometric => (a.0 + a.1, a.0).cmp(&(b.0 + b.1, b.0)),
+            Orientation::Staggered | Orientation::Hexagonal => match self.stagger_axis {
+                StaggerAxis::Y => (a.1, a.0).cmp(&(b.1, b.0)),
+                // Within a row, the columns shifted up are drawn before the ones shifted down.
+                StaggerAxis::X => {
+                    let shifted_down = |x: i32| {
+                        (x.rem_euclid(2) == 1) == (self.stagger_index == StaggerIndex::Odd)
+                    };
+                    (a.1, shifted_down(a.0), a.0).cmp(&(b.1, shifted_down(b.0), b.0))
+                }
+            },
+        }
+    }
+
     /// Get an iterator over top-level layers in the map in ascending order of their layer index.
     ///
     /// Note: "top-level" means that if a map has layers of `LayerDataType::Group` type, you
@@ -156,6 +392,52 @@ impl Map {
     pub fn get_layer(&self, index: usize) -> Option<Layer> {
         self.layers.get(index).map(|data| Layer::new(self, data))
     }
+
+    /// Returns an iterator over all the layers of the map that aren't group layers, including the
+    /// ones within group layers, in the order they are drawn in. Each layer comes with its offset,
+    /// opacity, visibility, tint color and parallax factor combined with those of the group layers
+    /// it is in.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    ///
+    /// let names: Vec<&str> = map
+    ///     .leaf_layers()
+    ///     .map(|leaf| leaf.layer.name.as_str())
+    ///     .collect();
+    /// assert_eq!(names, vec!["tile-1", "tile-2", "tile-3"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn leaf_layers(&self) -> impl ExactSizeIterator<Item = EffectiveLayer> {
+        let mut leaves = Vec::new();
+        for layer in self.layers() {
+            EffectiveLayer::collect(layer, None, &mut leaves);
+        }
+        leaves.into_iter()
+    }
+
+    /// Fills in the default members of every class property and decodes every enum property in
+    /// the map, its tilesets and its layers.
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        project.apply(&mut self.properties);
+        for tileset in &mut self.tilesets {
+            // Tilesets are shared with the loader's cache, so they are only replaced by a copy if
+            // anything actually changes.
+            let mut filled = Tileset::clone(tileset);
+            filled.apply_project(project);
+            if filled != **tileset {
+                *tileset = Arc::new(filled);
+            }
+        }
+        for layer in &mut self.layers {
+            layer.apply_project(project);
+        }
+    }
 }
 
 impl Map {
@@ -163,12 +445,29 @@ impl Map {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         map_path: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Map> {
         let (
-            (c, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length),
+            (
+                c,
+                infinite,
+                user_type,
+                user_class,
+                stagger_axis,
+                stagger_index,
+                hex_side_length,
+                render_order,
+                tiled_version,
+                compression_level,
+                next_layer_id,
+                next_object_id,
+                parallax_origin_x,
+                parallax_origin_y,
+            ),
             (v, o, w, h, tw, th),
+            unknown_attrs,
         ) = get_attrs!(
             for v in attrs {
                 Some("backgroundcolor") => colour ?= v.parse(),
@@ -178,6 +477,13 @@ impl Map {
                 Some("staggeraxis") => stagger_axis ?= v.parse::<StaggerAxis>(),
                 Some("staggerindex") => stagger_index ?= v.parse::<StaggerIndex>(),
                 Some("hexsidelength") => hex_side_length ?= v.parse(),
+                Some("renderorder") => render_order ?= v.parse::<RenderOrder>(),
+                Some("tiledversion") => tiled_version = v,
+                Some("compressionlevel") => compression_level ?= v.parse::<i32>(),
+                Some("nextlayerid") => next_layer_id ?= v.parse::<u32>(),
+                Some("nextobjectid") => next_object_id ?= v.parse::<u32>(),
+                Some("parallaxoriginx") => parallax_origin_x ?= v.parse::<f32>(),
+                Some("parallaxoriginy") => parallax_origin_y ?= v.parse::<f32>(),
                 "version" => version = v,
                 "orientation" => orientation ?= v.parse::<Orientation>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -185,7 +491,8 @@ impl Map {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length), (version, orientation, width, height, tile_width, tile_height))
+            unknown => unknown_attrs,
+            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length, render_order, tiled_version, compression_level, next_layer_id, next_object_id, parallax_origin_x, parallax_origin_y), (version, orientation, width, height, tile_width, tile_height), unknown_attrs)
         );
 
         let infinite = infinite.unwrap_or(false);
@@ -199,26 +506,12 @@ impl Map {
         let mut layers = Vec::new();
         let mut properties = HashMap::new();
         let mut tilesets = Vec::new();
+        let mut unknown = UnknownXml::with_attributes(unknown_attrs, preserve_unknown);
 
         parse_tag!(parser, "map", {
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  reader, cache)?;
-                match res.result_type {
-                    EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        let tileset = if let Some(ts) = cache.get_tileset(&tileset_path) {
-                            ts
-                        } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path,  reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
-                        };
-
-                        tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset});
-                    }
-                    EmbeddedParseResultType::Embedded { tileset } => {
-                        tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset: Arc::new(tileset)});
-                    },
-                };
+                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path, preserve_unknown, reader, cache)?;
+                tilesets.push(res.into_map_tileset(preserve_unknown, reader, cache)?);
                 Ok(())
             },
             "layer" => |attrs| {
@@ -230,6 +523,7 @@ impl Map {
                     map_path,
                     &tilesets,
                     None,
+                    preserve_unknown,
                     reader,
                     cache
                 )?);
@@ -244,6 +538,7 @@ impl Map {
                     map_path,
                     &tilesets,
                     None,
+                    preserve_unknown,
                     reader,
                     cache
                 )?);
@@ -258,6 +553,7 @@ impl Map {
                     map_path,
                     &tilesets,
                     None,
+                    preserve_unknown,
                     reader,
                     cache
                 )?);
@@ -272,6 +568,7 @@ impl Map {
                     map_path,
                     &tilesets,
                     None,
+                    preserve_unknown,
                     reader,
                     cache
                 )?);
@@ -281,15 +578,25 @@ impl Map {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown.elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
 
-        // We do not need first GIDs any more
-        let tilesets = tilesets.into_iter().map(|ts| ts.tileset).collect();
+        let (tileset_first_gids, tilesets) = tilesets
+            .into_iter()
+            .map(|ts| (ts.first_gid, ts.tileset))
+            .unzip();
+        let (next_layer_id, next_object_id) = next_ids(&layers, next_layer_id, next_object_id);
 
         Ok(Map {
             version: v,
+            tiled_version,
             source: map_path.to_owned(),
             orientation: o,
+            render_order: render_order.unwrap_or_default(),
             width: w,
             height: h,
             tile_width: tw,
@@ -297,16 +604,427 @@ impl Map {
             hex_side_length,
             stagger_axis,
             stagger_index,
+            parallax_origin_x: parallax_origin_x.unwrap_or(0.0),
+            parallax_origin_y: parallax_origin_y.unwrap_or(0.0),
             tilesets,
+            tileset_first_gids,
+            index: MapIndex::new(&layers),
             layers,
             properties,
             background_color: c,
             infinite,
             user_type,
+            compression_level: compression_level.unwrap_or(-1),
+            next_layer_id,
+            next_object_id,
+            unknown,
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl Map {
+    pub(crate) fn parse_json(
+        map: &JsonObject,
+        map_path: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Map> {
+        // Older versions of Tiled saved the format version as a number.
+        let version = match map.get("version") {
+            Some(serde_json::Value::String(version)) => version.clone(),
+            Some(serde_json::Value::Number(version)) => version.to_string(),
+            Some(_) => {
+                return Err(Error::MalformedAttributes(
+                    "Error parsing attribute 'version'".to_string(),
+                ))
+            }
+            None => {
+                return Err(Error::MalformedAttributes(
+                    "Missing attribute: version".to_string(),
+                ))
+            }
+        };
+        let infinite = json_field_opt(map, "infinite")?.unwrap_or(false);
+
+        let mut tilesets = Vec::new();
+        for tileset in json_array(map, "tilesets")? {
+            let res = Tileset::parse_json_in_map(
+                json_object(tileset)?,
+                map_path,
+                preserve_unknown,
+                reader,
+                cache,
+            )?;
+            tilesets.push(res.into_map_tileset(preserve_unknown, reader, cache)?);
+        }
+
+        let mut layers = Vec::new();
+        for layer in json_array(map, "layers")? {
+            layers.push(LayerData::from_json(
+                json_object(layer)?,
+                infinite,
+                map_path,
+                &tilesets,
+                None,
+                preserve_unknown,
+                reader,
+                cache,
+            )?);
+        }
+
+        let (tileset_first_gids, tilesets) = tilesets
+            .into_iter()
+            .map(|ts| (ts.first_gid, ts.tileset))
+            .unzip();
+        let (next_layer_id, next_object_id) = next_ids(
+            &layers,
+            json_field_opt(map, "nextlayerid")?,
+            json_field_opt(map, "nextobjectid")?,
+        );
+
+        Ok(Map {
+            version,
+            tiled_version: json_field_opt(map, "tiledversion")?,
+            source: map_path.to_owned(),
+            orientation: json_parse(map, "orientation")?,
+            render_order: json_parse_opt(map, "renderorder")?.unwrap_or_default(),
+            width: json_field(map, "width")?,
+            height: json_field(map, "height")?,
+            tile_width: json_field(map, "tilewidth")?,
+            tile_height: json_field(map, "tileheight")?,
+            hex_side_length: json_field_opt(map, "hexsidelength")?,
+            stagger_axis: json_parse_opt(map, "staggeraxis")?.unwrap_or_default(),
+            stagger_index: json_parse_opt(map, "staggerindex")?.unwrap_or_default(),
+            parallax_origin_x: json_field_opt(map, "parallaxoriginx")?.unwrap_or(0.0),
+            parallax_origin_y: json_field_opt(map, "parallaxoriginy")?.unwrap_or(0.0),
+            tilesets,
+            tileset_first_gids,
+            index: MapIndex::new(&layers),
+            layers,
+            properties: parse_properties_json(map)?,
+            background_color: json_field_opt(map, "backgroundcolor")?,
+            infinite,
+            user_type: json_field_opt(map, "class")?,
+            compression_level: json_field_opt(map, "compressionlevel")?.unwrap_or(-1),
+            next_layer_id,
+            next_object_id,
+            unknown: UnknownXml::default(),
         })
     }
 }
 
+impl Map {
+    /// Writes this map to `writer` in the TMX format.
+    ///
+    /// Paths to external tilesets, templates and images are written relative to the directory
+    /// of [`Self::source`]. Tilesets that were embedded in the map (those with the same source as
+    /// the map) are written embedded as well, while external tilesets and templates are only
+    /// referenced and not written themselves.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let mut tmx = Vec::new();
+    /// map.write_tmx(&mut tmx)?;
+    /// assert!(String::from_utf8(tmx).unwrap().contains(r#"compression="zlib""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tmx(&self, writer: impl Write) -> Result<()> {
+        self.write_xml(writer, None)
+    }
+
+    /// Writes this map to `writer` in the TMX format like [`Map::write_tmx`], but with the data
+    /// of every tile layer encoded with `encoding` instead of the one it was loaded with.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, TileDataEncoding};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let mut tmx = Vec::new();
+    /// map.write_tmx_with_encoding(&mut tmx, TileDataEncoding::Csv)?;
+    /// assert!(String::from_utf8(tmx).unwrap().contains(r#"encoding="csv""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tmx_with_encoding(
+        &self,
+        writer: impl Write,
+        encoding: TileDataEncoding,
+    ) -> Result<()> {
+        self.write_xml(writer, Some(encoding))
+    }
+
+    fn write_xml(&self, writer: impl Write, tile_encoding: Option<TileDataEncoding>) -> Result<()> {
+        let mut writer = XmlWriter::new(writer);
+        let ctx = WriteContext {
+            base_path: self.source.parent().ok_or(Error::PathIsNotFile)?,
+            first_gids: &self.tileset_first_gids,
+            map_size: (self.width, self.height),
+            compression_level: self.compression_level,
+            tile_encoding,
+        };
+
+        let mut attrs = vec![("version", self.version.clone())];
+        if let Some(tiled_version) = &self.tiled_version {
+            attrs.push(("tiledversion", tiled_version.clone()));
+        }
+        attrs.extend([
+            ("orientation", self.orientation.to_string()),
+            ("renderorder", self.render_order.to_string()),
+        ]);
+        if self.compression_level != -1 {
+            attrs.push(("compressionlevel", self.compression_level.to_string()));
+        }
+        attrs.extend([
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("tilewidth", self.tile_width.to_string()),
+            ("tileheight", self.tile_height.to_string()),
+        ]);
+        if let Some(hex_side_length) = self.hex_side_length {
+            attrs.push(("hexsidelength", hex_side_length.to_string()));
+        }
+        // Tiled only writes these for the orientations that use them.
+        let staggered = matches!(
+            self.orientation,
+            Orientation::Staggered | Orientation::Hexagonal
+        );
+        if staggered || self.stagger_axis != StaggerAxis::default() {
+            attrs.push(("staggeraxis", self.stagger_axis.to_string()));
+        }
+        if staggered || self.stagger_index != StaggerIndex::default() {
+            attrs.push(("staggerindex", self.stagger_index.to_string()));
+        }
+        if self.parallax_origin_x != 0.0 {
+            attrs.push(("parallaxoriginx", self.parallax_origin_x.to_string()));
+        }
+        if self.parallax_origin_y != 0.0 {
+            attrs.push(("parallaxoriginy", self.parallax_origin_y.to_string()));
+        }
+        attrs.push(("infinite", if self.infinite { "1" } else { "0" }.to_owned()));
+        if let Some(background_color) = self.background_color {
+            attrs.push(("backgroundcolor", background_color.to_string()));
+        }
+        attrs.push(("nextlayerid", self.next_layer_id.to_string()));
+        attrs.push(("nextobjectid", self.next_object_id.to_string()));
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        self.unknown.extend_attributes(&mut attrs);
+
+        writer.start_with_unknown("map", &attrs, &self.unknown.elements)?;
+        write_properties(&mut writer, &self.properties)?;
+        for (tileset, first_gid) in self.tilesets.iter().zip(&self.tileset_first_gids) {
+            if tileset.source == self.source {
+                tileset.write_xml(&mut writer, Some(*first_gid), &ctx)?;
+            } else {
+                writer.empty(
+                    "tileset",
+                    &[
+                        ("firstgid", first_gid.0.to_string()),
+                        ("source", relative_path(ctx.base_path, &tileset.source)),
+                    ],
+                )?;
+            }
+        }
+        for layer in &self.layers {
+            layer.write_xml(&mut writer, &ctx)?;
+        }
+        writer.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Map {
+    /// Writes this map to `writer` in the Tiled JSON format (TMJ).
+    ///
+    /// This follows the same rules as [`Map::write_tmx`]: paths are written relative to the
+    /// directory of [`Self::source`] and only tilesets that were embedded in the map are written
+    /// embedded. Tile layer data is written with the [`TileDataEncoding`](crate::TileDataEncoding)
+    /// of each layer, with CSV data becoming a plain array of GIDs. Use
+    /// [`Map::write_tmj_with_encoding`] to write all of it with another encoding.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let mut tmj = Vec::new();
+    /// map.write_tmj(&mut tmj)?;
+    /// assert!(String::from_utf8(tmj).unwrap().contains(r#""compression": "zlib""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tmj(&self, writer: impl Write) -> Result<()> {
+        self.write_json(writer, None)
+    }
+
+    /// Writes this map to `writer` in the Tiled JSON format like [`Map::write_tmj`], but with the
+    /// data of every tile layer encoded with `encoding` instead of the one it was loaded with.
+    ///
+    /// [`TileDataEncoding::Xml`] has no JSON equivalent, so it results in plain arrays of GIDs
+    /// like [`TileDataEncoding::Csv`] does.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, TileDataCompression, TileDataEncoding};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    ///
+    /// let mut tmj = Vec::new();
+    /// map.write_tmj_with_encoding(
+    ///     &mut tmj,
+    ///     TileDataEncoding::Base64(Some(TileDataCompression::Gzip)),
+    /// )?;
+    /// assert!(String::from_utf8(tmj).unwrap().contains(r#""compression": "gzip""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tmj_with_encoding(
+        &self,
+        writer: impl Write,
+        encoding: TileDataEncoding,
+    ) -> Result<()> {
+        self.write_json(writer, Some(encoding))
+    }
+
+    fn write_json(
+        &self,
+        writer: impl Write,
+        tile_encoding: Option<TileDataEncoding>,
+    ) -> Result<()> {
+        let ctx = WriteContext {
+            base_path: self.source.parent().ok_or(Error::PathIsNotFile)?,
+            first_gids: &self.tileset_first_gids,
+            map_size: (self.width, self.height),
+            compression_level: self.compression_level,
+            tile_encoding,
+        };
+
+        let mut map = JsonObject::new();
+        map.insert("type".to_owned(), "map".into());
+        map.insert("version".to_owned(), self.version.clone().into());
+        if let Some(tiled_version) = &self.tiled_version {
+            map.insert("tiledversion".to_owned(), tiled_version.clone().into());
+        }
+        map.insert(
+            "orientation".to_owned(),
+            self.orientation.to_string().into(),
+        );
+        map.insert(
+            "renderorder".to_owned(),
+            self.render_order.to_string().into(),
+        );
+        map.insert("compressionlevel".to_owned(), self.compression_level.into());
+        map.insert("width".to_owned(), self.width.into());
+        map.insert("height".to_owned(), self.height.into());
+        map.insert("tilewidth".to_owned(), self.tile_width.into());
+        map.insert("tileheight".to_owned(), self.tile_height.into());
+        if let Some(hex_side_length) = self.hex_side_length {
+            map.insert("hexsidelength".to_owned(), hex_side_length.into());
+        }
+        let staggered = matches!(
+            self.orientation,
+            Orientation::Staggered | Orientation::Hexagonal
+        );
+        if staggered || self.stagger_axis != StaggerAxis::default() {
+            map.insert(
+                "staggeraxis".to_owned(),
+                self.stagger_axis.to_string().into(),
+            );
+        }
+        if staggered || self.stagger_index != StaggerIndex::default() {
+            map.insert(
+                "staggerindex".to_owned(),
+                self.stagger_index.to_string().into(),
+            );
+        }
+        if self.parallax_origin_x != 0.0 {
+            map.insert(
+                "parallaxoriginx".to_owned(),
+                json_f32(self.parallax_origin_x),
+            );
+        }
+        if self.parallax_origin_y != 0.0 {
+            map.insert(
+                "parallaxoriginy".to_owned(),
+                json_f32(self.parallax_origin_y),
+            );
+        }
+        map.insert("infinite".to_owned(), self.infinite.into());
+        map.insert("nextlayerid".to_owned(), self.next_layer_id.into());
+        map.insert("nextobjectid".to_owned(), self.next_object_id.into());
+        if let Some(background_color) = self.background_color {
+            map.insert(
+                "backgroundcolor".to_owned(),
+                background_color.to_string().into(),
+            );
+        }
+        if let Some(user_type) = &self.user_type {
+            map.insert("class".to_owned(), user_type.clone().into());
+        }
+        write_properties_json(&mut map, &self.properties);
+
+        let tilesets = self
+            .tilesets
+            .iter()
+            .zip(&self.tileset_first_gids)
+            .map(|(tileset, first_gid)| {
+                if tileset.source == self.source {
+                    tileset
+                        .to_json(Some(*first_gid), &ctx)
+                        .map(serde_json::Value::Object)
+                } else {
+                    Ok(serde_json::json!({
+                        "firstgid": first_gid.0,
+                        "source": relative_path(ctx.base_path, &tileset.source),
+                    }))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        map.insert("tilesets".to_owned(), tilesets.into());
+
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.to_json(&ctx).map(serde_json::Value::Object))
+            .collect::<Result<Vec<_>>>()?;
+        map.insert("layers".to_owned(), layers.into());
+
+        write_json_document(writer, map)
+    }
+}
+
+/// Works out the next free layer and object IDs of a map from the ones stored in its file, making
+/// sure that they don't collide with any ID already in use.
+fn next_ids(
+    layers: &[LayerData],
+    next_layer_id: Option<u32>,
+    next_object_id: Option<u32>,
+) -> (u32, u32) {
+    let (max_layer_id, max_object_id) = layers
+        .iter()
+        .map(LayerData::max_ids)
+        .fold((0, 0), |(l1, o1), (l2, o2)| (l1.max(l2), o1.max(o2)));
+    (
+        next_layer_id.unwrap_or(1).max(max_layer_id + 1),
+        next_object_id.unwrap_or(1).max(max_object_id + 1),
+    )
+}
+
 // Specifies whether the odd or even rows/columns are shifted half a tile
 // right/down. Only applies to Staggered and Hexagonal map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
@@ -334,6 +1052,15 @@ impl std::fmt::Display for StaggerIndexError {
     }
 }
 
+impl fmt::Display for StaggerIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaggerIndex::Even => write!(f, "even"),
+            StaggerIndex::Odd => write!(f, "odd"),
+        }
+    }
+}
+
 impl FromStr for StaggerIndex {
     type Err = StaggerIndexError;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -374,6 +1101,15 @@ impl std::fmt::Display for StaggerAxisError {
     }
 }
 
+impl fmt::Display for StaggerAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaggerAxis::X => write!(f, "x"),
+            StaggerAxis::Y => write!(f, "y"),
+        }
+    }
+}
+
 impl FromStr for StaggerAxis {
     type Err = StaggerAxisError;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -440,6 +1176,98 @@ impl fmt::Display for Orientation {
     }
 }
 
+/// The order in which the tiles of a map are rendered, named after the direction tiles are drawn
+/// in within a row followed by the direction the rows are drawn in.
+///
+/// Only affects the order of drawing; Tiles are always placed at the same position no matter the
+/// order. Tiled only uses it for orthogonal maps, as other orientations must be drawn back to
+/// front for their tiles to overlap correctly. Also see [`TileLayer::tiles_in_render_order`](crate::TileLayer::tiles_in_render_order).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum RenderOrder {
+    /// Rows are drawn from top to bottom, and the tiles of each row from left to right.
+    #[default]
+    RightDown,
+    /// Rows are drawn from bottom to top, and the tiles of each row from left to right.
+    RightUp,
+    /// Rows are drawn from top to bottom, and the tiles of each row from right to left.
+    LeftDown,
+    /// Rows are drawn from bottom to top, and the tiles of each row from right to left.
+    LeftUp,
+}
+
+impl RenderOrder {
+    /// Whether the tiles of each row are drawn from left to right.
+    pub(crate) fn goes_right(self) -> bool {
+        matches!(self, RenderOrder::RightDown | RenderOrder::RightUp)
+    }
+
+    /// Whether the rows are drawn from top to bottom.
+    pub(crate) fn goes_down(self) -> bool {
+        matches!(self, RenderOrder::RightDown | RenderOrder::LeftDown)
+    }
+
+    /// Compares two tile positions by the order they are rendered in.
+    pub(crate) fn compare(self, a: (i32, i32), b: (i32, i32)) -> std::cmp::Ordering {
+        let rows = if self.goes_down() {
+            a.1.cmp(&b.1)
+        } else {
+            b.1.cmp(&a.1)
+        };
+        let columns = if self.goes_right() {
+            a.0.cmp(&b.0)
+        } else {
+            b.0.cmp(&a.0)
+        };
+        rows.then(columns)
+    }
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`RenderOrder`] that is not valid.
+pub struct RenderOrderError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for RenderOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse render order, valid options are `right-down`, `right-up`, \
+        `left-down` and `left-up` but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for RenderOrderError {}
+
+impl FromStr for RenderOrder {
+    type Err = RenderOrderError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "right-down" => Ok(RenderOrder::RightDown),
+            "right-up" => Ok(RenderOrder::RightUp),
+            "left-down" => Ok(RenderOrder::LeftDown),
+            "left-up" => Ok(RenderOrder::LeftUp),
+            _ => Err(RenderOrderError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RenderOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderOrder::RightDown => write!(f, "right-down"),
+            RenderOrder::RightUp => write!(f, "right-up"),
+            RenderOrder::LeftDown => write!(f, "left-down"),
+            RenderOrder::LeftUp => write!(f, "left-up"),
+        }
+    }
+}
+
 /// A Tiled global tile ID.
 ///
 /// These are used to identify tiles in a map. Since the map may have more than one tileset, an
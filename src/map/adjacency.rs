@@ -0,0 +1,260 @@
+use crate::{Map, Orientation};
+
+use super::coordinates::StaggerParams;
+
+/// Which tiles count as the neighbors of a tile on orthogonal and isometric maps.
+///
+/// Tiles on hexagonal and staggered maps always have six neighbors, so this is ignored for them.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum Connectivity {
+    /// Tiles are neighbors if they share an edge.
+    #[default]
+    Four,
+    /// Tiles are neighbors if they share an edge or a corner.
+    Eight,
+}
+
+/// Queries about which tiles are next to each other.
+///
+/// On hexagonal maps, the neighbors of a tile are the six tiles that share an edge with it.
+/// Staggered maps are treated as hexagonal maps whose sides parallel to the stagger axis have a
+/// length of 0, so the neighbors of a tile are the four tiles that share an edge with it, along
+/// with the two tiles that only touch its corners on either side of the stagger axis.
+///
+/// Distances are the number of steps between neighbors it takes to go from one tile to another.
+impl Map {
+    /// Returns the coordinates of the neighbors of the tile at the given coordinates.
+    ///
+    /// The neighbors aren't required to be within the bounds of the map.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Connectivity, Loader};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let neighbors = map.tile_neighbors(0, 0, Connectivity::Four);
+    /// assert_eq!(neighbors, vec![(1, 0), (0, 1), (-1, 0), (0, -1)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_neighbors(&self, x: i32, y: i32, connectivity: Connectivity) -> Vec<(i32, i32)> {
+        let layout = Layout::new(self, connectivity);
+        let (gx, gy) = layout.tile_to_grid(x, y);
+        layout
+            .directions()
+            .iter()
+            .map(|(dx, dy)| layout.grid_to_tile(gx + dx, gy + dy))
+            .collect()
+    }
+
+    /// Returns the smallest number of steps between neighbors it takes to go from one tile to
+    /// another.
+    pub fn tile_distance(
+        &self,
+        from: (i32, i32),
+        to: (i32, i32),
+        connectivity: Connectivity,
+    ) -> u32 {
+        let layout = Layout::new(self, connectivity);
+        layout.distance(
+            layout.tile_to_grid(from.0, from.1),
+            layout.tile_to_grid(to.0, to.1),
+        )
+    }
+
+    /// Returns an iterator over the tiles on the straight line between two tiles, including both
+    /// of them. Each tile is a neighbor of the previous one.
+    pub fn tiles_in_line(
+        &self,
+        from: (i32, i32),
+        to: (i32, i32),
+        connectivity: Connectivity,
+    ) -> impl ExactSizeIterator<Item = (i32, i32)> {
+        let layout = Layout::new(self, connectivity);
+        let from = layout.tile_to_grid(from.0, from.1);
+        let to = layout.tile_to_grid(to.0, to.1);
+        let tiles: Vec<(i32, i32)> = layout
+            .line(from, to)
+            .into_iter()
+            .map(|(x, y)| layout.grid_to_tile(x, y))
+            .collect();
+        tiles.into_iter()
+    }
+
+    /// Returns an iterator over the tiles that are exactly `radius` steps away from the tile at
+    /// the given coordinates, in order around it. A radius of 0 only yields the center tile.
+    pub fn tiles_in_ring(
+        &self,
+        x: i32,
+        y: i32,
+        radius: u32,
+        connectivity: Connectivity,
+    ) -> impl ExactSizeIterator<Item = (i32, i32)> {
+        let layout = Layout::new(self, connectivity);
+        let (cx, cy) = layout.tile_to_grid(x, y);
+        let radius = radius as i32;
+        let ((sx, sy), steps, side) = layout.ring_walk();
+
+        let mut tiles = Vec::new();
+        let (mut gx, mut gy) = (cx + sx * radius, cy + sy * radius);
+        if radius == 0 {
+            tiles.push(layout.grid_to_tile(gx, gy));
+        }
+        for (dx, dy) in steps {
+            for _ in 0..side * radius {
+                tiles.push(layout.grid_to_tile(gx, gy));
+                gx += dx;
+                gy += dy;
+            }
+        }
+        tiles.into_iter()
+    }
+}
+
+/// The arrangement of the tiles of a map, in the coordinates its neighbors are easiest to find
+/// in. These are the tile coordinates themselves on orthogonal and isometric maps, and axial hex
+/// coordinates on hexagonal and staggered ones.
+enum Layout {
+    Square(Connectivity),
+    Hex(StaggerParams),
+}
+
+impl Layout {
+    fn new(map: &Map, connectivity: Connectivity) -> Self {
+        match map.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => Layout::Square(connectivity),
+            Orientation::Staggered | Orientation::Hexagonal => Layout::Hex(StaggerParams::new(map)),
+        }
+    }
+
+    fn tile_to_grid(&self, x: i32, y: i32) -> (i32, i32) {
+        match self {
+            Layout::Square(_) => (x, y),
+            Layout::Hex(params) => params.tile_to_axial(x, y),
+        }
+    }
+
+    fn grid_to_tile(&self, x: i32, y: i32) -> (i32, i32) {
+        match self {
+            Layout::Square(_) => (x, y),
+            Layout::Hex(params) => params.axial_to_tile(x, y),
+        }
+    }
+
+    fn directions(&self) -> &'static [(i32, i32)] {
+        match self {
+            Layout::Square(Connectivity::Four) => &[(1, 0), (0, 1), (-1, 0), (0, -1)],
+            Layout::Square(Connectivity::Eight) => &[
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ],
+            Layout::Hex(_) => &[(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)],
+        }
+    }
+
+    fn distance(&self, from: (i32, i32), to: (i32, i32)) -> u32 {
+        let (dx, dy) = ((to.0 - from.0).abs(), (to.1 - from.1).abs());
+        match self {
+            Layout::Square(Connectivity::Four) => (dx + dy) as u32,
+            Layout::Square(Connectivity::Eight) => dx.max(dy) as u32,
+            Layout::Hex(_) => {
+                let dz = (to.0 - from.0 + to.1 - from.1).abs();
+                ((dx + dy + dz) / 2) as u32
+            }
+        }
+    }
+
+    /// How to walk around a ring: The direction of its first tile from the center, and the
+    /// directions of its sides, each of which is as many steps long as the radius times the last
+    /// value.
+    fn ring_walk(&self) -> ((i32, i32), &'static [(i32, i32)], i32) {
+        match self {
+            Layout::Square(Connectivity::Four) => {
+                ((0, -1), &[(1, 1), (-1, 1), (-1, -1), (1, -1)], 1)
+            }
+            Layout::Square(Connectivity::Eight) => {
+                ((-1, -1), &[(1, 0), (0, 1), (-1, 0), (0, -1)], 2)
+            }
+            Layout::Hex(_) => ((-1, 1), self.directions(), 1),
+        }
+    }
+
+    fn line(&self, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+        let steps = self.distance(from, to) as i32;
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        match self {
+            Layout::Square(Connectivity::Four) => {
+                // Walk towards the target one axis at a time, picking whichever axis the line
+                // crosses a tile boundary of first.
+                let (nx, ny) = (dx.abs(), dy.abs());
+                let (mut ix, mut iy) = (0, 0);
+                let mut tile = from;
+                let mut tiles = vec![tile];
+                while ix < nx || iy < ny {
+                    if (1 + 2 * ix) * ny < (1 + 2 * iy) * nx {
+                        tile.0 += dx.signum();
+                        ix += 1;
+                    } else {
+                        tile.1 += dy.signum();
+                        iy += 1;
+                    }
+                    tiles.push(tile);
+                }
+                tiles
+            }
+            Layout::Square(Connectivity::Eight) => {
+                // Rounds to the nearest tile, with halves rounded up.
+                let lerp = |start: i32, delta: i32, i: i32| {
+                    start + (2 * delta * i + steps).div_euclid(2 * steps)
+                };
+                (0..=steps)
+                    .map(|i| {
+                        if steps == 0 {
+                            from
+                        } else {
+                            (lerp(from.0, dx, i), lerp(from.1, dy, i))
+                        }
+                    })
+                    .collect()
+            }
+            Layout::Hex(_) => (0..=steps)
+                .map(|i| {
+                    let t = if steps == 0 {
+                        0.0
+                    } else {
+                        i as f64 / steps as f64
+                    };
+                    // The nudge keeps points that lie exactly between two tiles from being rounded
+                    // in different directions along the line.
+                    let q = from.0 as f64 + dx as f64 * t + 1e-6;
+                    let r = from.1 as f64 + dy as f64 * t + 1e-6;
+                    round_hex(q, r)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Rounds fractional axial hex coordinates to those of the hex they are in.
+fn round_hex(q: f64, r: f64) -> (i32, i32) {
+    let s = -q - r;
+    let (rq, rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    // The three coordinates always add up to zero, so the one that was rounded the most is
+    // recomputed from the other two.
+    if dq > dr && dq > ds {
+        ((-rr - rs) as i32, rr as i32)
+    } else if dr > ds {
+        (rq as i32, (-rq - rs) as i32)
+    } else {
+        (rq as i32, rr as i32)
+    }
+}
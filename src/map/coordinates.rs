@@ -0,0 +1,227 @@
+use crate::{Map, Orientation, StaggerAxis, StaggerIndex};
+
+/// Converting between tile and pixel coordinates.
+///
+/// Pixel coordinates are relative to the top left corner of the map as Tiled renders it, without
+/// taking layer offsets or parallax into account. Tile coordinates are the ones used by tile
+/// layers, such as in [`TileLayer::get_tile`](crate::TileLayer::get_tile).
+///
+/// Fractional tile coordinates describe points within tiles: Their integer part is the tile the
+/// point is in. On orthogonal and isometric maps, tile `(x, y)` spans the fractional coordinates
+/// from `(x, y)` to `(x + 1, y + 1)` along the axes of the grid, so that `(x, y)` is its top
+/// corner. On staggered and hexagonal maps, tiles don't line up along a grid, so their fractional
+/// part is instead the position of the point within the rectangle returned by
+/// [`Map::tile_to_pixel`], from 0 to 1 along each axis.
+///
+/// ## Note
+/// Objects on isometric maps are not positioned in pixel coordinates; Tiled stores their position
+/// in fractional tile coordinates multiplied by [`Map::tile_height`].
+impl Map {
+    /// Returns the top left corner of the area a tile at the given tile coordinates takes up in
+    /// the map, which is [`Map::tile_width`] wide and [`Map::tile_height`] high.
+    ///
+    /// Tiles whose images are bigger than this area are drawn with their bottom left corner at the
+    /// bottom left corner of it.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// assert_eq!(map.tile_to_pixel(2, 1), (64.0, 32.0));
+    /// assert_eq!(map.pixel_to_tile(70.0, 40.0), (2, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_to_pixel(&self, x: i32, y: i32) -> (f32, f32) {
+        match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => {
+                let (px, py) = self.tile_to_pixel_fractional(x as f32, y as f32);
+                if self.orientation == Orientation::Isometric {
+                    // The fractional origin of an isometric tile is its top corner, which is in the
+                    // middle of the top edge of its area.
+                    (px - self.tile_width as f32 / 2.0, py)
+                } else {
+                    (px, py)
+                }
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (px, py) = StaggerParams::new(self).tile_to_pixel(x, y);
+                (px as f32, py as f32)
+            }
+        }
+    }
+
+    /// Returns the coordinates of the tile that contains the given pixel.
+    ///
+    /// This is the same as rounding down the result of [`Map::pixel_to_tile_fractional`].
+    pub fn pixel_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        let (tx, ty) = self.pixel_to_tile_fractional(x, y);
+        (tx.floor() as i32, ty.floor() as i32)
+    }
+
+    /// Converts fractional tile coordinates to pixel coordinates. This is the inverse of
+    /// [`Map::pixel_to_tile_fractional`].
+    pub fn tile_to_pixel_fractional(&self, x: f32, y: f32) -> (f32, f32) {
+        let tile_width = self.tile_width as f32;
+        let tile_height = self.tile_height as f32;
+        match self.orientation {
+            Orientation::Orthogonal => (x * tile_width, y * tile_height),
+            Orientation::Isometric => {
+                let origin_x = (self.height * self.tile_width / 2) as f32;
+                (
+                    (x - y) * tile_width / 2.0 + origin_x,
+                    (x + y) * tile_height / 2.0,
+                )
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (px, py) = self.tile_to_pixel(x.floor() as i32, y.floor() as i32);
+                (
+                    px + x.rem_euclid(1.0) * tile_width,
+                    py + y.rem_euclid(1.0) * tile_height,
+                )
+            }
+        }
+    }
+
+    /// Converts pixel coordinates to fractional tile coordinates. This is the inverse of
+    /// [`Map::tile_to_pixel_fractional`].
+    pub fn pixel_to_tile_fractional(&self, x: f32, y: f32) -> (f32, f32) {
+        let tile_width = self.tile_width as f32;
+        let tile_height = self.tile_height as f32;
+        match self.orientation {
+            Orientation::Orthogonal => (x / tile_width, y / tile_height),
+            Orientation::Isometric => {
+                let origin_x = (self.height * self.tile_width / 2) as f32;
+                let tx = (x - origin_x) / tile_width;
+                let ty = y / tile_height;
+                (ty + tx, ty - tx)
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let (tx, ty) = StaggerParams::new(self).pixel_to_tile(x, y);
+                let (px, py) = self.tile_to_pixel(tx, ty);
+                (
+                    tx as f32 + (x - px) / tile_width,
+                    ty as f32 + (y - py) / tile_height,
+                )
+            }
+        }
+    }
+}
+
+/// The layout of the tiles of a staggered or hexagonal map, described along its stagger axis
+/// (`along`) and the other one (`across`).
+///
+/// Staggered maps are laid out like hexagonal maps whose side length is 0. The values are rounded
+/// down to even sizes the same way Tiled does, so that tiles land on whole pixels.
+pub(crate) struct StaggerParams {
+    stagger_x: bool,
+    stagger_even: bool,
+    /// The size of a tile along the stagger axis.
+    along_size: i32,
+    /// The size of a tile across the stagger axis.
+    across_size: i32,
+    /// The length of the sides of a hexagon that are parallel to the other axis.
+    side_length: i32,
+    /// The distance between two consecutive lines of tiles along the stagger axis.
+    step: i32,
+}
+
+impl StaggerParams {
+    pub(crate) fn new(map: &Map) -> Self {
+        let stagger_x = map.stagger_axis == StaggerAxis::X;
+        let tile_width = map.tile_width as i32 & !1;
+        let tile_height = map.tile_height as i32 & !1;
+        let side_length = match map.orientation {
+            Orientation::Hexagonal => map.hex_side_length.unwrap_or(0),
+            _ => 0,
+        };
+        let (along_size, across_size) = if stagger_x {
+            (tile_width, tile_height)
+        } else {
+            (tile_height, tile_width)
+        };
+        Self {
+            stagger_x,
+            stagger_even: map.stagger_index == StaggerIndex::Even,
+            along_size,
+            across_size,
+            side_length,
+            step: (along_size - side_length) / 2 + side_length,
+        }
+    }
+
+    /// Whether the line of tiles at the given index along the stagger axis is shifted by half a
+    /// tile.
+    fn is_shifted(&self, line: i32) -> bool {
+        (line.rem_euclid(2) == 1) != self.stagger_even
+    }
+
+    /// Swaps the given coordinates between `(x, y)` and `(along, across)` order.
+    fn swap<T>(&self, a: T, b: T) -> (T, T) {
+        if self.stagger_x {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    /// Converts tile coordinates to axial hex coordinates, whose first axis runs across the
+    /// stagger axis and second one is the line of tiles along it. Neighboring tiles are one step
+    /// apart along either axis or diagonally along `(1, -1)`.
+    pub(crate) fn tile_to_axial(&self, x: i32, y: i32) -> (i32, i32) {
+        let (line, index) = self.swap(x, y);
+        // Twice the position of the tile across the stagger axis, in tiles.
+        let across = 2 * index + self.is_shifted(line) as i32;
+        ((across - line).div_euclid(2), line)
+    }
+
+    /// Converts axial hex coordinates back to tile coordinates. This is the inverse of
+    /// [`StaggerParams::tile_to_axial`].
+    pub(crate) fn axial_to_tile(&self, q: i32, line: i32) -> (i32, i32) {
+        let shift = self.is_shifted(line) as i32;
+        let index = (2 * q + (shift - line).rem_euclid(2) + line - shift) / 2;
+        self.swap(line, index)
+    }
+
+    fn tile_to_pixel(&self, x: i32, y: i32) -> (i32, i32) {
+        let (line, index) = self.swap(x, y);
+        let mut across = index * self.across_size;
+        if self.is_shifted(line) {
+            across += self.across_size / 2;
+        }
+        self.swap(line * self.step, across)
+    }
+
+    fn pixel_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        let (along, across) = self.swap(x, y);
+        let half_along = self.along_size as f32 / 2.0;
+        let half_across = self.across_size as f32 / 2.0;
+        // The distance between the center and the corners where the slanted edges of a tile meet
+        // the sides parallel to the stagger axis.
+        let slant = (self.along_size - self.side_length) as f32 / 2.0;
+
+        // Tiles overlap the next line of tiles along the stagger axis, so the point is within the
+        // bounds of a tile of either of the two lines it falls in. Of those, it is inside of the
+        // one that it is the smallest amount of "tile sizes" away from.
+        let last_line = (along / self.step as f32).floor() as i32;
+        let candidate = |line: i32| {
+            let shift = if self.is_shifted(line) {
+                self.across_size / 2
+            } else {
+                0
+            };
+            let index = ((across - shift as f32) / self.across_size as f32).floor() as i32;
+            let center_along = (line * self.step) as f32 + half_along;
+            let center_across = (index * self.across_size + shift) as f32 + half_across;
+            let d_across = (across - center_across).abs() / half_across;
+            let d_along = ((along - center_along).abs() + slant * d_across) / half_along;
+            (line, index, d_along.max(d_across))
+        };
+        let (previous, last) = (candidate(last_line - 1), candidate(last_line));
+        let (line, index, _) = if previous.2 <= last.2 { previous } else { last };
+        self.swap(line, index)
+    }
+}
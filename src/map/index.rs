@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::{
+    layers::LayerData, Layer, Map, Object, ObjectLayer, ObjectLayerData, Properties, PropertyValue,
+};
+
+/// Where each layer and object of a map is found, so that the ones within group layers can be
+/// looked up without walking through all of them.
+///
+/// Layers are located by their position in the tree of layers: The index of the top-level layer
+/// they are in, followed by their index within each group layer down to them. Objects are located
+/// by the position of their layer and their index within it.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub(crate) struct MapIndex {
+    layers_by_id: HashMap<u32, Vec<usize>>,
+    layers_by_path: HashMap<String, Vec<usize>>,
+    objects_by_id: HashMap<u32, (Vec<usize>, usize)>,
+}
+
+/// An object found by [`Map::object_by_id`], along with the object layer it is in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LocatedObject<'map> {
+    /// The object that was found.
+    pub object: Object<'map>,
+    /// The object layer the object is in. See [`ObjectLayer::layer`] for its name and the other
+    /// data common to all layers.
+    pub object_layer: ObjectLayer<'map>,
+}
+
+impl MapIndex {
+    pub(crate) fn new(layers: &[LayerData]) -> Self {
+        let mut index = Self::default();
+        index.add(layers, None, &mut Vec::new());
+        index
+    }
+
+    fn add(&mut self, layers: &[LayerData], parent_path: Option<&str>, position: &mut Vec<usize>) {
+        for (i, layer) in layers.iter().enumerate() {
+            position.push(i);
+            let path = match parent_path {
+                Some(parent_path) => format!("{}/{}", parent_path, layer.name),
+                None => layer.name.clone(),
+            };
+            // Layers without an ID have it set to 0. If several layers or objects share an ID or a
+            // path, the first one found is used.
+            if layer.id() != 0 {
+                self.layers_by_id
+                    .entry(layer.id())
+                    .or_insert_with(|| position.clone());
+            }
+            self.layers_by_path
+                .entry(path.clone())
+                .or_insert_with(|| position.clone());
+            for (object_index, object) in layer.object_data().iter().enumerate() {
+                if object.id() != 0 {
+                    self.objects_by_id
+                        .entry(object.id())
+                        .or_insert_with(|| (position.clone(), object_index));
+                }
+            }
+            self.add(layer.sublayers(), Some(&path), position);
+            position.pop();
+        }
+    }
+
+    /// Returns the position of the layer with the given ID in the tree of layers.
+    pub(crate) fn layer_position(&self, id: u32) -> Option<Vec<usize>> {
+        self.layers_by_id.get(&id).cloned()
+    }
+}
+
+impl Map {
+    /// Returns the layer with the given ID, which may be within a group layer.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    ///
+    /// assert_eq!(map.layer_by_id(9).unwrap().name, "tile-3");
+    /// assert!(map.layer_by_id(2).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layer_by_id(&self, id: u32) -> Option<Layer> {
+        self.index
+            .layers_by_id
+            .get(&id)
+            .and_then(|position| self.layer_at(position))
+    }
+
+    /// Returns the layer found by following the given path of layer names separated by slashes,
+    /// such as `"Group/Subgroup/Layer"` for a layer named `Layer` within a group layer named
+    /// `Subgroup`, itself within a top-level group layer named `Group`.
+    ///
+    /// If several layers match the path, the first one in the map is returned.
+    pub fn layer_by_path(&self, path: &str) -> Option<Layer> {
+        self.index
+            .layers_by_path
+            .get(path)
+            .and_then(|position| self.layer_at(position))
+    }
+
+    /// Returns the object with the given ID, which may be within a group layer, along with the
+    /// object layer it is in.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    ///
+    /// let found = map.object_by_id(3).unwrap();
+    /// assert_eq!((found.object.x, found.object.y), (32.0, 32.0));
+    /// assert_eq!(found.object_layer.layer().name, "Object Layer 1");
+    /// assert!(found.object_layer.objects().any(|object| object == found.object));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn object_by_id(&self, id: u32) -> Option<LocatedObject> {
+        let (position, object_index) = self.index.objects_by_id.get(&id)?;
+        let object_layer = self.layer_at(position)?.as_object_layer()?;
+        let object = object_layer.get_object(*object_index)?;
+        Some(LocatedObject {
+            object,
+            object_layer,
+        })
+    }
+
+    /// Looks up the objects referenced by the object properties in `properties`, including the
+    /// ones that are members of class properties, and returns them by property name.
+    ///
+    /// Members of class properties are named after the path to them, with the names of the
+    /// properties along it separated by dots, such as `"door.target"` for the `target` member of a
+    /// `door` property. Properties that don't reference an object or reference one that isn't in
+    /// the map are left out.
+    pub fn resolve_object_properties(&self, properties: &Properties) -> HashMap<String, Object> {
+        let mut objects = HashMap::new();
+        self.resolve_object_properties_into(properties, None, &mut objects);
+        objects
+    }
+
+    fn resolve_object_properties_into<'map>(
+        &'map self,
+        properties: &Properties,
+        parent_path: Option<&str>,
+        objects: &mut HashMap<String, Object<'map>>,
+    ) {
+        for (name, value) in properties {
+            let path = match parent_path {
+                Some(parent_path) => format!("{}.{}", parent_path, name),
+                None => name.clone(),
+            };
+            match value {
+                PropertyValue::ObjectValue(id) => {
+                    if let Some(found) = self.object_by_id(*id) {
+                        objects.insert(path, found.object);
+                    }
+                }
+                PropertyValue::ClassValue { properties, .. } => {
+                    self.resolve_object_properties_into(properties, Some(&path), objects)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the layer whose data is the given object layer data, if it is in this map.
+    pub(crate) fn layer_with_objects(&self, data: &ObjectLayerData) -> Option<Layer> {
+        fn find<'map>(
+            layers: &'map [LayerData],
+            data: &ObjectLayerData,
+        ) -> Option<&'map LayerData> {
+            layers
+                .iter()
+                .find_map(|layer| match layer.object_layer_data() {
+                    Some(objects) if std::ptr::eq(objects, data) => Some(layer),
+                    _ => find(layer.sublayers(), data),
+                })
+        }
+        find(&self.layers, data).map(|layer| Layer::new(self, layer))
+    }
+
+    /// Returns the layer at the given position in the tree of layers. See [`MapIndex`].
+    fn layer_at(&self, position: &[usize]) -> Option<Layer> {
+        let (first, rest) = position.split_first()?;
+        let mut data = self.layers.get(*first)?;
+        for &index in rest {
+            data = data.sublayers().get(index)?;
+        }
+        Some(Layer::new(self, data))
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{Connectivity, LayerTile, Map, Properties, PropertyValue, TileLayer};
+
+/// A path between two tiles, as found by [`Map::find_path`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct TilePath {
+    /// The coordinates of the tiles along the path, including the first and last ones. Each tile
+    /// is a neighbor of the previous one.
+    pub tiles: Vec<(i32, i32)>,
+    /// The total cost of walking the path, which is the sum of the costs of every tile entered
+    /// along it, excluding the first one.
+    pub cost: f32,
+}
+
+/// Pathfinding over the tiles of a map.
+impl Map {
+    /// Finds the cheapest path between two tiles using the A* algorithm, walking between
+    /// neighbors as given by [`Map::tile_neighbors`].
+    ///
+    /// The cost of entering a tile is worked out from the tiles at its position in all of the
+    /// given layers, using their custom properties:
+    /// - Positions that have no tile in any of the layers can't be entered.
+    /// - Tiles with a `solid` property that is `true` can't be entered.
+    /// - Tiles with a `cost` property, whether an integer or a float, cost as much to enter.
+    ///   Otherwise, they cost 1. If the layers have more than one tile at a position, the most
+    ///   expensive one is used.
+    /// - Tiles whose cost is negative, infinite or not a number can't be entered.
+    ///
+    /// Costs may be lower than 1: the distance to the last tile is estimated using the lowest
+    /// cost found in the map's tilesets, so that the path found is always the cheapest one.
+    ///
+    /// Returns [`None`] if there is no path between the tiles. Use [`Map::find_path_by`] to work
+    /// out the cost of tiles in a different way.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Connectivity, Loader, TileLayer};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    /// let layers: Vec<TileLayer> = map
+    ///     .layers()
+    ///     .filter_map(|layer| layer.as_tile_layer())
+    ///     .collect();
+    ///
+    /// let path = map
+    ///     .find_path((0, 0), (3, 2), &layers, Connectivity::Four)
+    ///     .unwrap();
+    /// assert_eq!(path.tiles.len(), 6);
+    /// assert_eq!(path.cost, 5.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_path<'map>(
+        &'map self,
+        from: (i32, i32),
+        to: (i32, i32),
+        layers: &[TileLayer<'map>],
+        connectivity: Connectivity,
+    ) -> Option<TilePath> {
+        let min_cost = self
+            .tilesets()
+            .iter()
+            .flat_map(|tileset| tileset.tiles())
+            .filter_map(|(_, tile)| cost_property(&tile.properties))
+            .filter(|cost| *cost >= 0.0)
+            .fold(1.0, f32::min);
+        self.find_path_by(from, to, layers, connectivity, min_cost, |_, tiles| {
+            property_cost(tiles)
+        })
+    }
+
+    /// Finds the cheapest path between two tiles using the A* algorithm, walking between
+    /// neighbors as given by [`Map::tile_neighbors`].
+    ///
+    /// `cost` is given the coordinates of a tile along with the tiles at that position in each of
+    /// the given layers, in the same order, and returns the cost of entering it, or [`None`] if it
+    /// can't be entered. Tiles whose cost is negative or not a number can't be entered.
+    ///
+    /// `min_cost` is the lowest cost `cost` can return, which is used to estimate how far away the
+    /// last tile is. If it is higher than the cost of some tile, the path found may not be the
+    /// cheapest one. Passing 0 always finds the cheapest path, but explores more tiles to do so, as
+    /// in Dijkstra's algorithm.
+    ///
+    /// On finite maps, paths stay within the bounds of the map. Infinite maps have no bounds, so
+    /// unless `cost` returns [`None`] for tiles far enough away, looking for a path to a tile that
+    /// can't be reached never ends.
+    ///
+    /// Returns [`None`] if there is no path between the tiles.
+    pub fn find_path_by<'map>(
+        &'map self,
+        from: (i32, i32),
+        to: (i32, i32),
+        layers: &[TileLayer<'map>],
+        connectivity: Connectivity,
+        min_cost: f32,
+        mut cost: impl FnMut((i32, i32), &[Option<LayerTile<'map>>]) -> Option<f32>,
+    ) -> Option<TilePath> {
+        let in_bounds = |(x, y): (i32, i32)| {
+            self.infinite() || (x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32)
+        };
+        if !in_bounds(from) || !in_bounds(to) {
+            return None;
+        }
+        let min_cost = min_cost.max(0.0);
+        let estimate =
+            |tile: (i32, i32)| min_cost * self.tile_distance(tile, to, connectivity) as f32;
+
+        let mut tiles = Vec::with_capacity(layers.len());
+        let mut tile_costs: HashMap<(i32, i32), Option<f32>> = HashMap::new();
+        let mut tile_cost = |tile: (i32, i32)| {
+            *tile_costs.entry(tile).or_insert_with(|| {
+                tiles.clear();
+                tiles.extend(layers.iter().map(|layer| layer.get_tile(tile.0, tile.1)));
+                cost(tile, &tiles)
+            })
+        };
+
+        // The cheapest known cost of reaching each tile, and the tile it is reached from.
+        let mut reached: HashMap<(i32, i32), (f32, Option<(i32, i32)>)> = HashMap::new();
+        let mut open = BinaryHeap::new();
+        reached.insert(from, (0.0, None));
+        open.push(Candidate {
+            estimate: estimate(from),
+            cost: 0.0,
+            tile: from,
+        });
+
+        while let Some(Candidate { cost, tile, .. }) = open.pop() {
+            if tile == to {
+                let mut path = vec![tile];
+                while let Some(previous) = reached[path.last().unwrap()].1 {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(TilePath { tiles: path, cost });
+            }
+            if cost > reached[&tile].0 {
+                // The tile was reached in a cheaper way after this candidate was queued.
+                continue;
+            }
+            for neighbor in self.tile_neighbors(tile.0, tile.1, connectivity) {
+                if !in_bounds(neighbor) {
+                    continue;
+                }
+                let neighbor_cost = match tile_cost(neighbor) {
+                    // Negative costs would make the same tiles cheaper to reach every time they
+                    // are walked through, and never let the search end.
+                    Some(step) if step >= 0.0 => cost + step,
+                    _ => continue,
+                };
+                if let Some(&(known, _)) = reached.get(&neighbor) {
+                    if known <= neighbor_cost {
+                        continue;
+                    }
+                }
+                reached.insert(neighbor, (neighbor_cost, Some(tile)));
+                open.push(Candidate {
+                    estimate: neighbor_cost + estimate(neighbor),
+                    cost: neighbor_cost,
+                    tile: neighbor,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Works out the cost of entering a position from the `solid` and `cost` properties of its tiles.
+fn property_cost(tiles: &[Option<LayerTile>]) -> Option<f32> {
+    let mut highest: Option<f32> = None;
+    for tile in tiles.iter().flatten() {
+        let tile = tile.get_tile();
+        let property = |name: &str| tile.as_ref().and_then(|tile| tile.properties.get(name));
+        if property("solid") == Some(&PropertyValue::BoolValue(true)) {
+            return None;
+        }
+        let cost = tile
+            .as_ref()
+            .and_then(|tile| cost_property(&tile.properties))
+            .unwrap_or(1.0);
+        if !cost.is_finite() || cost < 0.0 {
+            return None;
+        }
+        highest = Some(highest.map_or(cost, |highest| highest.max(cost)));
+    }
+    highest
+}
+
+/// Returns the value of the `cost` property among the given tile properties, if there is one.
+fn cost_property(properties: &Properties) -> Option<f32> {
+    match properties.get("cost")? {
+        PropertyValue::FloatValue(cost) => Some(*cost),
+        PropertyValue::IntValue(cost) => Some(*cost as f32),
+        _ => None,
+    }
+}
+
+/// A tile waiting to be explored, ordered so that the one with the lowest estimated path cost is
+/// explored first.
+struct Candidate {
+    /// The cost of reaching the tile plus the smallest possible cost of going from it to the last
+    /// tile.
+    estimate: f32,
+    /// The cost of reaching the tile.
+    cost: f32,
+    tile: (i32, i32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` pops the greatest element first, so lower estimates must compare greater.
+        // Between equal estimates, tiles that took more to reach are closer to the last tile.
+        other
+            .estimate
+            .partial_cmp(&self.estimate)
+            .unwrap_or(Ordering::Equal)
+            .then(
+                self.cost
+                    .partial_cmp(&other.cost)
+                    .unwrap_or(Ordering::Equal),
+            )
+    }
+}
@@ -1,13 +1,27 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::{Error, Result},
-    properties::{parse_properties, Properties},
+    properties::{parse_properties, write_properties, Properties, PropertyValue},
     template::Template,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Color, Gid, MapTilesetGid, ResourceCache, ResourceReader, Tile, TileId, Tileset,
+    write::{relative_path, WriteContext, XmlWriter},
+    Color, FillMode, Gid, MapTilesetGid, Orientation, Project, ResourceCache, ResourceReader, Tile,
+    TileId, Tileset, UnknownXml, XmlElement,
+};
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{json_field, json_field_opt, json_object, JsonObject},
+    properties::{parse_properties_json, write_properties_json},
+    write::json_f32,
 };
 
 /// The location of the tileset this tile is in
@@ -90,6 +104,22 @@ impl ObjectTileData {
             })
         }
     }
+
+    /// Turns this tile back into a [`Gid`] plus its flipping bits, given the first GID of the
+    /// tileset it is in.
+    pub(crate) fn to_bits(&self, first_gid: Gid) -> u32 {
+        let mut bits = first_gid.0 + self.id;
+        if self.flip_h {
+            bits |= Self::FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if self.flip_v {
+            bits |= Self::FLIPPED_VERTICALLY_FLAG;
+        }
+        if self.flip_d {
+            bits |= Self::FLIPPED_DIAGONALLY_FLAG;
+        }
+        bits
+    }
 }
 
 map_wrapper!(
@@ -175,6 +205,19 @@ pub enum VerticalAlignment {
     Bottom,
 }
 
+/// The area the image of a tile object is drawn in, as given by [`Object::tile_placement`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TilePlacement {
+    /// The X coordinate of the top-left corner of the image, in pixels.
+    pub x: f32,
+    /// The Y coordinate of the top-left corner of the image, in pixels.
+    pub y: f32,
+    /// The width the image is drawn at, in pixels.
+    pub width: f32,
+    /// The height the image is drawn at, in pixels.
+    pub height: f32,
+}
+
 /// Raw data belonging to an object. Used internally and for tile collisions.
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
@@ -198,6 +241,13 @@ pub struct ObjectData {
     pub shape: ObjectShape,
     /// The object's custom properties as set by the user.
     pub properties: Properties,
+    /// The attributes and elements of the `<object>` element that aren't recognized, if they were
+    /// preserved when loading it.
+    pub unknown: UnknownXml,
+    template: Option<Arc<Template>>,
+    /// The names of the properties that were inherited from the template rather than set by the
+    /// object itself.
+    inherited_properties: HashSet<String>,
 }
 
 impl ObjectData {
@@ -214,11 +264,57 @@ impl ObjectData {
     pub fn tile_data(&self) -> Option<ObjectTileData> {
         self.tile.clone()
     }
+
+    /// Returns the template this object is an instance of, if any.
+    #[inline]
+    pub fn template(&self) -> Option<&Arc<Template>> {
+        self.template.as_ref()
+    }
+
+    pub(crate) fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        project.apply(&mut self.properties);
+        // Inherited properties are compared to the template's when writing the object, so they
+        // must be filled in the same way.
+        self.template = self
+            .template
+            .take()
+            .map(|template| template.with_project(project));
+    }
+}
+
+impl Default for ObjectData {
+    /// Returns a visible, unnamed rectangle object with no size at (0, 0). Its ID is 0, so that
+    /// it is given a new one when added to a map with [`Map::push_object`](crate::Map::push_object).
+    fn default() -> Self {
+        ObjectData {
+            id: 0,
+            tile: None,
+            name: String::new(),
+            user_type: String::new(),
+            x: 0.0,
+            y: 0.0,
+            rotation: 0.0,
+            visible: true,
+            shape: ObjectShape::Rect {
+                width: 0.0,
+                height: 0.0,
+            },
+            properties: HashMap::new(),
+            unknown: UnknownXml::default(),
+            template: None,
+            inherited_properties: HashSet::new(),
+        }
+    }
 }
 
 impl ObjectData {
     /// If it is known that the object has no tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -226,10 +322,11 @@ impl ObjectData {
         for_tileset: Option<Arc<Tileset>>,
         // Base path is a directory to which all other files are relative to
         base_path: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<ObjectData> {
-        let (id, tile, mut n, mut t, c, mut w, mut h, mut v, mut r, template, x, y) = get_attrs!(
+        let (id, tile, mut n, mut t, c, mut w, mut h, mut v, mut r, template, x, y, unknown_attrs) = get_attrs!(
             for v in attrs {
                 Some("id") => id ?= v.parse(),
                 Some("gid") => tile ?= v.parse::<u32>(),
@@ -244,7 +341,8 @@ impl ObjectData {
                 Some("x") => x ?= v.parse::<f32>(),
                 Some("y") => y ?= v.parse::<f32>(),
             }
-            (id, tile, name, user_type, user_class, width, height, visible, rotation, template, x, y)
+            unknown => unknown_attrs,
+            (id, tile, name, user_type, user_class, width, height, visible, rotation, template, x, y, unknown_attrs)
         );
         let x = x.unwrap_or(0.);
         let y = y.unwrap_or(0.);
@@ -255,16 +353,7 @@ impl ObjectData {
         let template = template
             .map(|template_path: String| {
                 let template_path = base_path.join(Path::new(&template_path));
-
-                // Check the cache to see if this template exists
-                let template = if let Some(templ) = cache.get_template(&template_path) {
-                    templ
-                } else {
-                    let template = Template::parse_template(&template_path, reader, cache)?;
-                    // Insert it into the cache
-                    cache.insert_template(&template_path, template.clone());
-                    template
-                };
+                let template = load_template(&template_path, preserve_unknown, reader, cache)?;
 
                 // The template sets the default values for the object
                 let obj = &template.object;
@@ -297,6 +386,8 @@ impl ObjectData {
         let user_type: String = t.or(c).unwrap_or_default();
         let mut shape = None;
         let mut properties = HashMap::new();
+        let mut unknown = UnknownXml::with_attributes(unknown_attrs, preserve_unknown);
+        let mut inherited_properties = HashSet::new();
 
         parse_tag!(parser, "object", {
             "ellipse" => |_| {
@@ -326,58 +417,16 @@ impl ObjectData {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown.elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
 
-        if let Some(templ) = template {
-            shape.get_or_insert_with(|| {
-                // Inherit the shape from the template but use the size and
-                // position from the object where relevant
-                match &templ.object.shape {
-                    ObjectShape::Rect { .. } => ObjectShape::Rect { width, height },
-                    ObjectShape::Ellipse { .. } => ObjectShape::Ellipse { width, height },
-                    ObjectShape::Point(_, _) => ObjectShape::Point(x, y),
-                    ObjectShape::Text {
-                        font_family,
-                        pixel_size,
-                        wrap,
-                        color,
-                        bold,
-                        italic,
-                        underline,
-                        strikeout,
-                        kerning,
-                        halign,
-                        valign,
-                        text,
-                        width: _,
-                        height: _,
-                    } => ObjectShape::Text {
-                        font_family: font_family.clone(),
-                        pixel_size: pixel_size.clone(),
-                        wrap: wrap.clone(),
-                        color: color.clone(),
-                        bold: bold.clone(),
-                        italic: italic.clone(),
-                        underline: underline.clone(),
-                        strikeout: strikeout.clone(),
-                        kerning: kerning.clone(),
-                        halign: halign.clone(),
-                        valign: valign.clone(),
-                        text: text.clone(),
-                        width,
-                        height,
-                    },
-                    shape => shape.clone(),
-                }
-            });
-
-            // Possibly copy properties from the template into the object
-            // Any that already exist in the object's map don't get copied over
-            for (k, v) in &templ.object.properties {
-                if !properties.contains_key(k) {
-                    properties.insert(k.clone(), v.clone());
-                }
-            }
+        if let Some(templ) = &template {
+            shape.get_or_insert_with(|| templ.object.shape.inherit(x, y, width, height));
+            inherited_properties = inherit_properties(&mut properties, &templ.object);
         }
 
         let shape = shape.unwrap_or(ObjectShape::Rect { width, height });
@@ -393,6 +442,9 @@ impl ObjectData {
             visible,
             shape,
             properties,
+            unknown,
+            template,
+            inherited_properties,
         })
     }
 }
@@ -447,22 +499,8 @@ impl ObjectData {
                 Some("underline") => underline ?= v.parse(),
                 Some("strikeout") => strikeout ?= v.parse(),
                 Some("kerning") => kerning ?= v.parse::<i32>(),
-                Some("halign") => halign = match v.as_str() {
-                    "left" => HorizontalAlignment::Left,
-                    "center" => HorizontalAlignment::Center,
-                    "right" => HorizontalAlignment::Right,
-                    "justify" => HorizontalAlignment::Justify,
-                    _ => return Err(Error::MalformedAttributes("`halign` property did not contain a valid value of 'left', 'center', 'right' or 'justify'".to_string()))
-                },
-                Some("valign") => valign = match v.as_str() {
-                    "top" => VerticalAlignment::Top,
-                    "center" => VerticalAlignment::Center,
-                    "bottom" => VerticalAlignment::Bottom,
-                    _ => return Err(Error::MalformedAttributes(
-                        "`halign` property did not contain a valid value of 'top', 'center' or 'bottom'"
-                            .to_string(),
-                    )),
-                },
+                Some("halign") => halign = parse_halign(&v)?,
+                Some("valign") => valign = parse_valign(&v)?,
             }
             (
                 font_family,
@@ -553,6 +591,605 @@ impl ObjectData {
     }
 }
 
+#[cfg(feature = "json")]
+impl ObjectData {
+    /// If it is known that the object has no tile images in it (i.e. collision data)
+    /// then we can pass in [`None`] as the tilesets
+    pub(crate) fn from_json(
+        object: &JsonObject,
+        tilesets: Option<&[MapTilesetGid]>,
+        for_tileset: Option<Arc<Tileset>>,
+        // Base path is a directory to which all other files are relative to
+        base_path: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<ObjectData> {
+        let x = json_field_opt(object, "x")?.unwrap_or(0.);
+        let y = json_field_opt(object, "y")?.unwrap_or(0.);
+        let mut name = json_field_opt::<String>(object, "name")?;
+        let mut user_type = json_field_opt::<String>(object, "type")?;
+        user_type = user_type.or(json_field_opt(object, "class")?);
+        let mut width = json_field_opt(object, "width")?;
+        let mut height = json_field_opt(object, "height")?;
+        let mut visible = json_field_opt(object, "visible")?;
+        let mut rotation = json_field_opt(object, "rotation")?;
+        let mut tile = json_field_opt::<u32>(object, "gid")?.and_then(|bits| {
+            ObjectTileData::from_bits(bits, tilesets?, for_tileset.as_ref().cloned())
+        });
+
+        let template = match json_field_opt::<String>(object, "template")? {
+            Some(template_path) => {
+                let template_path = base_path.join(Path::new(&template_path));
+                let template = load_template(&template_path, preserve_unknown, reader, cache)?;
+
+                // The template sets the default values for the object
+                let obj = &template.object;
+                visible.get_or_insert(obj.visible);
+                rotation.get_or_insert(obj.rotation);
+                name.get_or_insert_with(|| obj.name.clone());
+                user_type.get_or_insert_with(|| obj.user_type.clone());
+                if let Some(templ_tile) = &obj.tile {
+                    tile.get_or_insert_with(|| templ_tile.clone());
+                }
+                match &obj.shape {
+                    ObjectShape::Rect {
+                        width: w,
+                        height: h,
+                    }
+                    | ObjectShape::Ellipse {
+                        width: w,
+                        height: h,
+                    }
+                    | ObjectShape::Text {
+                        width: w,
+                        height: h,
+                        ..
+                    } => {
+                        width.get_or_insert(*w);
+                        height.get_or_insert(*h);
+                    }
+                    _ => {}
+                }
+                Some(template)
+            }
+            None => None,
+        };
+
+        let width = width.unwrap_or(0f32);
+        let height = height.unwrap_or(0f32);
+        let mut properties = parse_properties_json(object)?;
+        let mut inherited_properties = HashSet::new();
+
+        let mut shape = if json_field_opt(object, "ellipse")?.unwrap_or(false) {
+            Some(ObjectShape::Ellipse { width, height })
+        } else if json_field_opt(object, "point")?.unwrap_or(false) {
+            Some(ObjectShape::Point(x, y))
+        } else if let Some(points) = object.get("polyline") {
+            Some(ObjectShape::Polyline {
+                points: ObjectData::parse_points_json(points)?,
+            })
+        } else if let Some(points) = object.get("polygon") {
+            Some(ObjectShape::Polygon {
+                points: ObjectData::parse_points_json(points)?,
+            })
+        } else if let Some(text) = object.get("text") {
+            Some(ObjectData::new_text_json(
+                json_object(text)?,
+                width,
+                height,
+            )?)
+        } else {
+            None
+        };
+
+        if let Some(templ) = &template {
+            shape.get_or_insert_with(|| templ.object.shape.inherit(x, y, width, height));
+            inherited_properties = inherit_properties(&mut properties, &templ.object);
+        }
+
+        Ok(ObjectData {
+            id: json_field_opt(object, "id")?.unwrap_or(0u32),
+            tile,
+            name: name.unwrap_or_default(),
+            user_type: user_type.unwrap_or_default(),
+            x,
+            y,
+            rotation: rotation.unwrap_or(0f32),
+            visible: visible.unwrap_or(true),
+            shape: shape.unwrap_or(ObjectShape::Rect { width, height }),
+            properties,
+            unknown: UnknownXml::default(),
+            template,
+            inherited_properties,
+        })
+    }
+
+    fn new_text_json(text: &JsonObject, width: f32, height: f32) -> Result<ObjectShape> {
+        Ok(ObjectShape::Text {
+            font_family: json_field_opt(text, "fontfamily")?
+                .unwrap_or_else(|| "sans-serif".to_string()),
+            pixel_size: json_field_opt(text, "pixelsize")?.unwrap_or(16),
+            wrap: json_field_opt(text, "wrap")?.unwrap_or(false),
+            color: json_field_opt(text, "color")?.unwrap_or(Color {
+                red: 0,
+                green: 0,
+                blue: 0,
+                alpha: 255,
+            }),
+            bold: json_field_opt(text, "bold")?.unwrap_or(false),
+            italic: json_field_opt(text, "italic")?.unwrap_or(false),
+            underline: json_field_opt(text, "underline")?.unwrap_or(false),
+            strikeout: json_field_opt(text, "strikeout")?.unwrap_or(false),
+            kerning: json_field_opt(text, "kerning")?.unwrap_or(true),
+            halign: json_field_opt::<String>(text, "halign")?
+                .map(|v| parse_halign(&v))
+                .transpose()?
+                .unwrap_or_default(),
+            valign: json_field_opt::<String>(text, "valign")?
+                .map(|v| parse_valign(&v))
+                .transpose()?
+                .unwrap_or_default(),
+            text: json_field_opt(text, "text")?.unwrap_or_default(),
+            width,
+            height,
+        })
+    }
+
+    fn parse_points_json(points: &serde_json::Value) -> Result<Vec<(f32, f32)>> {
+        points
+            .as_array()
+            .ok_or_else(|| {
+                Error::MalformedAttributes("polyline points are not an array".to_string())
+            })?
+            .iter()
+            .map(|point| {
+                let point = json_object(point)?;
+                Ok((json_field(point, "x")?, json_field(point, "y")?))
+            })
+            .collect()
+    }
+}
+
+impl ObjectData {
+    /// Returns the object of the template this object is an instance of, if any.
+    fn template_object(&self) -> Option<&ObjectData> {
+        self.template.as_deref().map(|template| &template.object)
+    }
+
+    /// Returns whether the shape of this object is the one it would inherit from its template.
+    fn inherits_shape(&self) -> bool {
+        match self.template_object() {
+            Some(template) => {
+                let (width, height) = self.shape.size().unwrap_or((0.0, 0.0));
+                template.shape.inherit(self.x, self.y, width, height) == self.shape
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the properties of this object, leaving out those that are inherited unchanged from
+    /// its template.
+    fn own_properties(&self) -> Cow<'_, Properties> {
+        match self.template_object() {
+            Some(template) => Cow::Owned(
+                self.properties
+                    .iter()
+                    .filter(|(name, value)| {
+                        !self.inherited_properties.contains(*name)
+                            || template.properties.get(*name) != Some(*value)
+                    })
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect(),
+            ),
+            None => Cow::Borrowed(&self.properties),
+        }
+    }
+
+    /// Writes this object as an `<object>` element.
+    ///
+    /// Like Tiled does, objects that are instances of a template only have the attributes and
+    /// properties that differ from the template object written.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let template = self.template_object();
+
+        let mut attrs = Vec::new();
+        if self.id != 0 {
+            attrs.push(("id", self.id.to_string()));
+        }
+        if let Some(template) = &self.template {
+            attrs.push(("template", relative_path(ctx.base_path, &template.source)));
+        }
+        if template.map_or(!self.name.is_empty(), |t| t.name != self.name) {
+            attrs.push(("name", self.name.clone()));
+        }
+        if template.map_or(!self.user_type.is_empty(), |t| {
+            t.user_type != self.user_type
+        }) {
+            attrs.push(("type", self.user_type.clone()));
+        }
+        if let Some(tile) = &self.tile {
+            match &tile.tileset_location {
+                TilesetLocation::Map(index) => {
+                    attrs.push(("gid", tile.to_bits(ctx.first_gids[*index]).to_string()))
+                }
+                // Tiles coming from a template's tileset are inherited from the template object,
+                // unless this is the template object itself; templates always use a first GID of 1.
+                TilesetLocation::Template(_) if template.is_none() => {
+                    attrs.push(("gid", tile.to_bits(Gid(1)).to_string()))
+                }
+                TilesetLocation::Template(_) => {}
+            }
+        }
+        attrs.push(("x", self.x.to_string()));
+        attrs.push(("y", self.y.to_string()));
+        if let Some((width, height)) = self.shape.size() {
+            let written = match template {
+                Some(t) => t.shape.size() != Some((width, height)),
+                None => width != 0.0 || height != 0.0,
+            };
+            if written {
+                attrs.push(("width", width.to_string()));
+                attrs.push(("height", height.to_string()));
+            }
+        }
+        if template.map_or(self.rotation != 0.0, |t| t.rotation != self.rotation) {
+            attrs.push(("rotation", self.rotation.to_string()));
+        }
+        if template.map_or(!self.visible, |t| t.visible != self.visible) {
+            attrs.push(("visible", if self.visible { "1" } else { "0" }.to_owned()));
+        }
+        self.unknown.extend_attributes(&mut attrs);
+
+        writer.start_with_unknown("object", &attrs, &self.unknown.elements)?;
+        write_properties(writer, &self.own_properties())?;
+        if !self.inherits_shape() {
+            self.shape.write_xml(writer)?;
+        }
+        writer.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl ObjectData {
+    /// Converts this object into a JSON object, as found in the `objects` array of object layers.
+    ///
+    /// As with XML, only the members that differ from the template object are written for objects
+    /// that are instances of a template.
+    pub(crate) fn to_json(&self, ctx: &WriteContext) -> JsonObject {
+        let template = self.template_object();
+
+        let mut object = JsonObject::new();
+        if self.id != 0 {
+            object.insert("id".to_owned(), self.id.into());
+        }
+        if let Some(template) = &self.template {
+            object.insert(
+                "template".to_owned(),
+                relative_path(ctx.base_path, &template.source).into(),
+            );
+        }
+        if template.map_or(!self.name.is_empty(), |t| t.name != self.name) {
+            object.insert("name".to_owned(), self.name.clone().into());
+        }
+        if template.map_or(!self.user_type.is_empty(), |t| {
+            t.user_type != self.user_type
+        }) {
+            object.insert("type".to_owned(), self.user_type.clone().into());
+        }
+        if let Some(tile) = &self.tile {
+            match &tile.tileset_location {
+                TilesetLocation::Map(index) => {
+                    object.insert(
+                        "gid".to_owned(),
+                        tile.to_bits(ctx.first_gids[*index]).into(),
+                    );
+                }
+                TilesetLocation::Template(_) if template.is_none() => {
+                    object.insert("gid".to_owned(), tile.to_bits(Gid(1)).into());
+                }
+                TilesetLocation::Template(_) => {}
+            }
+        }
+        object.insert("x".to_owned(), json_f32(self.x));
+        object.insert("y".to_owned(), json_f32(self.y));
+        if let Some((width, height)) = self.shape.size() {
+            if template.and_then(|t| t.shape.size()) != Some((width, height)) {
+                object.insert("width".to_owned(), json_f32(width));
+                object.insert("height".to_owned(), json_f32(height));
+            }
+        }
+        if template.map(|t| t.rotation) != Some(self.rotation) {
+            object.insert("rotation".to_owned(), json_f32(self.rotation));
+        }
+        if template.map(|t| t.visible) != Some(self.visible) {
+            object.insert("visible".to_owned(), self.visible.into());
+        }
+        write_properties_json(&mut object, &self.own_properties());
+        if !self.inherits_shape() {
+            self.shape.write_json(&mut object);
+        }
+        object
+    }
+}
+
+#[cfg(feature = "json")]
+impl ObjectShape {
+    /// Adds the members describing this shape to the JSON object it belongs to, if any.
+    fn write_json(&self, object: &mut JsonObject) {
+        let points_json = |points: &[(f32, f32)]| -> serde_json::Value {
+            points
+                .iter()
+                .map(|(x, y)| serde_json::json!({ "x": json_f32(*x), "y": json_f32(*y) }))
+                .collect::<Vec<_>>()
+                .into()
+        };
+
+        match self {
+            ObjectShape::Rect { .. } => {}
+            ObjectShape::Ellipse { .. } => {
+                object.insert("ellipse".to_owned(), true.into());
+            }
+            ObjectShape::Point(..) => {
+                object.insert("point".to_owned(), true.into());
+            }
+            ObjectShape::Polygon { points } => {
+                object.insert("polygon".to_owned(), points_json(points));
+            }
+            ObjectShape::Polyline { points } => {
+                object.insert("polyline".to_owned(), points_json(points));
+            }
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                text,
+                ..
+            } => {
+                let halign = match halign {
+                    HorizontalAlignment::Left => "left",
+                    HorizontalAlignment::Center => "center",
+                    HorizontalAlignment::Right => "right",
+                    HorizontalAlignment::Justify => "justify",
+                };
+                let valign = match valign {
+                    VerticalAlignment::Top => "top",
+                    VerticalAlignment::Center => "center",
+                    VerticalAlignment::Bottom => "bottom",
+                };
+                object.insert(
+                    "text".to_owned(),
+                    serde_json::json!({
+                        "fontfamily": font_family,
+                        "pixelsize": pixel_size,
+                        "wrap": wrap,
+                        "color": color.to_string(),
+                        "bold": bold,
+                        "italic": italic,
+                        "underline": underline,
+                        "strikeout": strikeout,
+                        "kerning": kerning,
+                        "halign": halign,
+                        "valign": valign,
+                        "text": text,
+                    }),
+                );
+            }
+        }
+    }
+}
+
+impl ObjectShape {
+    /// Returns the width and height of this shape, if it has any.
+    fn size(&self) -> Option<(f32, f32)> {
+        match self {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => Some((*width, *height)),
+            _ => None,
+        }
+    }
+
+    /// Writes the element describing this shape, if any.
+    fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let format_points = |points: &[(f32, f32)]| {
+            points
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match self {
+            ObjectShape::Rect { .. } => Ok(()),
+            ObjectShape::Ellipse { .. } => writer.empty("ellipse", &[]),
+            ObjectShape::Point(..) => writer.empty("point", &[]),
+            ObjectShape::Polygon { points } => {
+                writer.empty("polygon", &[("points", format_points(points))])
+            }
+            ObjectShape::Polyline { points } => {
+                writer.empty("polyline", &[("points", format_points(points))])
+            }
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                text,
+                ..
+            } => {
+                let mut attrs = Vec::new();
+                if font_family != "sans-serif" {
+                    attrs.push(("fontfamily", font_family.clone()));
+                }
+                if *pixel_size != 16 {
+                    attrs.push(("pixelsize", pixel_size.to_string()));
+                }
+                for (name, set) in [
+                    ("wrap", *wrap),
+                    ("bold", *bold),
+                    ("italic", *italic),
+                    ("underline", *underline),
+                    ("strikeout", *strikeout),
+                ]
+                .iter()
+                {
+                    if *set {
+                        attrs.push((*name, "1".to_owned()));
+                    }
+                }
+                if !*kerning {
+                    attrs.push(("kerning", "0".to_owned()));
+                }
+                let default_color = Color {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                    alpha: 255,
+                };
+                if *color != default_color {
+                    attrs.push(("color", color.to_string()));
+                }
+                let halign = match halign {
+                    HorizontalAlignment::Left => None,
+                    HorizontalAlignment::Center => Some("center"),
+                    HorizontalAlignment::Right => Some("right"),
+                    HorizontalAlignment::Justify => Some("justify"),
+                };
+                if let Some(halign) = halign {
+                    attrs.push(("halign", halign.to_owned()));
+                }
+                let valign = match valign {
+                    VerticalAlignment::Top => None,
+                    VerticalAlignment::Center => Some("center"),
+                    VerticalAlignment::Bottom => Some("bottom"),
+                };
+                if let Some(valign) = valign {
+                    attrs.push(("valign", valign.to_owned()));
+                }
+
+                writer.start("text", &attrs)?;
+                writer.characters(text)?;
+                writer.end()
+            }
+        }
+    }
+
+    /// Returns the shape an object inherits from a template whose object has this shape, using
+    /// the size and position from the object where relevant.
+    fn inherit(&self, x: f32, y: f32, width: f32, height: f32) -> ObjectShape {
+        match self {
+            ObjectShape::Rect { .. } => ObjectShape::Rect { width, height },
+            ObjectShape::Ellipse { .. } => ObjectShape::Ellipse { width, height },
+            ObjectShape::Point(_, _) => ObjectShape::Point(x, y),
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                text,
+                width: _,
+                height: _,
+            } => ObjectShape::Text {
+                font_family: font_family.clone(),
+                pixel_size: *pixel_size,
+                wrap: *wrap,
+                color: *color,
+                bold: *bold,
+                italic: *italic,
+                underline: *underline,
+                strikeout: *strikeout,
+                kerning: *kerning,
+                halign: *halign,
+                valign: *valign,
+                text: text.clone(),
+                width,
+                height,
+            },
+            shape => shape.clone(),
+        }
+    }
+}
+
+/// Copies the properties of a template object into an object, and returns the names of those
+/// that the object didn't already have.
+fn inherit_properties(properties: &mut Properties, template: &ObjectData) -> HashSet<String> {
+    let mut inherited = HashSet::new();
+    for (k, v) in &template.properties {
+        if !properties.contains_key(k) {
+            properties.insert(k.clone(), v.clone());
+            inherited.insert(k.clone());
+        }
+    }
+    inherited
+}
+
+/// Loads the template found at `template_path`, checking the cache first to see if it already
+/// exists.
+pub(crate) fn load_template(
+    template_path: &Path,
+    preserve_unknown: bool,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+) -> Result<Arc<Template>> {
+    if let Some(templ) = cache.get_template(template_path) {
+        Ok(templ)
+    } else {
+        let template = Template::parse_template(template_path, preserve_unknown, reader, cache)?;
+        // Insert it into the cache
+        cache.insert_template(template_path, template.clone());
+        Ok(template)
+    }
+}
+
+fn parse_halign(v: &str) -> Result<HorizontalAlignment> {
+    match v {
+        "left" => Ok(HorizontalAlignment::Left),
+        "center" => Ok(HorizontalAlignment::Center),
+        "right" => Ok(HorizontalAlignment::Right),
+        "justify" => Ok(HorizontalAlignment::Justify),
+        _ => Err(Error::MalformedAttributes("`halign` property did not contain a valid value of 'left', 'center', 'right' or 'justify'".to_string())),
+    }
+}
+
+fn parse_valign(v: &str) -> Result<VerticalAlignment> {
+    match v {
+        "top" => Ok(VerticalAlignment::Top),
+        "center" => Ok(VerticalAlignment::Center),
+        "bottom" => Ok(VerticalAlignment::Bottom),
+        _ => Err(Error::MalformedAttributes(
+            "`halign` property did not contain a valid value of 'top', 'center' or 'bottom'"
+                .to_string(),
+        )),
+    }
+}
+
 map_wrapper!(
     #[doc = "Wrapper over an [`ObjectData`] that contains both a reference to the data as well as
     to the map it is contained in."]
@@ -560,6 +1197,51 @@ map_wrapper!(
 );
 
 impl<'map> Object<'map> {
+    /// Returns the path the file property with the given name points to, relative to the same
+    /// directory as [`Map::source`](crate::Map::source), or [`None`] if the object has no such
+    /// property, or it isn't a file property or is unset.
+    ///
+    /// Members of class properties are named after the path to them, with the names of the
+    /// properties along it separated by dots, as in
+    /// [`Map::resolve_object_properties`](crate::Map::resolve_object_properties).
+    ///
+    /// Unlike with [`Map::resolve_file_property`](crate::Map::resolve_file_property), properties
+    /// that the object inherits from its template are resolved against the template's path, which
+    /// is what they are relative to.
+    ///
+    /// ## Example
+    /// ```
+    /// # use std::path::PathBuf;
+    /// # use tiled::{Loader, PropertyValue};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    /// let object = map.object_by_id(2).unwrap().object;
+    ///
+    /// // Object properties aren't file properties.
+    /// assert_eq!(object.resolve_file_property("object property"), None);
+    /// assert_eq!(object.resolve_file_property("missing"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_file_property(&self, name: &str) -> Option<PathBuf> {
+        let mut names = name.split('.');
+        let property = names.next()?;
+        let mut value = self.data.properties.get(property)?;
+        for member in names {
+            value = match value {
+                PropertyValue::ClassValue { properties, .. } => properties.get(member)?,
+                _ => return None,
+            };
+        }
+
+        let owner_source = match &self.data.template {
+            Some(template) if self.data.inherited_properties.contains(property) => &template.source,
+            _ => &self.map.source,
+        };
+        value.file_path(owner_source)
+    }
+
     /// Returns the tile that the object is using as image, if any.
     pub fn get_tile(&self) -> Option<ObjectTile<'map>> {
         self.data
@@ -567,4 +1249,84 @@ impl<'map> Object<'map> {
             .as_ref()
             .map(|tile| ObjectTile::new(self.map, tile))
     }
+
+    /// Returns the area the image of a tile object is drawn in, or [`None`] if the object has no
+    /// tile or its tile doesn't exist.
+    ///
+    /// The object's position is taken to be the point of the image given by the tileset's
+    /// [`object_alignment`](Tileset::object_alignment), and the image is scaled to the object's size
+    /// according to the tileset's [`fill_mode`](Tileset::fill_mode), then moved by its tile offset.
+    /// The object's rotation, which is around its position, is not applied.
+    ///
+    /// On isometric maps, the object's position is projected to pixel coordinates first, as
+    /// given by [`Map::tile_to_pixel_fractional`](crate::Map::tile_to_pixel_fractional).
+    pub fn tile_placement(&self) -> Option<TilePlacement> {
+        let object_tile = self.get_tile()?;
+        let tileset = object_tile.get_tileset();
+        let source = tileset.tile_source_rect(object_tile.id())?;
+        let (image_width, image_height) = (source.width as f32, source.height as f32);
+
+        let mut placement = self.tile_area(image_width, image_height, tileset);
+        placement.x += tileset.offset_x as f32;
+        placement.y += tileset.offset_y as f32;
+        if tileset.fill_mode == FillMode::PreserveAspectFit {
+            let (width, height) = (placement.width, placement.height);
+            let scale = (width / image_width).min(height / image_height);
+            placement.width = image_width * scale;
+            placement.height = image_height * scale;
+            placement.x += (width - placement.width) / 2.0;
+            placement.y += (height - placement.height) / 2.0;
+        }
+        Some(placement)
+    }
+
+    /// Returns the area covered by a tile object with a tile of the given size, as given by its
+    /// position, size and the tileset's object alignment.
+    fn tile_area(&self, tile_width: f32, tile_height: f32, tileset: &Tileset) -> TilePlacement {
+        // Objects saved by old versions of Tiled may not have a size.
+        let (width, height) = match self.shape {
+            ObjectShape::Rect { width, height } if width > 0.0 && height > 0.0 => (width, height),
+            _ => (tile_width, tile_height),
+        };
+        let (anchor_x, anchor_y) =
+            tileset
+                .object_alignment
+                .anchor(self.map.orientation, width, height);
+        let (x, y) = self.pixel_position();
+        TilePlacement {
+            x: x - anchor_x,
+            y: y - anchor_y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the position of the object in pixel coordinates. Objects on isometric maps are
+    /// positioned in fractional tile coordinates multiplied by [`Map::tile_height`], so their
+    /// position is projected.
+    ///
+    /// [`Map::tile_height`]: crate::Map::tile_height
+    fn pixel_position(&self) -> (f32, f32) {
+        match self.map.orientation {
+            Orientation::Isometric => {
+                let tile_height = self.map.tile_height as f32;
+                self.map
+                    .tile_to_pixel_fractional(self.x / tile_height, self.y / tile_height)
+            }
+            _ => (self.x, self.y),
+        }
+    }
+
+    /// Returns the Y coordinate objects are sorted by when drawn from top to bottom.
+    pub(crate) fn draw_order_y(&self) -> f32 {
+        let tile_area = self.get_tile().and_then(|object_tile| {
+            let tileset = object_tile.get_tileset();
+            let source = tileset.tile_source_rect(object_tile.id())?;
+            Some(self.tile_area(source.width as f32, source.height as f32, tileset))
+        });
+        match tile_area {
+            Some(area) => area.y + area.height,
+            None => self.pixel_position().1,
+        }
+    }
 }
@@ -0,0 +1,15 @@
+use std::path::Path;
+
+use crate::{Map, ResourceCache, ResourceReader, Result};
+
+use super::{json_object, read_json};
+
+pub fn parse_map(
+    path: &Path,
+    preserve_unknown: bool,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+) -> Result<Map> {
+    let map = read_json(path, reader)?;
+    Map::parse_json(json_object(&map)?, path, preserve_unknown, reader, cache)
+}
@@ -0,0 +1,6 @@
+mod map;
+pub use map::*;
+mod tileset;
+pub use tileset::*;
+mod util;
+pub(crate) use util::*;
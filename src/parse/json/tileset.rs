@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use crate::{ResourceCache, ResourceReader, Result, Tileset};
+
+use super::{json_object, read_json};
+
+pub fn parse_tileset(
+    path: &Path,
+    preserve_unknown: bool,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+) -> Result<Tileset> {
+    let tileset = read_json(path, reader)?;
+    Tileset::parse_json(
+        json_object(&tileset)?,
+        path,
+        preserve_unknown,
+        reader,
+        cache,
+    )
+}
@@ -0,0 +1,126 @@
+use std::{io::Read, path::Path, str::FromStr};
+
+use serde_json::Value;
+
+use crate::{Color, Error, ResourceReader, Result};
+
+/// A JSON object, as found in TMJ, TSJ and TJ files.
+pub(crate) type JsonObject = serde_json::Map<String, Value>;
+
+/// Types that can be read from a single JSON value.
+///
+/// Numbers are converted through their textual representation so that the parsed values are
+/// identical to the ones obtained from the equivalent XML attributes.
+pub(crate) trait FromJson: Sized {
+    fn from_json(value: &Value) -> Option<Self>;
+}
+
+impl FromJson for String {
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_str().map(str::to_owned)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+macro_rules! impl_from_json_number {
+    ($($ty:ty),*) => {
+        $(
+            impl FromJson for $ty {
+                fn from_json(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(n) => n.to_string().parse().ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_json_number!(u32, i32, i64, usize, f32);
+
+impl FromJson for Color {
+    fn from_json(value: &Value) -> Option<Self> {
+        value.as_str().and_then(|s| s.parse().ok())
+    }
+}
+
+/// Reads a required field of a JSON object.
+pub(crate) fn json_field<T: FromJson>(object: &JsonObject, key: &str) -> Result<T> {
+    let value = object
+        .get(key)
+        .ok_or_else(|| Error::MalformedAttributes(format!("Missing attribute: {}", key)))?;
+    T::from_json(value)
+        .ok_or_else(|| Error::MalformedAttributes(format!("Error parsing attribute '{}'", key)))
+}
+
+/// Reads an optional field of a JSON object. `null` values are treated as missing.
+pub(crate) fn json_field_opt<T: FromJson>(object: &JsonObject, key: &str) -> Result<Option<T>> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => T::from_json(value).map(Some).ok_or_else(|| {
+            Error::MalformedAttributes(format!("Error parsing optional attribute '{}'", key))
+        }),
+    }
+}
+
+/// Reads a required string field of a JSON object and parses it through [`FromStr`].
+pub(crate) fn json_parse<T: FromStr>(object: &JsonObject, key: &str) -> Result<T> {
+    json_field::<String>(object, key)?
+        .parse()
+        .map_err(|_| Error::MalformedAttributes(format!("Error parsing attribute '{}'", key)))
+}
+
+/// Reads an optional string field of a JSON object and parses it through [`FromStr`].
+pub(crate) fn json_parse_opt<T: FromStr>(object: &JsonObject, key: &str) -> Result<Option<T>> {
+    json_field_opt::<String>(object, key)?
+        .map(|v| {
+            v.parse().map_err(|_| {
+                Error::MalformedAttributes(format!("Error parsing optional attribute '{}'", key))
+            })
+        })
+        .transpose()
+}
+
+/// Reads an array field of a JSON object. A missing field results in an empty slice.
+pub(crate) fn json_array<'a>(object: &'a JsonObject, key: &str) -> Result<&'a [Value]> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(&[][..]),
+        Some(Value::Array(values)) => Ok(values.as_slice()),
+        Some(_) => Err(Error::MalformedAttributes(format!(
+            "Error parsing attribute '{}'",
+            key
+        ))),
+    }
+}
+
+/// Interprets a JSON value as an object.
+pub(crate) fn json_object(value: &Value) -> Result<&JsonObject> {
+    value
+        .as_object()
+        .ok_or_else(|| Error::MalformedAttributes("Expected a JSON object".to_owned()))
+}
+
+/// Reads and deserializes a whole JSON document from a resource.
+pub(crate) fn read_json(path: &Path, reader: &mut impl ResourceReader) -> Result<Value> {
+    let mut resource = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let mut contents = String::new();
+    resource
+        .read_to_string(&mut contents)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+
+    serde_json::from_str(&contents).map_err(Error::JsonDecodingError)
+}
@@ -1 +1,57 @@
+use std::path::Path;
+
+use crate::{ResourceCache, ResourceReader, Result, Tileset};
+
+#[cfg(feature = "json")]
+pub mod json;
 pub mod xml;
+
+/// Parses an external tileset, picking the XML or JSON parser depending on the file's format.
+pub(crate) fn parse_tileset(
+    path: &Path,
+    preserve_unknown: bool,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+) -> Result<Tileset> {
+    #[cfg(feature = "json")]
+    {
+        if is_json(path, reader)? {
+            return json::parse_tileset(path, preserve_unknown, reader, cache);
+        }
+    }
+    xml::parse_tileset(path, preserve_unknown, reader, cache)
+}
+
+/// Determines whether the resource at the given path is a JSON file. The file extension is used
+/// if it is one Tiled knows about; Otherwise, the start of the file's contents is checked.
+#[cfg(feature = "json")]
+pub(crate) fn is_json(path: &Path, reader: &mut impl ResourceReader) -> Result<bool> {
+    use std::io::Read;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("tmj" | "tsj" | "tj" | "json") => return Ok(true),
+        Some("tmx" | "tsx" | "tx" | "xml") => return Ok(false),
+        _ => {}
+    }
+
+    let resource = reader
+        .read_from(path)
+        .map_err(|err| crate::Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    // JSON documents start with an object, while XML ones start with a tag. Whitespace and the
+    // UTF-8 byte order mark may precede either of them.
+    for byte in resource.bytes() {
+        match byte.map_err(|err| crate::Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })? {
+            b'{' => return Ok(true),
+            b'\xEF' | b'\xBB' | b'\xBF' => {}
+            b if b.is_ascii_whitespace() => {}
+            _ => break,
+        }
+    }
+    Ok(false)
+}
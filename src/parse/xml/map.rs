@@ -6,6 +6,7 @@ use crate::{Error, Map, ResourceCache, ResourceReader, Result};
 
 pub fn parse_map(
     path: &Path,
+    preserve_unknown: bool,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
 ) -> Result<Map> {
@@ -28,6 +29,7 @@ pub fn parse_map(
                         &mut parser.into_iter(),
                         attributes,
                         path,
+                        preserve_unknown,
                         reader,
                         cache,
                     );
@@ -6,6 +6,7 @@ use crate::{Error, ResourceCache, ResourceReader, Result, Tileset};
 
 pub fn parse_tileset(
     path: &Path,
+    preserve_unknown: bool,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
 ) -> Result<Tileset> {
@@ -27,6 +28,7 @@ pub fn parse_tileset(
                     &mut tileset_parser.into_iter(),
                     &attributes,
                     path,
+                    preserve_unknown,
                     reader,
                     cache,
                 );
@@ -0,0 +1,372 @@
+use std::{convert::TryFrom, path::PathBuf};
+
+#[cfg(feature = "json")]
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{
+        json_array, json_field, json_field_opt, json_object, json_parse_opt, read_json, JsonObject,
+    },
+    properties::parse_property_json,
+    Error, ResourceReader, Result,
+};
+use crate::{Color, Properties, PropertyValue, RawEnumValue};
+
+/// A Tiled project, which holds the custom property types shared by the maps and tilesets in it.
+///
+/// Loading maps through a [`Loader`](crate::Loader) that has a project set will fill in the
+/// members of class properties that were left to their default values in the files, which
+/// Tiled doesn't save, and decode the values of enum properties.
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/projects/).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Project {
+    /// The path first used in a [`ResourceReader`](crate::ResourceReader) to load this project.
+    pub source: PathBuf,
+    /// The folders that make up the project, relative to the path given to load the project.
+    pub folders: Vec<PathBuf>,
+    /// The custom property types defined in this project.
+    pub property_types: Vec<PropertyType>,
+}
+
+impl Project {
+    /// Returns the custom property type with the given name, if there is one.
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types.iter().find(|t| t.name() == name)
+    }
+
+    /// Returns the custom class with the given name, if there is one.
+    pub fn class(&self, name: &str) -> Option<&ClassType> {
+        match self.property_type(name) {
+            Some(PropertyType::Class(class)) => Some(class),
+            _ => None,
+        }
+    }
+
+    /// Returns the custom enum with the given name, if there is one.
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        match self.property_type(name) {
+            Some(PropertyType::Enum(enum_type)) => Some(enum_type),
+            _ => None,
+        }
+    }
+
+    /// Adds the default value of every member missing from the class values in `properties`,
+    /// including nested ones, according to the classes defined in this project.
+    ///
+    /// Class members read from JSON files don't carry their type, so the members whose type
+    /// couldn't be told from their value alone (colors, files, object references, enums and
+    /// nested classes) are also converted to the type of their definition.
+    pub fn fill_class_defaults(&self, properties: &mut Properties) {
+        for value in properties.values_mut() {
+            if let PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } = value
+            {
+                if let Some(class) = self.class(property_type) {
+                    for (name, default) in &class.members {
+                        match properties.get_mut(name) {
+                            Some(member) => convert_member(member, default),
+                            None => {
+                                properties.insert(name.clone(), default.clone());
+                            }
+                        }
+                    }
+                }
+                self.fill_class_defaults(properties);
+            }
+        }
+    }
+
+    /// Sets the [names](PropertyValue::EnumValue) of the enum values in `properties`,
+    /// including the ones that are members of class values, according to the enums defined in
+    /// this project. Values whose enum isn't defined in this project are left untouched.
+    pub fn decode_enums(&self, properties: &mut Properties) {
+        for value in properties.values_mut() {
+            match value {
+                PropertyValue::EnumValue {
+                    property_type,
+                    value,
+                    names,
+                } => {
+                    if let Some(enum_type) = self.enum_type(property_type) {
+                        *names = enum_type.value_names(value);
+                    }
+                }
+                PropertyValue::ClassValue { properties, .. } => self.decode_enums(properties),
+                _ => {}
+            }
+        }
+    }
+
+    /// Fills in class defaults, then decodes enums.
+    pub(crate) fn apply(&self, properties: &mut Properties) {
+        self.fill_class_defaults(properties);
+        self.decode_enums(properties);
+    }
+}
+
+/// Converts a class member that was read from a JSON file to the type of its definition.
+fn convert_member(member: &mut PropertyValue, definition: &PropertyValue) {
+    let converted = match (&mut *member, definition) {
+        (PropertyValue::StringValue(v), PropertyValue::ColorValue(_)) => {
+            v.parse().ok().map(PropertyValue::ColorValue)
+        }
+        (PropertyValue::StringValue(v), PropertyValue::FileValue(_)) => {
+            Some(PropertyValue::FileValue(v.clone()))
+        }
+        (PropertyValue::IntValue(v), PropertyValue::FloatValue(_)) => {
+            Some(PropertyValue::FloatValue(*v as f32))
+        }
+        (PropertyValue::IntValue(v), PropertyValue::ObjectValue(_)) => {
+            u32::try_from(*v).ok().map(PropertyValue::ObjectValue)
+        }
+        (
+            value @ (PropertyValue::StringValue(_) | PropertyValue::IntValue(_)),
+            PropertyValue::EnumValue { property_type, .. },
+        ) => Some(value.clone().with_enum_type(Some(property_type.clone()))),
+        (
+            PropertyValue::ClassValue { property_type, .. },
+            PropertyValue::ClassValue {
+                property_type: class,
+                ..
+            },
+        ) => {
+            if property_type.is_empty() {
+                *property_type = class.clone();
+            }
+            None
+        }
+        _ => None,
+    };
+    if let Some(converted) = converted {
+        *member = converted;
+    }
+}
+
+/// A custom property type defined in a [`Project`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum PropertyType {
+    /// A class, made of a set of members.
+    Class(ClassType),
+    /// An enum, made of a set of named values.
+    Enum(EnumType),
+}
+
+impl PropertyType {
+    /// The ID of this type, unique within its project.
+    pub fn id(&self) -> u32 {
+        match self {
+            PropertyType::Class(class) => class.id,
+            PropertyType::Enum(enum_type) => enum_type.id,
+        }
+    }
+
+    /// The name of this type, which is how properties refer to it.
+    pub fn name(&self) -> &str {
+        match self {
+            PropertyType::Class(class) => &class.name,
+            PropertyType::Enum(enum_type) => &enum_type.name,
+        }
+    }
+}
+
+/// A custom class, which can be used as the type of properties and of the other objects it is
+/// [used as](Self::use_as).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ClassType {
+    /// The ID of this class, unique within its project.
+    pub id: u32,
+    /// The name of this class.
+    pub name: String,
+    /// The color used in the editor to display objects of this class.
+    pub color: Option<Color>,
+    /// Whether objects of this class are filled with [`Self::color`] in the editor.
+    pub draw_fill: bool,
+    /// What this class can be used for. Usages unknown to the crate are left out.
+    pub use_as: Vec<ClassUsage>,
+    /// The members of this class, along with their default values. Members that are class values
+    /// only hold the members whose defaults differ from the ones of their own class.
+    pub members: Properties,
+}
+
+/// Something a [`ClassType`] can be used for.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ClassUsage {
+    /// The class can be the type of custom properties.
+    Property,
+    /// The class can be the class of maps.
+    Map,
+    /// The class can be the class of layers.
+    Layer,
+    /// The class can be the class of objects.
+    Object,
+    /// The class can be the class of tiles.
+    Tile,
+    /// The class can be the class of tilesets.
+    Tileset,
+    /// The class can be the class of Wang colors.
+    WangColor,
+    /// The class can be the class of Wang sets.
+    WangSet,
+    /// The class can be the class of projects.
+    Project,
+}
+
+impl ClassUsage {
+    /// Parses a value of a class' `useAs` member.
+    #[cfg(feature = "json")]
+    fn from_name(name: &str) -> Option<ClassUsage> {
+        match name {
+            "property" => Some(ClassUsage::Property),
+            "map" => Some(ClassUsage::Map),
+            "layer" => Some(ClassUsage::Layer),
+            "object" => Some(ClassUsage::Object),
+            "tile" => Some(ClassUsage::Tile),
+            "tileset" => Some(ClassUsage::Tileset),
+            "wangcolor" => Some(ClassUsage::WangColor),
+            "wangset" => Some(ClassUsage::WangSet),
+            "project" => Some(ClassUsage::Project),
+            _ => None,
+        }
+    }
+}
+
+/// A custom enum, whose properties hold one of its values (or a combination of them, if
+/// [`Self::values_as_flags`] is set).
+#[derive(Debug, PartialEq, Clone)]
+pub struct EnumType {
+    /// The ID of this enum, unique within its project.
+    pub id: u32,
+    /// The name of this enum.
+    pub name: String,
+    /// How the values of this enum are stored in files.
+    pub storage_type: EnumStorageType,
+    /// The names of the values of this enum, in order.
+    pub values: Vec<String>,
+    /// Whether properties of this enum can hold several values at once.
+    pub values_as_flags: bool,
+}
+
+impl EnumType {
+    /// Returns the names of the values held by a value of this enum: a single one for regular
+    /// enums, and the name of every set flag for flag enums. Returns [`None`] if the value doesn't
+    /// refer to a value of this enum.
+    pub fn value_names(&self, value: &RawEnumValue) -> Option<Vec<String>> {
+        match (value, self.values_as_flags) {
+            (RawEnumValue::String(name), false) => self
+                .values
+                .iter()
+                .find(|v| *v == name)
+                .map(|v| vec![v.clone()]),
+            (RawEnumValue::String(names), true) => names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| self.values.iter().find(|v| *v == name).cloned())
+                .collect(),
+            (RawEnumValue::Int(index), false) => usize::try_from(*index)
+                .ok()
+                .and_then(|index| self.values.get(index))
+                .map(|v| vec![v.clone()]),
+            (RawEnumValue::Int(bits), true) => Some(
+                self.values
+                    .iter()
+                    .take(32)
+                    .enumerate()
+                    .filter(|(bit, _)| bits & (1 << bit) != 0)
+                    .map(|(_, v)| v.clone())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// How the values of an [`EnumType`] are stored in files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EnumStorageType {
+    /// Values are stored by name. Flag enums store a comma-separated list of names.
+    String,
+    /// Values are stored by index. Flag enums store a bitmask with a bit per value instead.
+    Int,
+}
+
+#[cfg(feature = "json")]
+pub(crate) fn parse_project(path: &Path, reader: &mut impl ResourceReader) -> Result<Project> {
+    let project = read_json(path, reader)?;
+    let project = json_object(&project)?;
+    let project_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+
+    let folders = json_array(project, "folders")?
+        .iter()
+        .map(|folder| {
+            folder
+                .as_str()
+                .map(|folder| project_dir.join(folder))
+                .ok_or_else(|| {
+                    Error::MalformedAttributes("Error parsing attribute 'folders'".to_owned())
+                })
+        })
+        .collect::<Result<_>>()?;
+    let property_types = json_array(project, "propertyTypes")?
+        .iter()
+        .map(|t| PropertyType::from_json(json_object(t)?))
+        .collect::<Result<_>>()?;
+
+    Ok(Project {
+        source: path.to_owned(),
+        folders,
+        property_types,
+    })
+}
+
+#[cfg(feature = "json")]
+impl PropertyType {
+    fn from_json(object: &JsonObject) -> Result<PropertyType> {
+        let id = json_field(object, "id")?;
+        let name = json_field(object, "name")?;
+        let t: String = json_field(object, "type")?;
+        match t.as_str() {
+            "class" => Ok(PropertyType::Class(ClassType {
+                id,
+                name,
+                color: json_parse_opt(object, "color")?,
+                draw_fill: json_field_opt(object, "drawFill")?.unwrap_or(true),
+                use_as: json_array(object, "useAs")?
+                    .iter()
+                    .filter_map(|usage| usage.as_str().and_then(ClassUsage::from_name))
+                    .collect(),
+                members: json_array(object, "members")?
+                    .iter()
+                    .map(|member| parse_property_json(json_object(member)?, "propertyType"))
+                    .collect::<Result<_>>()?,
+            })),
+            "enum" => Ok(PropertyType::Enum(EnumType {
+                id,
+                name,
+                storage_type: match json_field::<String>(object, "storageType")?.as_str() {
+                    "string" => EnumStorageType::String,
+                    "int" => EnumStorageType::Int,
+                    _ => {
+                        return Err(Error::MalformedAttributes(
+                            "Error parsing attribute 'storageType'".to_owned(),
+                        ))
+                    }
+                },
+                values: json_array(object, "values")?
+                    .iter()
+                    .map(|value| {
+                        value.as_str().map(str::to_owned).ok_or_else(|| {
+                            Error::MalformedAttributes(
+                                "Error parsing attribute 'values'".to_owned(),
+                            )
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                values_as_flags: json_field_opt(object, "valuesAsFlags")?.unwrap_or(false),
+            })),
+            _ => Err(Error::UnknownPropertyType { type_name: t }),
+        }
+    }
+}
@@ -1,12 +1,29 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use xml::{attribute::OwnedAttribute, reader::XmlEvent};
 
 use crate::{
     error::{Error, Result},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
+};
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{json_array, json_field, json_field_opt, json_object, JsonObject},
+    write::json_f32,
 };
 
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+pub use de::*;
+
 /// Represents a RGBA color with 8-bit depth on each channel.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[allow(missing_docs)]
@@ -61,6 +78,35 @@ impl FromStr for Color {
     }
 }
 
+impl Color {
+    /// Multiplies each channel of this color with the same one of another color, as Tiled does to
+    /// combine the tint colors of layers.
+    pub(crate) fn multiply(self, other: Color) -> Color {
+        let channel = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+        Color {
+            alpha: channel(self.alpha, other.alpha),
+            red: channel(self.red, other.red),
+            green: channel(self.green, other.green),
+            blue: channel(self.blue, other.blue),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats the color the way Tiled does, as `#RRGGBB` or `#AARRGGBB` if it isn't opaque.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.alpha == 0xFF {
+            write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            write!(
+                f,
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.alpha, self.red, self.green, self.blue
+            )
+        }
+    }
+}
+
 /// Represents a custom property's value.
 ///
 /// Also read the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-properties).
@@ -77,7 +123,9 @@ pub enum PropertyValue {
     /// A string value. Corresponds to the `string` property type.
     StringValue(String),
     /// A filepath value. Corresponds to the `file` property type.
-    /// Holds the path relative to the map or tileset.
+    /// Holds the path relative to the map, tileset or template. Use
+    /// [`Map::resolve_file_property`](crate::Map::resolve_file_property) or its tileset and
+    /// template counterparts to get the full path.
     FileValue(String),
     /// An object ID value. Corresponds to the `object` property type.
     /// Holds the id of a referenced object, or 0 if unset.
@@ -90,6 +138,39 @@ pub enum PropertyValue {
         /// A set of properties.
         properties: Properties,
     },
+    /// An enum value. Corresponds to the `string` and `int` property types when they have a custom
+    /// enum type.
+    /// Holds the type name, the value as it is stored and the names of the values it holds.
+    EnumValue {
+        /// The type name.
+        property_type: String,
+        /// The value as stored in the file. This is the only part of the value that is written
+        /// back when saving.
+        value: RawEnumValue,
+        /// The names of the enum values this value holds, if the definition of the enum is known
+        /// (see [`Loader::set_project`](crate::Loader::set_project)). Holds a single name for
+        /// regular enums and the name of every set flag for flag enums.
+        names: Option<Vec<String>>,
+    },
+}
+
+/// The value of an enum property as stored in a file. Which one is used depends on the
+/// [storage type](crate::EnumStorageType) of the enum.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RawEnumValue {
+    /// The name of the value. Flag enums store a comma-separated list of the set flags instead.
+    String(String),
+    /// The index of the value. Flag enums store a bitmask with a bit per value instead.
+    Int(i32),
+}
+
+impl fmt::Display for RawEnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawEnumValue::String(v) => write!(f, "{}", v),
+            RawEnumValue::Int(v) => write!(f, "{}", v),
+        }
+    }
 }
 
 impl PropertyValue {
@@ -132,6 +213,108 @@ impl PropertyValue {
             }),
         }
     }
+
+    /// Enum values are stored as plain `string` and `int` properties that have a custom type,
+    /// which is given as `enum_type`.
+    pub(crate) fn with_enum_type(self, enum_type: Option<String>) -> PropertyValue {
+        let enum_type = match enum_type {
+            Some(enum_type) if !enum_type.is_empty() => enum_type,
+            _ => return self,
+        };
+        let value = match self {
+            PropertyValue::StringValue(v) => RawEnumValue::String(v),
+            PropertyValue::IntValue(v) => RawEnumValue::Int(v),
+            value => return value,
+        };
+        PropertyValue::EnumValue {
+            property_type: enum_type,
+            value,
+            names: None,
+        }
+    }
+
+    /// Resolves a file value against `owner_source`, the path of the map, tileset or template it
+    /// was loaded from, the same way image sources are. Returns [`None`] for other values and for
+    /// file values that are unset.
+    pub(crate) fn file_path(&self, owner_source: &Path) -> Option<PathBuf> {
+        match self {
+            PropertyValue::FileValue(path) if !path.is_empty() => {
+                Some(owner_source.parent()?.join(path))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl PropertyValue {
+    fn from_json(property_type: String, value: &serde_json::Value) -> Result<PropertyValue> {
+        use serde_json::Value;
+
+        // Converting the value back into text guarantees the result is the same as the one
+        // obtained from the equivalent XML attribute.
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => {
+                return Err(Error::InvalidPropertyValue {
+                    description: format!(
+                        "unexpected JSON value for a '{}' property",
+                        property_type
+                    ),
+                })
+            }
+        };
+        PropertyValue::new(property_type, value)
+    }
+}
+
+#[cfg(feature = "json")]
+impl PropertyValue {
+    /// The name of this value's type, as found in the `type` member of JSON properties.
+    fn json_type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::BoolValue(_) => "bool",
+            PropertyValue::FloatValue(_) => "float",
+            PropertyValue::IntValue(_) => "int",
+            PropertyValue::ColorValue(_) => "color",
+            PropertyValue::StringValue(_) => "string",
+            PropertyValue::FileValue(_) => "file",
+            PropertyValue::ObjectValue(_) => "object",
+            PropertyValue::ClassValue { .. } => "class",
+            PropertyValue::EnumValue {
+                value: RawEnumValue::String(_),
+                ..
+            } => "string",
+            PropertyValue::EnumValue {
+                value: RawEnumValue::Int(_),
+                ..
+            } => "int",
+        }
+    }
+
+    /// Converts this value into the `value` member of a JSON property. Class values become an
+    /// object holding their members' values.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            PropertyValue::BoolValue(v) => (*v).into(),
+            PropertyValue::FloatValue(v) => json_f32(*v),
+            PropertyValue::IntValue(v) => (*v).into(),
+            PropertyValue::ColorValue(v) => v.to_string().into(),
+            PropertyValue::StringValue(v) | PropertyValue::FileValue(v) => v.clone().into(),
+            PropertyValue::ObjectValue(v) => (*v).into(),
+            PropertyValue::ClassValue { properties, .. } => properties
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json()))
+                .collect::<JsonObject>()
+                .into(),
+            PropertyValue::EnumValue { value, .. } => match value {
+                RawEnumValue::String(v) => v.clone().into(),
+                RawEnumValue::Int(v) => (*v).into(),
+            },
+        }
+    }
 }
 
 /// A custom property container.
@@ -182,13 +365,215 @@ pub(crate) fn parse_properties(
                 }
             };
 
-            p.insert(k, PropertyValue::new(t, v)?);
+            p.insert(k, PropertyValue::new(t, v)?.with_enum_type(p_t));
             Ok(())
         },
     });
     Ok(p)
 }
 
+#[cfg(feature = "json")]
+pub(crate) fn parse_properties_json(object: &JsonObject) -> Result<Properties> {
+    json_array(object, "properties")?
+        .iter()
+        .map(|property| parse_property_json(json_object(property)?, "propertytype"))
+        .collect()
+}
+
+/// Reads a single JSON property, returning its name and value. The name of the member holding the
+/// custom type of the property is given as `property_type_key`, since it is `propertytype` in maps
+/// and tilesets but `propertyType` in project files.
+#[cfg(feature = "json")]
+pub(crate) fn parse_property_json(
+    property: &JsonObject,
+    property_type_key: &str,
+) -> Result<(String, PropertyValue)> {
+    let name: String = json_field(property, "name")?;
+    let t = json_field_opt::<String>(property, "type")?.unwrap_or_else(|| "string".to_owned());
+    let value = if t == "class" {
+        // Only the members that were actually set are saved, as a plain JSON object. When no
+        // members have been set the value is left out or empty.
+        let properties = match property.get("value") {
+            Some(serde_json::Value::Object(members)) => parse_class_members_json(members)?,
+            _ => HashMap::new(),
+        };
+        PropertyValue::ClassValue {
+            property_type: json_field_opt::<String>(property, property_type_key)?
+                .unwrap_or_default(),
+            properties,
+        }
+    } else {
+        let value = property.get("value").ok_or_else(|| {
+            Error::MalformedAttributes(format!("property '{}' is missing a value", name))
+        })?;
+        PropertyValue::from_json(t, value)?
+            .with_enum_type(json_field_opt(property, property_type_key)?)
+    };
+    Ok((name, value))
+}
+
+/// Class members don't carry their type in JSON files, so it is inferred from the JSON value
+/// itself. As such, colors, files and object references are read as strings and integers.
+#[cfg(feature = "json")]
+fn parse_class_members_json(members: &JsonObject) -> Result<Properties> {
+    use serde_json::Value;
+
+    members
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::Bool(b) => PropertyValue::BoolValue(*b),
+                Value::Number(n) if n.is_i64() || n.is_u64() => {
+                    PropertyValue::new("int".to_owned(), n.to_string())?
+                }
+                Value::Number(n) => PropertyValue::new("float".to_owned(), n.to_string())?,
+                Value::String(s) => PropertyValue::StringValue(s.clone()),
+                Value::Object(members) => PropertyValue::ClassValue {
+                    property_type: String::new(),
+                    properties: parse_class_members_json(members)?,
+                },
+                _ => {
+                    return Err(Error::InvalidPropertyValue {
+                        description: format!("unsupported value for class member '{}'", name),
+                    })
+                }
+            };
+            Ok((name.clone(), value))
+        })
+        .collect()
+}
+
+/// Writes a `<properties>` element, unless there are no properties to write. Properties are sorted
+/// by name so that the output doesn't depend on the order of the [`HashMap`].
+pub(crate) fn write_properties(
+    writer: &mut XmlWriter<impl Write>,
+    properties: &Properties,
+) -> Result<()> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+
+    let mut names: Vec<&String> = properties.keys().collect();
+    names.sort();
+
+    writer.start("properties", &[])?;
+    for name in names {
+        let name_attr = ("name", name.clone());
+        match &properties[name] {
+            PropertyValue::BoolValue(v) => writer.empty(
+                "property",
+                &[
+                    name_attr,
+                    ("type", "bool".to_owned()),
+                    ("value", v.to_string()),
+                ],
+            )?,
+            PropertyValue::FloatValue(v) => writer.empty(
+                "property",
+                &[
+                    name_attr,
+                    ("type", "float".to_owned()),
+                    ("value", v.to_string()),
+                ],
+            )?,
+            PropertyValue::IntValue(v) => writer.empty(
+                "property",
+                &[
+                    name_attr,
+                    ("type", "int".to_owned()),
+                    ("value", v.to_string()),
+                ],
+            )?,
+            PropertyValue::ColorValue(v) => writer.empty(
+                "property",
+                &[
+                    name_attr,
+                    ("type", "color".to_owned()),
+                    ("value", v.to_string()),
+                ],
+            )?,
+            // Multiline strings are stored as the contents of the element instead, since newlines
+            // in attributes don't survive being read back.
+            PropertyValue::StringValue(v) if v.contains('\n') => {
+                writer.start("property", &[name_attr])?;
+                writer.characters(v)?;
+                writer.end()?;
+            }
+            PropertyValue::StringValue(v) => {
+                writer.empty("property", &[name_attr, ("value", v.clone())])?
+            }
+            PropertyValue::FileValue(v) => writer.empty(
+                "property",
+                &[name_attr, ("type", "file".to_owned()), ("value", v.clone())],
+            )?,
+            PropertyValue::ObjectValue(v) => writer.empty(
+                "property",
+                &[
+                    name_attr,
+                    ("type", "object".to_owned()),
+                    ("value", v.to_string()),
+                ],
+            )?,
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => {
+                let mut attrs = vec![name_attr, ("type", "class".to_owned())];
+                if !property_type.is_empty() {
+                    attrs.push(("propertytype", property_type.clone()));
+                }
+                writer.start("property", &attrs)?;
+                write_properties(writer, properties)?;
+                writer.end()?;
+            }
+            PropertyValue::EnumValue {
+                property_type,
+                value,
+                ..
+            } => {
+                let mut attrs = vec![name_attr];
+                if let RawEnumValue::Int(_) = value {
+                    attrs.push(("type", "int".to_owned()));
+                }
+                attrs.push(("propertytype", property_type.clone()));
+                attrs.push(("value", value.to_string()));
+                writer.empty("property", &attrs)?
+            }
+        }
+    }
+    writer.end()
+}
+
+/// Adds a `properties` array to a JSON object, unless there are no properties to write. Like with
+/// XML, properties are sorted by name.
+#[cfg(feature = "json")]
+pub(crate) fn write_properties_json(object: &mut JsonObject, properties: &Properties) {
+    if properties.is_empty() {
+        return;
+    }
+
+    let mut names: Vec<&String> = properties.keys().collect();
+    names.sort();
+
+    let properties = names
+        .into_iter()
+        .map(|name| {
+            let value = &properties[name];
+            let mut property = JsonObject::new();
+            property.insert("name".to_owned(), name.clone().into());
+            property.insert("type".to_owned(), value.json_type_name().into());
+            if let PropertyValue::ClassValue { property_type, .. }
+            | PropertyValue::EnumValue { property_type, .. } = value
+            {
+                property.insert("propertytype".to_owned(), property_type.clone().into());
+            }
+            property.insert("value".to_owned(), value.to_json());
+            serde_json::Value::Object(property)
+        })
+        .collect::<Vec<_>>();
+    object.insert("properties".to_owned(), properties.into());
+}
+
 /// Checks if there is a properties tag next in the parser. Will consume any whitespace or comments.
 fn has_properties_tag_next(parser: &mut impl Iterator<Item = XmlEventResult>) -> bool {
     let mut peekable = parser.by_ref().peekable();
@@ -0,0 +1,371 @@
+use std::{collections::hash_map, convert::TryFrom, fmt, str::FromStr};
+
+use serde::{
+    de::{
+        self,
+        value::{BorrowedStrDeserializer, SeqDeserializer},
+        DeserializeSeed, IntoDeserializer, MapAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use crate::{Color, Properties, PropertyValue, RawEnumValue};
+
+/// Deserializes a set of custom properties into any type that implements [`Deserialize`], such as
+/// a struct with a field for each property. See [`PropertyValueDeserializer`] for how each value
+/// is deserialized.
+///
+/// ## Example
+/// ```
+/// # use serde::Deserialize;
+/// # use tiled::Loader;
+/// #
+/// #[derive(Deserialize)]
+/// struct Inner {
+///     target: u32,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Stats {
+///     health: i32,
+///     speed: f32,
+///     inner: Inner,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Level {
+///     stats: Stats,
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map = Loader::new().load_tmx_map("assets/tiled_project_classes.tmx")?;
+///
+/// let level: Level = tiled::from_properties(&map.properties)?;
+/// assert_eq!(level.stats.health, 50);
+/// assert_eq!(level.stats.speed, 3.0);
+/// assert_eq!(level.stats.inner.target, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_properties<'de, T: Deserialize<'de>>(
+    properties: &'de Properties,
+) -> Result<T, PropertyDeserializeError> {
+    T::deserialize(PropertiesDeserializer::new(properties))
+}
+
+/// Deserializes a single custom property value into any type that implements [`Deserialize`].
+/// See [`PropertyValueDeserializer`] for how it is deserialized.
+pub fn from_property_value<'de, T: Deserialize<'de>>(
+    value: &'de PropertyValue,
+) -> Result<T, PropertyDeserializeError> {
+    T::deserialize(PropertyValueDeserializer::new(value))
+}
+
+/// An error that occurred while deserializing custom properties.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PropertyDeserializeError {
+    /// The property the error is about, if any. Members of class properties are named after the
+    /// path to them, with the names of the properties along it separated by dots, such as
+    /// `"stats.health"` for the `health` member of a `stats` property.
+    pub property: Option<String>,
+    /// A description of what went wrong.
+    pub message: String,
+}
+
+impl PropertyDeserializeError {
+    /// Attributes the error to the property at `path`, unless it is already about a property
+    /// within it.
+    fn at(mut self, path: &Option<String>) -> Self {
+        if self.property.is_none() {
+            self.property = path.clone();
+        }
+        self
+    }
+}
+
+impl fmt::Display for PropertyDeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.property {
+            Some(property) => write!(f, "invalid property `{}`: {}", property, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for PropertyDeserializeError {}
+
+impl de::Error for PropertyDeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            property: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// A [`Deserializer`] for a set of custom properties, which are deserialized as a map from their
+/// names to their values. Use [`from_properties`] to deserialize them directly.
+#[derive(Debug, Clone, Copy)]
+pub struct PropertiesDeserializer<'de> {
+    properties: &'de Properties,
+}
+
+impl<'de> PropertiesDeserializer<'de> {
+    /// Creates a deserializer for the given properties.
+    pub fn new(properties: &'de Properties) -> Self {
+        Self { properties }
+    }
+}
+
+impl<'de> Deserializer<'de> for PropertiesDeserializer<'de> {
+    type Error = PropertyDeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(PropertiesAccess::new(self.properties, None))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// A [`Deserializer`] for a single custom property value. Use [`from_property_value`] to
+/// deserialize one directly.
+///
+/// Values are deserialized as follows:
+/// - Booleans, integers and floats as themselves. Integers can also be deserialized as any other
+///   number type they fit in.
+/// - Strings and files as strings, which can be borrowed from the value.
+/// - Colors as strings in the `#RRGGBB` or `#AARRGGBB` format, which [`Color`] is deserialized
+///   from.
+/// - Objects as the `u32` ID of the object they reference, which is 0 if unset. Unset references
+///   are `None` when deserialized as an [`Option`]. See
+///   [`Map::object_by_id`](crate::Map::object_by_id) to look it up.
+/// - Classes as a map from the names of their members to their values, such as a struct.
+/// - Enums as the value stored in the file, unless deserialized as a Rust enum with unit variants
+///   or as a sequence of those:
+///   - A Rust enum is given the name of the value when it is known (see
+///     [`PropertyValue::EnumValue`]), and otherwise the stored string or the index stored as an
+///     integer. Flag enums can only be deserialized as one if a single flag is set.
+///   - A sequence is given the names of the set flags of a flag enum, or of the value of a regular
+///     enum. Without knowing them, flags stored as a string are split at commas.
+///
+///   Plain string properties can also be deserialized as Rust enums.
+#[derive(Debug, Clone)]
+pub struct PropertyValueDeserializer<'de> {
+    value: &'de PropertyValue,
+    /// The path to the value, which errors are attributed to.
+    path: Option<String>,
+}
+
+impl<'de> PropertyValueDeserializer<'de> {
+    /// Creates a deserializer for the given property value.
+    pub fn new(value: &'de PropertyValue) -> Self {
+        Self { value, path: None }
+    }
+}
+
+impl<'de> Deserializer<'de> for PropertyValueDeserializer<'de> {
+    type Error = PropertyDeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result: Result<V::Value, Self::Error> = match self.value {
+            PropertyValue::BoolValue(v) => visitor.visit_bool(*v),
+            PropertyValue::FloatValue(v) => visitor.visit_f32(*v),
+            PropertyValue::IntValue(v) => visitor.visit_i32(*v),
+            PropertyValue::ColorValue(v) => visitor.visit_string(v.to_string()),
+            PropertyValue::StringValue(v) | PropertyValue::FileValue(v) => {
+                visitor.visit_borrowed_str(v)
+            }
+            PropertyValue::ObjectValue(v) => visitor.visit_u32(*v),
+            PropertyValue::ClassValue { properties, .. } => {
+                visitor.visit_map(PropertiesAccess::new(properties, self.path.clone()))
+            }
+            PropertyValue::EnumValue { value, .. } => match value {
+                RawEnumValue::String(v) => visitor.visit_borrowed_str(v),
+                RawEnumValue::Int(v) => visitor.visit_i32(*v),
+            },
+        };
+        result.map_err(|err| err.at(&self.path))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // Class members that aren't set are missing, which serde already treats as `None` for
+        // struct fields. Object references use 0 for "no object".
+        match self.value {
+            PropertyValue::ObjectValue(0) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let result: Result<V::Value, Self::Error> = match self.value {
+            PropertyValue::EnumValue {
+                property_type,
+                names: Some(names),
+                ..
+            } => match names.as_slice() {
+                [variant] => visitor.visit_enum(variant.as_str().into_deserializer()),
+                _ => Err(de::Error::custom(format!(
+                    "expected a single value of enum `{}`, found {}",
+                    property_type,
+                    names.len()
+                ))),
+            },
+            PropertyValue::EnumValue {
+                value: RawEnumValue::String(v),
+                ..
+            }
+            | PropertyValue::StringValue(v) => visitor.visit_enum(v.as_str().into_deserializer()),
+            PropertyValue::EnumValue {
+                value: RawEnumValue::Int(v),
+                ..
+            } => match u32::try_from(*v) {
+                Ok(index) => visitor.visit_enum(index.into_deserializer()),
+                Err(_) => Err(de::Error::invalid_value(
+                    de::Unexpected::Signed(*v as i64),
+                    &"a variant index",
+                )),
+            },
+            _ => return self.deserialize_any(visitor),
+        };
+        result.map_err(|err| err.at(&self.path))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result: Result<V::Value, Self::Error> = match self.value {
+            PropertyValue::EnumValue {
+                names: Some(names), ..
+            } => visit_names(names.iter().map(String::as_str), visitor),
+            PropertyValue::EnumValue {
+                value: RawEnumValue::String(v),
+                ..
+            } => visit_names(v.split(',').filter(|name| !name.is_empty()), visitor),
+            _ => return self.deserialize_any(visitor),
+        };
+        result.map_err(|err| err.at(&self.path))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct tuple tuple_struct map struct identifier
+    }
+}
+
+/// Has `visitor` visit a sequence of enum value names.
+fn visit_names<'de, V: Visitor<'de>>(
+    names: impl Iterator<Item = &'de str>,
+    visitor: V,
+) -> Result<V::Value, PropertyDeserializeError> {
+    let mut seq = SeqDeserializer::<_, PropertyDeserializeError>::new(names);
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+/// Walks through a set of properties as a map from their names to their values.
+struct PropertiesAccess<'de> {
+    properties: hash_map::Iter<'de, String, PropertyValue>,
+    /// The path to the class property the properties are the members of, if any.
+    parent_path: Option<String>,
+    /// The value of the last property whose name was deserialized.
+    value: Option<PropertyValueDeserializer<'de>>,
+}
+
+impl<'de> PropertiesAccess<'de> {
+    fn new(properties: &'de Properties, parent_path: Option<String>) -> Self {
+        Self {
+            properties: properties.iter(),
+            parent_path,
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for PropertiesAccess<'de> {
+    type Error = PropertyDeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let (name, value) = match self.properties.next() {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+        let path = match &self.parent_path {
+            Some(parent_path) => format!("{}.{}", parent_path, name),
+            None => name.clone(),
+        };
+        self.value = Some(PropertyValueDeserializer {
+            value,
+            path: Some(path),
+        });
+        seed.deserialize(BorrowedStrDeserializer::new(name.as_str()))
+            .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom(
+                "property value requested before its name",
+            )),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.properties.len())
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    /// Deserializes a color from a string in the `#RRGGBB` or `#AARRGGBB` format, with or without
+    /// the leading `#`, which is how color properties are deserialized.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+
+        impl Visitor<'_> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color in the `#RRGGBB` or `#AARRGGBB` format")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Color, E> {
+                Color::from_str(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(ColorVisitor)
+    }
+}
@@ -1,19 +1,24 @@
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use xml::EventReader;
 use xml::{attribute::OwnedAttribute, reader::XmlEvent};
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_object, read_json};
 use crate::{
-    util::*, EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache,
-    ResourceReader, Result, Tileset,
+    util::*,
+    write::{relative_path, WriteContext, XmlWriter},
+    Error, Gid, MapTilesetGid, ObjectData, Project, PropertyValue, ResourceCache, ResourceReader,
+    Result, Tileset,
 };
 
 /// A template, consisting of an object and a tileset
 ///
 /// Templates define a tileset and object data to use for an object that can be shared between multiple objects and
 /// maps.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Template {
     /// The path first used in a [`ResourceReader`] to load this template.
     pub source: PathBuf,
@@ -24,11 +29,80 @@ pub struct Template {
 }
 
 impl Template {
+    /// Returns the path a file property of this template's object points to, relative to the
+    /// same directory as [`Template::source`], or [`None`] if the value isn't a file property or is
+    /// unset.
+    pub fn resolve_file_property(&self, value: &PropertyValue) -> Option<PathBuf> {
+        value.file_path(&self.source)
+    }
+
+    /// Fills in the class and enum properties of the template's object and tileset according to
+    /// `project`. Templates are shared with the loader's cache, so they are only replaced by a
+    /// copy if anything actually changes.
+    pub(crate) fn with_project(self: Arc<Self>, project: &Project) -> Arc<Self> {
+        let mut filled = Template::clone(&self);
+        filled.object.apply_project(project);
+        if let Some(tileset) = &mut filled.tileset {
+            let mut filled_tileset = Tileset::clone(tileset);
+            filled_tileset.apply_project(project);
+            if filled_tileset != **tileset {
+                *tileset = Arc::new(filled_tileset);
+            }
+        }
+        if filled == *self {
+            self
+        } else {
+            Arc::new(filled)
+        }
+    }
+
+    /// Writes this template to `writer` in the TX format.
+    ///
+    /// Paths to images and to the template's tileset are written relative to the directory of
+    /// [`Self::source`], so it should be set to the location the template is being written to
+    /// beforehand. Like with maps, the tileset is only written embedded if it was embedded in the
+    /// template to begin with.
+    pub fn write_tx(&self, writer: impl Write) -> Result<()> {
+        let mut writer = XmlWriter::new(writer);
+        let ctx = WriteContext {
+            base_path: self.source.parent().ok_or(Error::PathIsNotFile)?,
+            first_gids: &[],
+            map_size: (0, 0),
+            compression_level: -1,
+            tile_encoding: None,
+        };
+
+        writer.start("template", &[])?;
+        if let Some(tileset) = &self.tileset {
+            if tileset.source == self.source {
+                tileset.write_xml(&mut writer, Some(Gid(1)), &ctx)?;
+            } else {
+                writer.empty(
+                    "tileset",
+                    &[
+                        ("firstgid", "1".to_owned()),
+                        ("source", relative_path(ctx.base_path, &tileset.source)),
+                    ],
+                )?;
+            }
+        }
+        self.object.write_xml(&mut writer, &ctx)?;
+        writer.end()
+    }
+
     pub(crate) fn parse_template(
         path: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Arc<Template>> {
+        #[cfg(feature = "json")]
+        {
+            if crate::parse::is_json(path, reader)? {
+                return Self::parse_json_template(path, preserve_unknown, reader, cache);
+            }
+        }
+
         // Open the template file
         let file = reader
             .read_from(path)
@@ -48,6 +122,7 @@ impl Template {
                     let template = Self::parse_external_template(
                         &mut template_parser.into_iter(),
                         path,
+                        preserve_unknown,
                         reader,
                         cache,
                     )?;
@@ -66,6 +141,7 @@ impl Template {
     fn parse_external_template(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         template_path: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Arc<Template>> {
@@ -75,29 +151,14 @@ impl Template {
 
         parse_tag!(parser, "template", {
             "object" => |attrs| {
-                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache)?);
+                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, preserve_unknown, reader, cache)?);
                 Ok(())
             },
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, reader, cache)?;
-                match res.result_type {
-                    EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        tileset = Some(if let Some(ts) = cache.get_tileset(&tileset_path) {
-                            ts
-                        } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
-                        });
-                    }
-                    EmbeddedParseResultType::Embedded { tileset: embedded_tileset } => {
-                        tileset = Some(Arc::new(embedded_tileset));
-                    },
-                };
-                tileset_gid.push(MapTilesetGid {
-                    tileset: tileset.clone().unwrap(),
-                    first_gid: res.first_gid,
-                });
+                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, preserve_unknown, reader, cache)?
+                    .into_map_tileset(preserve_unknown, reader, cache)?;
+                tileset = Some(res.tileset.clone());
+                tileset_gid.push(res);
                 Ok(())
             },
         });
@@ -110,4 +171,47 @@ impl Template {
             object,
         }))
     }
+
+    #[cfg(feature = "json")]
+    fn parse_json_template(
+        template_path: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Arc<Template>> {
+        let template = read_json(template_path, reader)?;
+        let template = json_object(&template)?;
+        let mut tileset = None;
+        let mut tileset_gid: Vec<MapTilesetGid> = vec![];
+
+        if let Some(ts) = template.get("tileset") {
+            let res = Tileset::parse_json_in_map(
+                json_object(ts)?,
+                template_path,
+                preserve_unknown,
+                reader,
+                cache,
+            )?
+            .into_map_tileset(preserve_unknown, reader, cache)?;
+            tileset = Some(res.tileset.clone());
+            tileset_gid.push(res);
+        }
+
+        let object = template.get("object").ok_or(Error::TemplateHasNoObject)?;
+        let object = ObjectData::from_json(
+            json_object(object)?,
+            Some(&tileset_gid),
+            tileset.clone(),
+            template_path.parent().ok_or(Error::PathIsNotFile)?,
+            preserve_unknown,
+            reader,
+            cache,
+        )?;
+
+        Ok(Arc::new(Template {
+            source: template_path.to_owned(),
+            tileset,
+            object,
+        }))
+    }
 }
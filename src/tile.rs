@@ -1,15 +1,23 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Write, path::Path};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
-    animation::{parse_animation, Frame},
+    animation::{parse_animation, write_animation, Frame},
     error::Error,
     image::Image,
     layers::ObjectLayerData,
-    properties::{parse_properties, Properties},
+    properties::{parse_properties, write_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
-    ResourceCache, ResourceReader, Result, Tileset,
+    write::{WriteContext, XmlWriter},
+    ResourceCache, ResourceReader, Result, Tileset, UnknownXml, XmlElement,
+};
+#[cfg(feature = "json")]
+use crate::{
+    animation::{parse_animation_json, write_animation_json},
+    parse::json::{json_array, json_field, json_field_opt, json_object, JsonObject},
+    properties::{parse_properties_json, write_properties_json},
+    write::json_f32,
 };
 
 /// A tile ID, local to a tileset.
@@ -20,6 +28,16 @@ pub type TileId = u32;
 pub struct TileData {
     /// The image of the tile. Only set when the tile is part of an "image collection" tileset.
     pub image: Option<Image>,
+    /// The X coordinate of the part of [`Self::image`] used by this tile, in pixels.
+    pub x: u32,
+    /// The Y coordinate of the part of [`Self::image`] used by this tile, in pixels.
+    pub y: u32,
+    /// The width of the part of [`Self::image`] used by this tile, in pixels, or [`None`] to use
+    /// the width of the image.
+    pub width: Option<u32>,
+    /// The height of the part of [`Self::image`] used by this tile, in pixels, or [`None`] to use
+    /// the height of the image.
+    pub height: Option<u32>,
     /// The custom properties of this tile.
     pub properties: Properties,
     /// The collision shapes of this tile.
@@ -30,6 +48,9 @@ pub struct TileData {
     pub user_type: Option<String>,
     /// The probability of this tile.
     pub probability: f32,
+    /// The attributes and elements of the `<tile>` element that aren't recognized, if they were
+    /// preserved when loading it.
+    pub unknown: UnknownXml,
 }
 
 /// Points to a tile belonging to a tileset.
@@ -64,23 +85,30 @@ impl TileData {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         path_relative_to: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<(TileId, TileData)> {
-        let ((user_type, user_class, probability), id) = get_attrs!(
+        let ((user_type, user_class, probability, x, y, width, height), id, unknown_attrs) = get_attrs!(
             for v in attrs {
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
                 Some("probability") => probability ?= v.parse(),
+                Some("x") => x ?= v.parse::<u32>(),
+                Some("y") => y ?= v.parse::<u32>(),
+                Some("width") => width ?= v.parse::<u32>(),
+                Some("height") => height ?= v.parse::<u32>(),
                 "id" => id ?= v.parse::<u32>(),
             }
-            ((user_type, user_class, probability), id)
+            unknown => unknown_attrs,
+            ((user_type, user_class, probability, x, y, width, height), id, unknown_attrs)
         );
         let user_type = user_type.or(user_class);
         let mut image = Option::None;
         let mut properties = HashMap::new();
         let mut objectgroup = None;
         let mut animation = None;
+        let mut unknown = UnknownXml::with_attributes(unknown_attrs, preserve_unknown);
         parse_tag!(parser, "tile", {
             "image" => |attrs| {
                 image = Some(Image::new(parser, attrs, path_relative_to)?);
@@ -93,23 +121,162 @@ impl TileData {
             "objectgroup" => |attrs| {
                 // Tile objects are not allowed within tile object groups, so we can pass None as the
                 // tilesets vector
-                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, reader, cache)?.0);
+                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, preserve_unknown, reader, cache)?.0);
                 Ok(())
             },
             "animation" => |_| {
                 animation = Some(parse_animation(parser)?);
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown.elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
         Ok((
             id,
             TileData {
                 image,
+                x: x.unwrap_or(0),
+                y: y.unwrap_or(0),
+                width,
+                height,
                 properties,
                 collision: objectgroup,
                 animation,
                 user_type,
                 probability: probability.unwrap_or(1.0),
+                unknown,
+            },
+        ))
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        id: TileId,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let mut attrs = vec![("id", id.to_string())];
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("type", user_type.clone()));
+        }
+        if self.probability != 1.0 {
+            attrs.push(("probability", self.probability.to_string()));
+        }
+        if self.x != 0 {
+            attrs.push(("x", self.x.to_string()));
+        }
+        if self.y != 0 {
+            attrs.push(("y", self.y.to_string()));
+        }
+        if let Some(width) = self.width {
+            attrs.push(("width", width.to_string()));
+        }
+        if let Some(height) = self.height {
+            attrs.push(("height", height.to_string()));
+        }
+        self.unknown.extend_attributes(&mut attrs);
+
+        writer.start_with_unknown("tile", &attrs, &self.unknown.elements)?;
+        write_properties(writer, &self.properties)?;
+        if let Some(image) = &self.image {
+            image.write_xml(writer, ctx.base_path)?;
+        }
+        if let Some(collision) = &self.collision {
+            collision.write_xml(writer, Vec::new(), &Properties::new(), &[], ctx)?;
+        }
+        if let Some(animation) = &self.animation {
+            write_animation(writer, animation)?;
+        }
+        writer.end()
+    }
+
+    /// Converts this tile into a JSON object, as found in the `tiles` array of tilesets.
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self, id: TileId, ctx: &WriteContext) -> Result<JsonObject> {
+        let mut tile = JsonObject::new();
+        tile.insert("id".to_owned(), id.into());
+        if let Some(user_type) = &self.user_type {
+            tile.insert("type".to_owned(), user_type.clone().into());
+        }
+        if self.probability != 1.0 {
+            tile.insert("probability".to_owned(), json_f32(self.probability));
+        }
+        if self.x != 0 {
+            tile.insert("x".to_owned(), self.x.into());
+        }
+        if self.y != 0 {
+            tile.insert("y".to_owned(), self.y.into());
+        }
+        if let Some(width) = self.width {
+            tile.insert("width".to_owned(), width.into());
+        }
+        if let Some(height) = self.height {
+            tile.insert("height".to_owned(), height.into());
+        }
+        write_properties_json(&mut tile, &self.properties);
+        if let Some(image) = &self.image {
+            image.write_json(&mut tile, ctx.base_path)?;
+        }
+        if let Some(collision) = &self.collision {
+            let mut group = JsonObject::new();
+            group.insert("type".to_owned(), "objectgroup".into());
+            collision.write_json(&mut group, ctx);
+            tile.insert("objectgroup".to_owned(), group.into());
+        }
+        if let Some(animation) = &self.animation {
+            tile.insert("animation".to_owned(), write_animation_json(animation));
+        }
+        Ok(tile)
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(
+        tile: &JsonObject,
+        path_relative_to: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<(TileId, TileData)> {
+        let id = json_field(tile, "id")?;
+        let user_type = json_field_opt::<String>(tile, "type")?;
+        let user_type = user_type.or(json_field_opt(tile, "class")?);
+        let collision = match tile.get("objectgroup") {
+            // Tile objects are not allowed within tile object groups, so we can pass None as the
+            // tilesets vector
+            Some(group) => Some(ObjectLayerData::from_json(
+                json_object(group)?,
+                None,
+                None,
+                path_relative_to,
+                preserve_unknown,
+                reader,
+                cache,
+            )?),
+            None => None,
+        };
+        let animation = if tile.contains_key("animation") {
+            Some(parse_animation_json(json_array(tile, "animation")?)?)
+        } else {
+            None
+        };
+
+        Ok((
+            id,
+            TileData {
+                image: Image::from_json(tile, path_relative_to)?,
+                x: json_field_opt(tile, "x")?.unwrap_or(0),
+                y: json_field_opt(tile, "y")?.unwrap_or(0),
+                width: json_field_opt(tile, "width")?,
+                height: json_field_opt(tile, "height")?,
+                properties: parse_properties_json(tile)?,
+                collision,
+                animation,
+                user_type,
+                probability: json_field_opt(tile, "probability")?.unwrap_or(1.0),
+                unknown: UnknownXml::default(),
             },
         ))
     }
@@ -1,14 +1,32 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
 use xml::attribute::OwnedAttribute;
 
 use crate::error::{Error, Result};
 use crate::image::Image;
-use crate::properties::{parse_properties, Properties};
+#[cfg(feature = "json")]
+use crate::parse::json::{
+    json_array, json_field, json_field_opt, json_object, json_parse_opt, JsonObject,
+};
+use crate::properties::{parse_properties, write_properties, Properties, PropertyValue};
+#[cfg(feature = "json")]
+use crate::properties::{parse_properties_json, write_properties_json};
 use crate::tile::TileData;
-use crate::{util::*, Gid, InvalidTilesetError, ResourceCache, ResourceReader, Tile, TileId};
+#[cfg(feature = "json")]
+use crate::write::write_json_document;
+use crate::write::{WriteContext, XmlWriter};
+use crate::{
+    util::*, Gid, InvalidTilesetError, MapTilesetGid, Orientation, Project, ResourceCache,
+    ResourceReader, Tile, TileId, UnknownXml, XmlElement,
+};
 
+mod transformations;
+pub use transformations::*;
 mod wangset;
 pub use wangset::*;
 
@@ -50,6 +68,18 @@ pub struct Tileset {
     pub offset_x: i32,
     /// The y-offset to be used when drawing tiles of this tileset.
     pub offset_y: i32,
+    /// The point of a tile object's image that is placed at the object's position.
+    pub object_alignment: ObjectAlignment,
+    /// The size tiles of this tileset are drawn at on tile layers.
+    pub tile_render_size: TileRenderSize,
+    /// How tiles of this tileset are scaled when drawn at a size other than their own.
+    pub fill_mode: FillMode,
+    /// The grid used by Tiled to show tile overlays such as collision shapes, if it differs from
+    /// the tile size.
+    pub grid: Option<Grid>,
+    /// The ways Tiled is allowed to transform the tiles of this tileset when placing them
+    /// automatically.
+    pub transformations: Transformations,
 
     /// A tileset can either:
     /// * have a single spritesheet `image` in `tileset` ("regular" tileset);
@@ -71,6 +101,10 @@ pub struct Tileset {
 
     /// The custom tileset type, arbitrarily set by the user.
     pub user_type: Option<String>,
+
+    /// The attributes and elements of the `<tileset>` element that aren't recognized, if they
+    /// were preserved when loading it.
+    pub unknown: UnknownXml,
 }
 
 pub(crate) enum EmbeddedParseResultType {
@@ -83,6 +117,40 @@ pub(crate) struct EmbeddedParseResult {
     pub result_type: EmbeddedParseResultType,
 }
 
+impl EmbeddedParseResult {
+    /// Obtains the tileset this result refers to, loading it if it is an external tileset that
+    /// isn't present in the cache yet.
+    pub(crate) fn into_map_tileset(
+        self,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<MapTilesetGid> {
+        let tileset = match self.result_type {
+            EmbeddedParseResultType::ExternalReference { tileset_path } => {
+                if let Some(ts) = cache.get_tileset(&tileset_path) {
+                    ts
+                } else {
+                    let tileset = Arc::new(crate::parse::parse_tileset(
+                        &tileset_path,
+                        preserve_unknown,
+                        reader,
+                        cache,
+                    )?);
+                    cache.insert_tileset(tileset_path.clone(), tileset.clone());
+                    tileset
+                }
+            }
+            EmbeddedParseResultType::Embedded { tileset } => Arc::new(tileset),
+        };
+
+        Ok(MapTilesetGid {
+            first_gid: self.first_gid,
+            tileset,
+        })
+    }
+}
+
 /// Internal structure for holding mid-parse information.
 struct TilesetProperties {
     spacing: Option<u32>,
@@ -93,11 +161,24 @@ struct TilesetProperties {
     user_type: Option<String>,
     tile_width: u32,
     tile_height: u32,
+    object_alignment: ObjectAlignment,
+    tile_render_size: TileRenderSize,
+    fill_mode: FillMode,
     /// The root all non-absolute paths contained within the tileset are relative to.
     root_path: PathBuf,
 }
 
 impl Tileset {
+    /// Returns the path a file property of this tileset or of anything within it points to,
+    /// relative to the same directory as [`Tileset::source`], or [`None`] if the value isn't a
+    /// file property or is unset.
+    ///
+    /// Tilesets embedded in a map have the map as their source, so this works the same for them
+    /// as [`Map::resolve_file_property`](crate::Map::resolve_file_property).
+    pub fn resolve_file_property(&self, value: &PropertyValue) -> Option<PathBuf> {
+        value.file_path(&self.source)
+    }
+
     /// Gets the tile with the specified ID from the tileset.
     #[inline]
     pub fn get_tile(&self, id: TileId) -> Option<Tile> {
@@ -111,6 +192,76 @@ impl Tileset {
             .iter()
             .map(move |(id, data)| (*id, Tile::new(self, data)))
     }
+
+    /// Returns the part of an image that the tile with the specified ID is drawn from, or [`None`]
+    /// if the tile doesn't exist.
+    ///
+    /// For tilesets with a single image, this is worked out from the tile size, margin, spacing
+    /// and columns of the tileset. For image collection tilesets, it is the part of the tile's own
+    /// image given by [`TileData::x`], [`TileData::y`], [`TileData::width`] and
+    /// [`TileData::height`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, SourceRect};
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// // The tileset has 14 columns of 32x32 tiles.
+    /// assert_eq!(
+    ///     tileset.tile_source_rect(15),
+    ///     Some(SourceRect {
+    ///         x: 32,
+    ///         y: 32,
+    ///         width: 32,
+    ///         height: 32,
+    ///     })
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_source_rect(&self, id: TileId) -> Option<SourceRect> {
+        let tile = self.tiles.get(&id)?;
+        if let Some(image) = &tile.image {
+            return Some(SourceRect {
+                x: tile.x,
+                y: tile.y,
+                width: tile.width.unwrap_or(image.width as u32),
+                height: tile.height.unwrap_or(image.height as u32),
+            });
+        }
+
+        if self.image.is_none() || self.columns == 0 {
+            return None;
+        }
+        let column = id % self.columns;
+        let row = id / self.columns;
+        Some(SourceRect {
+            x: self.margin + column * (self.tile_width + self.spacing),
+            y: self.margin + row * (self.tile_height + self.spacing),
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+
+    /// Fills in the default members of every class property and decodes every enum property in
+    /// the tileset, its tiles and its Wang sets.
+    pub(crate) fn apply_project(&mut self, project: &Project) {
+        project.apply(&mut self.properties);
+        for tile in self.tiles.values_mut() {
+            project.apply(&mut tile.properties);
+            if let Some(collision) = &mut tile.collision {
+                collision.apply_project(project);
+            }
+        }
+        for wang_set in &mut self.wang_sets {
+            project.apply(&mut wang_set.properties);
+            for wang_color in &mut wang_set.wang_colors {
+                project.apply(&mut wang_color.properties);
+            }
+        }
+    }
 }
 
 impl Tileset {
@@ -118,28 +269,43 @@ impl Tileset {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
         path: &Path, // Template or Map file
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<EmbeddedParseResult> {
-        Tileset::parse_xml_embedded(parser, attrs, path, reader, cache).or_else(|err| {
-            if matches!(err, Error::MalformedAttributes(_)) {
-                Tileset::parse_xml_reference(attrs, path)
-            } else {
-                Err(err)
-            }
-        })
+        Tileset::parse_xml_embedded(parser, attrs, path, preserve_unknown, reader, cache).or_else(
+            |err| {
+                if matches!(err, Error::MalformedAttributes(_)) {
+                    Tileset::parse_xml_reference(attrs, path)
+                } else {
+                    Err(err)
+                }
+            },
+        )
     }
 
     fn parse_xml_embedded(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
         path: &Path, // Template or Map file
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<EmbeddedParseResult> {
         let (
-            (spacing, margin, columns, name, user_type, user_class),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                object_alignment,
+                tile_render_size,
+                fill_mode,
+            ),
             (tilecount, first_gid, tile_width, tile_height),
+            unknown_attrs,
         ) = get_attrs!(
            for v in attrs {
             Some("spacing") => spacing ?= v.parse(),
@@ -148,13 +314,17 @@ impl Tileset {
             Some("name") => name = v,
             Some("type") => user_type ?= v.parse(),
             Some("class") => user_class ?= v.parse(),
+            Some("objectalignment") => object_alignment ?= v.parse::<ObjectAlignment>(),
+            Some("tilerendersize") => tile_render_size ?= v.parse::<TileRenderSize>(),
+            Some("fillmode") => fill_mode ?= v.parse::<FillMode>(),
 
             "tilecount" => tilecount ?= v.parse::<u32>(),
             "firstgid" => first_gid ?= v.parse::<u32>().map(Gid),
             "tilewidth" => tile_width ?= v.parse::<u32>(),
             "tileheight" => tile_height ?= v.parse::<u32>(),
            }
-           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height))
+           unknown => unknown_attrs,
+           ((spacing, margin, columns, name, user_type, user_class, object_alignment, tile_render_size, fill_mode), (tilecount, first_gid, tile_width, tile_height), unknown_attrs)
         );
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
@@ -172,7 +342,12 @@ impl Tileset {
                 tilecount,
                 tile_height,
                 tile_width,
+                object_alignment: object_alignment.unwrap_or_default(),
+                tile_render_size: tile_render_size.unwrap_or_default(),
+                fill_mode: fill_mode.unwrap_or_default(),
             },
+            UnknownXml::with_attributes(unknown_attrs, preserve_unknown),
+            preserve_unknown,
             reader,
             cache,
         )
@@ -206,12 +381,24 @@ impl Tileset {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
         path: &Path,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Tileset> {
         let (
-            (spacing, margin, columns, name, user_type, user_class),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                object_alignment,
+                tile_render_size,
+                fill_mode,
+            ),
             (tilecount, tile_width, tile_height),
+            unknown_attrs,
         ) = get_attrs!(
             for v in attrs {
                 Some("spacing") => spacing ?= v.parse(),
@@ -220,12 +407,16 @@ impl Tileset {
                 Some("name") => name = v,
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
+                Some("objectalignment") => object_alignment ?= v.parse::<ObjectAlignment>(),
+                Some("tilerendersize") => tile_render_size ?= v.parse::<TileRenderSize>(),
+                Some("fillmode") => fill_mode ?= v.parse::<FillMode>(),
 
                 "tilecount" => tilecount ?= v.parse::<u32>(),
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height))
+            unknown => unknown_attrs,
+            ((spacing, margin, columns, name, user_type, user_class, object_alignment, tile_render_size, fill_mode), (tilecount, tile_width, tile_height), unknown_attrs)
         );
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
@@ -243,7 +434,12 @@ impl Tileset {
                 tilecount,
                 tile_height,
                 tile_width,
+                object_alignment: object_alignment.unwrap_or_default(),
+                tile_render_size: tile_render_size.unwrap_or_default(),
+                fill_mode: fill_mode.unwrap_or_default(),
             },
+            UnknownXml::with_attributes(unknown_attrs, preserve_unknown),
+            preserve_unknown,
             reader,
             cache,
         )
@@ -253,6 +449,8 @@ impl Tileset {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         container_path: PathBuf,
         prop: TilesetProperties,
+        mut unknown: UnknownXml,
+        preserve_unknown: bool,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
     ) -> Result<Tileset> {
@@ -261,6 +459,8 @@ impl Tileset {
         let mut properties = HashMap::new();
         let mut wang_sets = Vec::new();
         let mut offset = (0i32, 0i32);
+        let mut grid = None;
+        let mut transformations = Transformations::default();
 
         parse_tag!(parser, "tileset", {
             "image" => |attrs| {
@@ -271,22 +471,161 @@ impl Tileset {
                 offset = parse_tileoffset(attrs)?;
                 Ok(())
             },
+            "grid" => |attrs| {
+                grid = Some(Grid::new(attrs)?);
+                Ok(())
+            },
+            "transformations" => |attrs| {
+                transformations = Transformations::new(attrs);
+                Ok(())
+            },
             "properties" => |_| {
                 properties = parse_properties(parser)?;
                 Ok(())
             },
             "tile" => |attrs| {
-                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, reader, cache)?;
+                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, preserve_unknown, reader, cache)?;
                 tiles.insert(id, tile);
                 Ok(())
             },
+            // Wang sets are grouped within a <wangsets> element, which has nothing else to it.
+            "wangsets" => |_| Ok(()),
             "wangset" => |attrs| {
                 let set = WangSet::new(parser, attrs)?;
                 wang_sets.push(set);
                 Ok(())
             },
+        } else |name, attrs, position| {
+            if preserve_unknown {
+                unknown.elements.push((position, XmlElement::parse(parser, name, attrs)?));
+            }
+            Ok(())
         });
 
+        Self::finish_parsing(
+            container_path,
+            prop,
+            image,
+            tiles,
+            wang_sets,
+            properties,
+            offset,
+            grid,
+            transformations,
+            unknown,
+        )
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json_in_map(
+        tileset: &JsonObject,
+        path: &Path, // Template or Map file
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<EmbeddedParseResult> {
+        let first_gid = Gid(json_field(tileset, "firstgid")?);
+        let result_type = match json_field_opt::<String>(tileset, "source")? {
+            Some(source) => EmbeddedParseResultType::ExternalReference {
+                tileset_path: path.parent().ok_or(Error::PathIsNotFile)?.join(source),
+            },
+            None => EmbeddedParseResultType::Embedded {
+                tileset: Tileset::parse_json(tileset, path, preserve_unknown, reader, cache)?,
+            },
+        };
+
+        Ok(EmbeddedParseResult {
+            first_gid,
+            result_type,
+        })
+    }
+
+    /// Parses the contents of a JSON tileset. `path` is the file containing it, which is the map
+    /// or template file for embedded tilesets.
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(
+        tileset: &JsonObject,
+        path: &Path,
+        preserve_unknown: bool,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Tileset> {
+        let prop = TilesetProperties {
+            spacing: json_field_opt(tileset, "spacing")?,
+            margin: json_field_opt(tileset, "margin")?,
+            tilecount: json_field(tileset, "tilecount")?,
+            columns: json_field_opt(tileset, "columns")?,
+            name: json_field_opt(tileset, "name")?.unwrap_or_default(),
+            user_type: json_field_opt(tileset, "class")?,
+            tile_width: json_field(tileset, "tilewidth")?,
+            tile_height: json_field(tileset, "tileheight")?,
+            object_alignment: json_parse_opt(tileset, "objectalignment")?.unwrap_or_default(),
+            tile_render_size: json_parse_opt(tileset, "tilerendersize")?.unwrap_or_default(),
+            fill_mode: json_parse_opt(tileset, "fillmode")?.unwrap_or_default(),
+            root_path: path.parent().ok_or(Error::PathIsNotFile)?.to_owned(),
+        };
+
+        let image = Image::from_json(tileset, &prop.root_path)?;
+        let offset = match tileset.get("tileoffset") {
+            Some(offset) => {
+                let offset = json_object(offset)?;
+                (json_field(offset, "x")?, json_field(offset, "y")?)
+            }
+            None => (0, 0),
+        };
+        let grid = match tileset.get("grid") {
+            Some(grid) => Some(Grid::from_json(json_object(grid)?)?),
+            None => None,
+        };
+        let transformations = match tileset.get("transformations") {
+            Some(transformations) => Transformations::from_json(json_object(transformations)?)?,
+            None => Transformations::default(),
+        };
+        let properties = parse_properties_json(tileset)?;
+
+        let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
+        for tile in json_array(tileset, "tiles")? {
+            let (id, tile) = TileData::from_json(
+                json_object(tile)?,
+                &prop.root_path,
+                preserve_unknown,
+                reader,
+                cache,
+            )?;
+            tiles.insert(id, tile);
+        }
+        let wang_sets = json_array(tileset, "wangsets")?
+            .iter()
+            .map(|set| WangSet::from_json(json_object(set)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::finish_parsing(
+            path.to_owned(),
+            prop,
+            image,
+            tiles,
+            wang_sets,
+            properties,
+            offset,
+            grid,
+            transformations,
+            UnknownXml::default(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn finish_parsing(
+        container_path: PathBuf,
+        prop: TilesetProperties,
+        image: Option<Image>,
+        mut tiles: HashMap<TileId, TileData>,
+        wang_sets: Vec<WangSet>,
+        properties: Properties,
+        offset: (i32, i32),
+        grid: Option<Grid>,
+        transformations: Transformations,
+        unknown: UnknownXml,
+    ) -> Result<Tileset> {
         // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
         let is_image_collection_tileset = image.is_none();
 
@@ -320,11 +659,17 @@ impl Tileset {
             columns,
             offset_x: offset.0,
             offset_y: offset.1,
+            object_alignment: prop.object_alignment,
+            tile_render_size: prop.tile_render_size,
+            fill_mode: prop.fill_mode,
+            grid,
+            transformations,
             tilecount: prop.tilecount,
             image,
             tiles,
             wang_sets,
             properties,
+            unknown,
         })
     }
 
@@ -345,6 +690,227 @@ impl Tileset {
     }
 }
 
+impl Tileset {
+    /// Writes this tileset to `writer` in the TSX format.
+    ///
+    /// Paths to images and templates are written relative to the directory of [`Self::source`],
+    /// so it should be set to the location the tileset is being written to beforehand.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// // The tileset image is referenced relative to the new location.
+    /// tileset.source = "assets/folder/tilesheet.tsx".into();
+    /// let mut tsx = Vec::new();
+    /// tileset.write_tsx(&mut tsx)?;
+    /// assert!(String::from_utf8(tsx).unwrap().contains(r#"source="../tilesheet.png""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tsx(&self, writer: impl Write) -> Result<()> {
+        let ctx = WriteContext {
+            base_path: self.source.parent().ok_or(Error::PathIsNotFile)?,
+            first_gids: &[],
+            map_size: (0, 0),
+            compression_level: -1,
+            tile_encoding: None,
+        };
+        self.write_xml(&mut XmlWriter::new(writer), None, &ctx)
+    }
+
+    /// Writes this tileset as a `<tileset>` element. The first GID is only present when the
+    /// tileset is embedded in a map or template.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        first_gid: Option<Gid>,
+        ctx: &WriteContext,
+    ) -> Result<()> {
+        let mut attrs = Vec::new();
+        if let Some(first_gid) = first_gid {
+            attrs.push(("firstgid", first_gid.0.to_string()));
+        }
+        attrs.push(("name", self.name.clone()));
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        attrs.push(("tilewidth", self.tile_width.to_string()));
+        attrs.push(("tileheight", self.tile_height.to_string()));
+        if self.spacing != 0 {
+            attrs.push(("spacing", self.spacing.to_string()));
+        }
+        if self.margin != 0 {
+            attrs.push(("margin", self.margin.to_string()));
+        }
+        attrs.push(("tilecount", self.tilecount.to_string()));
+        attrs.push(("columns", self.columns.to_string()));
+        if self.object_alignment != ObjectAlignment::default() {
+            attrs.push(("objectalignment", self.object_alignment.to_string()));
+        }
+        if self.tile_render_size != TileRenderSize::default() {
+            attrs.push(("tilerendersize", self.tile_render_size.to_string()));
+        }
+        if self.fill_mode != FillMode::default() {
+            attrs.push(("fillmode", self.fill_mode.to_string()));
+        }
+        self.unknown.extend_attributes(&mut attrs);
+
+        writer.start_with_unknown("tileset", &attrs, &self.unknown.elements)?;
+        if self.offset_x != 0 || self.offset_y != 0 {
+            writer.empty(
+                "tileoffset",
+                &[
+                    ("x", self.offset_x.to_string()),
+                    ("y", self.offset_y.to_string()),
+                ],
+            )?;
+        }
+        if let Some(grid) = &self.grid {
+            grid.write_xml(writer)?;
+        }
+        if self.transformations != Transformations::default() {
+            self.transformations.write_xml(writer)?;
+        }
+        if let Some(image) = &self.image {
+            image.write_xml(writer, ctx.base_path)?;
+        }
+        write_properties(writer, &self.properties)?;
+
+        // Tiles without any data of their own are filled in when loading the tileset.
+        let mut tile_ids: Vec<&TileId> = self.tiles.keys().collect();
+        tile_ids.sort();
+        for id in tile_ids {
+            let tile = &self.tiles[id];
+            if *tile != TileData::default() {
+                tile.write_xml(writer, *id, ctx)?;
+            }
+        }
+
+        if !self.wang_sets.is_empty() {
+            writer.start("wangsets", &[])?;
+            for wang_set in &self.wang_sets {
+                wang_set.write_xml(writer)?;
+            }
+            writer.end()?;
+        }
+        writer.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Tileset {
+    /// Writes this tileset to `writer` in the Tiled JSON format (TSJ).
+    ///
+    /// As with [`Tileset::write_tsx`], paths are written relative to the directory of
+    /// [`Self::source`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// let mut tsj = Vec::new();
+    /// tileset.write_tsj(&mut tsj)?;
+    /// assert!(String::from_utf8(tsj).unwrap().contains(r#""image": "tilesheet.png""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_tsj(&self, writer: impl Write) -> Result<()> {
+        let ctx = WriteContext {
+            base_path: self.source.parent().ok_or(Error::PathIsNotFile)?,
+            first_gids: &[],
+            map_size: (0, 0),
+            compression_level: -1,
+            tile_encoding: None,
+        };
+        let mut tileset = self.to_json(None, &ctx)?;
+        tileset.insert("type".to_owned(), "tileset".into());
+        write_json_document(writer, tileset)
+    }
+
+    /// Converts this tileset into a JSON object. The first GID is only present when the tileset is
+    /// embedded in a map.
+    pub(crate) fn to_json(&self, first_gid: Option<Gid>, ctx: &WriteContext) -> Result<JsonObject> {
+        let mut tileset = JsonObject::new();
+        if let Some(first_gid) = first_gid {
+            tileset.insert("firstgid".to_owned(), first_gid.0.into());
+        }
+        tileset.insert("name".to_owned(), self.name.clone().into());
+        if let Some(user_type) = &self.user_type {
+            tileset.insert("class".to_owned(), user_type.clone().into());
+        }
+        tileset.insert("tilewidth".to_owned(), self.tile_width.into());
+        tileset.insert("tileheight".to_owned(), self.tile_height.into());
+        tileset.insert("spacing".to_owned(), self.spacing.into());
+        tileset.insert("margin".to_owned(), self.margin.into());
+        tileset.insert("tilecount".to_owned(), self.tilecount.into());
+        tileset.insert("columns".to_owned(), self.columns.into());
+        if self.object_alignment != ObjectAlignment::default() {
+            tileset.insert(
+                "objectalignment".to_owned(),
+                self.object_alignment.to_string().into(),
+            );
+        }
+        if self.tile_render_size != TileRenderSize::default() {
+            tileset.insert(
+                "tilerendersize".to_owned(),
+                self.tile_render_size.to_string().into(),
+            );
+        }
+        if self.fill_mode != FillMode::default() {
+            tileset.insert("fillmode".to_owned(), self.fill_mode.to_string().into());
+        }
+        if self.offset_x != 0 || self.offset_y != 0 {
+            tileset.insert(
+                "tileoffset".to_owned(),
+                serde_json::json!({ "x": self.offset_x, "y": self.offset_y }),
+            );
+        }
+        if let Some(grid) = &self.grid {
+            tileset.insert("grid".to_owned(), grid.to_json());
+        }
+        if self.transformations != Transformations::default() {
+            tileset.insert("transformations".to_owned(), self.transformations.to_json());
+        }
+        if let Some(image) = &self.image {
+            image.write_json(&mut tileset, ctx.base_path)?;
+        }
+        write_properties_json(&mut tileset, &self.properties);
+
+        // Tiles without any data of their own are filled in when loading the tileset.
+        let mut tile_ids: Vec<&TileId> = self.tiles.keys().collect();
+        tile_ids.sort();
+        let tiles = tile_ids
+            .into_iter()
+            .filter(|id| self.tiles[*id] != TileData::default())
+            .map(|id| {
+                self.tiles[id]
+                    .to_json(*id, ctx)
+                    .map(serde_json::Value::Object)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !tiles.is_empty() {
+            tileset.insert("tiles".to_owned(), tiles.into());
+        }
+
+        if !self.wang_sets.is_empty() {
+            let wang_sets = self
+                .wang_sets
+                .iter()
+                .map(|wang_set| wang_set.to_json().into())
+                .collect::<Vec<serde_json::Value>>();
+            tileset.insert("wangsets".to_owned(), wang_sets.into());
+        }
+        Ok(tileset)
+    }
+}
+
 /// Parse the optional <tileoffset x=... y=.../> tag.
 fn parse_tileoffset(attrs: Vec<OwnedAttribute>) -> Result<(i32, i32)> {
     Ok(get_attrs!(
@@ -355,3 +921,343 @@ fn parse_tileoffset(attrs: Vec<OwnedAttribute>) -> Result<(i32, i32)> {
         (offset_x, offset_y)
     ))
 }
+
+/// A rectangle within an image, in pixels. See [`Tileset::tile_source_rect`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SourceRect {
+    /// The X coordinate of the left edge of the rectangle.
+    pub x: u32,
+    /// The Y coordinate of the top edge of the rectangle.
+    pub y: u32,
+    /// The width of the rectangle.
+    pub width: u32,
+    /// The height of the rectangle.
+    pub height: u32,
+}
+
+/// The point of a tile object's image that is placed at the object's position. Also see
+/// [`Object::tile_placement`](crate::Object::tile_placement).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[allow(missing_docs)]
+pub enum ObjectAlignment {
+    /// Bottom left on orthogonal maps and bottom on isometric maps, for compatibility with older
+    /// versions of Tiled.
+    #[default]
+    Unspecified,
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ObjectAlignment {
+    /// Returns the position of the aligned point within a rectangle of the given size, relative to
+    /// its top-left corner, on a map with the given orientation.
+    pub(crate) fn anchor(self, orientation: Orientation, width: f32, height: f32) -> (f32, f32) {
+        let alignment = match (self, orientation) {
+            (ObjectAlignment::Unspecified, Orientation::Isometric) => ObjectAlignment::Bottom,
+            (ObjectAlignment::Unspecified, _) => ObjectAlignment::BottomLeft,
+            (alignment, _) => alignment,
+        };
+        let x = match alignment {
+            ObjectAlignment::TopLeft | ObjectAlignment::Left | ObjectAlignment::BottomLeft => 0.0,
+            ObjectAlignment::TopRight | ObjectAlignment::Right | ObjectAlignment::BottomRight => {
+                width
+            }
+            _ => width / 2.0,
+        };
+        let y = match alignment {
+            ObjectAlignment::TopLeft | ObjectAlignment::Top | ObjectAlignment::TopRight => 0.0,
+            ObjectAlignment::Left | ObjectAlignment::Center | ObjectAlignment::Right => {
+                height / 2.0
+            }
+            _ => height,
+        };
+        (x, y)
+    }
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse an [`ObjectAlignment`] that is not valid.
+pub struct ObjectAlignmentError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for ObjectAlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse object alignment, valid options are `unspecified`, `topleft`, `top`, \
+        `topright`, `left`, `center`, `right`, `bottomleft`, `bottom` and `bottomright` but got \
+        `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for ObjectAlignmentError {}
+
+impl FromStr for ObjectAlignment {
+    type Err = ObjectAlignmentError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "unspecified" => Ok(ObjectAlignment::Unspecified),
+            "topleft" => Ok(ObjectAlignment::TopLeft),
+            "top" => Ok(ObjectAlignment::Top),
+            "topright" => Ok(ObjectAlignment::TopRight),
+            "left" => Ok(ObjectAlignment::Left),
+            "center" => Ok(ObjectAlignment::Center),
+            "right" => Ok(ObjectAlignment::Right),
+            "bottomleft" => Ok(ObjectAlignment::BottomLeft),
+            "bottom" => Ok(ObjectAlignment::Bottom),
+            "bottomright" => Ok(ObjectAlignment::BottomRight),
+            _ => Err(ObjectAlignmentError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ObjectAlignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectAlignment::Unspecified => write!(f, "unspecified"),
+            ObjectAlignment::TopLeft => write!(f, "topleft"),
+            ObjectAlignment::Top => write!(f, "top"),
+            ObjectAlignment::TopRight => write!(f, "topright"),
+            ObjectAlignment::Left => write!(f, "left"),
+            ObjectAlignment::Center => write!(f, "center"),
+            ObjectAlignment::Right => write!(f, "right"),
+            ObjectAlignment::BottomLeft => write!(f, "bottomleft"),
+            ObjectAlignment::Bottom => write!(f, "bottom"),
+            ObjectAlignment::BottomRight => write!(f, "bottomright"),
+        }
+    }
+}
+
+/// The size tiles of a tileset are drawn at on tile layers.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum TileRenderSize {
+    /// Tiles are drawn at their own size.
+    #[default]
+    Tile,
+    /// Tiles are drawn at the tile size of the map, scaled according to the tileset's
+    /// [`FillMode`].
+    Grid,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`TileRenderSize`] that is not valid.
+pub struct TileRenderSizeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for TileRenderSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse tile render size, valid options are `tile`, `grid` \
+        but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for TileRenderSizeError {}
+
+impl FromStr for TileRenderSize {
+    type Err = TileRenderSizeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "tile" => Ok(TileRenderSize::Tile),
+            "grid" => Ok(TileRenderSize::Grid),
+            _ => Err(TileRenderSizeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for TileRenderSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileRenderSize::Tile => write!(f, "tile"),
+            TileRenderSize::Grid => write!(f, "grid"),
+        }
+    }
+}
+
+/// How the tiles of a tileset are scaled when drawn at a size other than their own, such as for
+/// tile objects that have been resized.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum FillMode {
+    /// Tiles are stretched to cover the whole area.
+    #[default]
+    Stretch,
+    /// Tiles are scaled as much as possible without changing their aspect ratio, and centered
+    /// within the area.
+    PreserveAspectFit,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`FillMode`] that is not valid.
+pub struct FillModeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for FillModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse fill mode, valid options are `stretch`, `preserve-aspect-fit` \
+        but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for FillModeError {}
+
+impl FromStr for FillMode {
+    type Err = FillModeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(FillMode::Stretch),
+            "preserve-aspect-fit" => Ok(FillMode::PreserveAspectFit),
+            _ => Err(FillModeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for FillMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillMode::Stretch => write!(f, "stretch"),
+            FillMode::PreserveAspectFit => write!(f, "preserve-aspect-fit"),
+        }
+    }
+}
+
+/// The grid Tiled uses to show tile overlays, such as terrain and collision information, for the
+/// tiles of a tileset.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#grid).
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Grid {
+    /// The orientation of the grid.
+    pub orientation: GridOrientation,
+    /// The width of a grid cell, in pixels.
+    pub width: u32,
+    /// The height of a grid cell, in pixels.
+    pub height: u32,
+}
+
+impl Grid {
+    fn new(attrs: Vec<OwnedAttribute>) -> Result<Self> {
+        let (orientation, (width, height)) = get_attrs!(
+            for v in attrs {
+                Some("orientation") => orientation ?= v.parse::<GridOrientation>(),
+                "width" => width ?= v.parse::<u32>(),
+                "height" => height ?= v.parse::<u32>(),
+            }
+            (orientation, (width, height))
+        );
+
+        Ok(Self {
+            orientation: orientation.unwrap_or_default(),
+            width,
+            height,
+        })
+    }
+
+    fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        writer.empty(
+            "grid",
+            &[
+                ("orientation", self.orientation.to_string()),
+                ("width", self.width.to_string()),
+                ("height", self.height.to_string()),
+            ],
+        )
+    }
+}
+
+#[cfg(feature = "json")]
+impl Grid {
+    fn from_json(grid: &JsonObject) -> Result<Self> {
+        Ok(Self {
+            orientation: json_parse_opt(grid, "orientation")?.unwrap_or_default(),
+            width: json_field(grid, "width")?,
+            height: json_field(grid, "height")?,
+        })
+    }
+
+    fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "orientation": self.orientation.to_string(),
+            "width": self.width,
+            "height": self.height,
+        })
+    }
+}
+
+/// The orientation of a tileset's [`Grid`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[allow(missing_docs)]
+pub enum GridOrientation {
+    #[default]
+    Orthogonal,
+    Isometric,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`GridOrientation`] that is not valid.
+pub struct GridOrientationError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for GridOrientationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse grid orientation, valid options are `orthogonal`, `isometric` \
+        but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for GridOrientationError {}
+
+impl FromStr for GridOrientation {
+    type Err = GridOrientationError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "orthogonal" => Ok(GridOrientation::Orthogonal),
+            "isometric" => Ok(GridOrientation::Isometric),
+            _ => Err(GridOrientationError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for GridOrientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridOrientation::Orthogonal => write!(f, "orthogonal"),
+            GridOrientation::Isometric => write!(f, "isometric"),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::io::Write;
+
+use xml::attribute::OwnedAttribute;
+
+#[cfg(feature = "json")]
+use crate::parse::json::{json_field_opt, JsonObject};
+use crate::{util::get_attrs, write::XmlWriter, Result};
+
+/// The ways the tiles of a tileset may be transformed by Tiled's terrain brush and automapping.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#transformations).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Transformations {
+    /// Whether tiles can be flipped horizontally.
+    pub flip_h: bool,
+    /// Whether tiles can be flipped vertically.
+    pub flip_v: bool,
+    /// Whether tiles can be rotated in 90-degree increments.
+    pub rotate: bool,
+    /// Whether untransformed tiles are preferred over transformed ones when both fit.
+    pub prefer_untransformed: bool,
+}
+
+impl Transformations {
+    /// Lists every way a tile can be drawn with the allowed transformations, starting with the
+    /// untransformed tile.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{TileFlip, Transformations};
+    ///
+    /// let transformations = Transformations {
+    ///     flip_h: true,
+    ///     ..Transformations::default()
+    /// };
+    /// let flipped = TileFlip {
+    ///     flip_h: true,
+    ///     flip_v: false,
+    ///     flip_d: false,
+    /// };
+    /// assert_eq!(transformations.variants(), vec![TileFlip::default(), flipped]);
+    ///
+    /// // Flipping and rotating together allow all eight variants.
+    /// let transformations = Transformations {
+    ///     rotate: true,
+    ///     ..transformations
+    /// };
+    /// assert_eq!(transformations.variants().len(), 8);
+    /// ```
+    pub fn variants(&self) -> Vec<TileFlip> {
+        let mut variants = vec![TileFlip::default()];
+        let mut next = 0;
+        while let Some(&variant) = variants.get(next) {
+            let candidates = [
+                (self.flip_h, variant.flipped_h()),
+                (self.flip_v, variant.flipped_v()),
+                (self.rotate, variant.rotated()),
+            ];
+            for (allowed, transformed) in candidates {
+                if allowed && !variants.contains(&transformed) {
+                    variants.push(transformed);
+                }
+            }
+            next += 1;
+        }
+        variants
+    }
+
+    pub(crate) fn new(attrs: Vec<OwnedAttribute>) -> Self {
+        let (flip_h, flip_v, rotate, prefer_untransformed) = get_attrs!(
+            for v in attrs {
+                Some("hflip") => flip_h = v == "1",
+                Some("vflip") => flip_v = v == "1",
+                Some("rotate") => rotate = v == "1",
+                Some("preferuntransformed") => prefer_untransformed = v == "1",
+            }
+            (flip_h, flip_v, rotate, prefer_untransformed)
+        );
+
+        Self {
+            flip_h: flip_h.unwrap_or(false),
+            flip_v: flip_v.unwrap_or(false),
+            rotate: rotate.unwrap_or(false),
+            prefer_untransformed: prefer_untransformed.unwrap_or(false),
+        }
+    }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let flag = |value: bool| if value { "1" } else { "0" }.to_owned();
+        writer.empty(
+            "transformations",
+            &[
+                ("hflip", flag(self.flip_h)),
+                ("vflip", flag(self.flip_v)),
+                ("rotate", flag(self.rotate)),
+                ("preferuntransformed", flag(self.prefer_untransformed)),
+            ],
+        )
+    }
+}
+
+#[cfg(feature = "json")]
+impl Transformations {
+    pub(crate) fn from_json(transformations: &JsonObject) -> Result<Self> {
+        Ok(Self {
+            flip_h: json_field_opt(transformations, "hflip")?.unwrap_or(false),
+            flip_v: json_field_opt(transformations, "vflip")?.unwrap_or(false),
+            rotate: json_field_opt(transformations, "rotate")?.unwrap_or(false),
+            prefer_untransformed: json_field_opt(transformations, "preferuntransformed")?
+                .unwrap_or(false),
+        })
+    }
+
+    pub(crate) fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "hflip": self.flip_h,
+            "vflip": self.flip_v,
+            "rotate": self.rotate,
+            "preferuntransformed": self.prefer_untransformed,
+        })
+    }
+}
+
+/// A way of drawing a tile, expressed with the same flags as
+/// [`LayerTileData`](crate::LayerTileData). The diagonal flip is applied first, followed by the
+/// horizontal and vertical ones.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct TileFlip {
+    /// Whether the tile is flipped on its Y axis (horizontally).
+    pub flip_h: bool,
+    /// Whether the tile is flipped on its X axis (vertically).
+    pub flip_v: bool,
+    /// Whether the tile is flipped diagonally.
+    pub flip_d: bool,
+}
+
+impl TileFlip {
+    /// Returns this variant flipped horizontally.
+    fn flipped_h(self) -> Self {
+        Self {
+            flip_h: !self.flip_h,
+            ..self
+        }
+    }
+
+    /// Returns this variant flipped vertically.
+    fn flipped_v(self) -> Self {
+        Self {
+            flip_v: !self.flip_v,
+            ..self
+        }
+    }
+
+    /// Returns this variant rotated 90 degrees clockwise.
+    fn rotated(self) -> Self {
+        // A clockwise rotation is a diagonal flip followed by a horizontal one. Moving the
+        // existing flips past the diagonal flip swaps their axes.
+        Self {
+            flip_h: !self.flip_v,
+            flip_v: self.flip_h,
+            flip_d: !self.flip_d,
+        }
+    }
+}
@@ -1,13 +1,19 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::Error,
-    properties::{parse_properties, Properties},
+    properties::{parse_properties, write_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
     Result, TileId,
 };
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{json_array, json_field, json_object, JsonObject},
+    properties::{parse_properties_json, write_properties_json},
+};
 
 mod wang_color;
 pub use wang_color::*;
@@ -62,11 +68,7 @@ impl WangSet {
             (name, wang_set_type, tile)
         );
 
-        let wang_set_type = match wang_set_type.as_str() {
-            "corner" => WangSetType::Corner,
-            "edge" => WangSetType::Edge,
-            _ => WangSetType::default(),
-        };
+        let wang_set_type = parse_wang_set_type(&wang_set_type);
         let tile = if tile >= 0 { Some(tile as u32) } else { None };
 
         // Gather variable data
@@ -99,4 +101,95 @@ impl WangSet {
             properties,
         })
     }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        writer.start(
+            "wangset",
+            &[
+                ("name", self.name.clone()),
+                ("type", wang_set_type_name(self.wang_set_type).to_owned()),
+                ("tile", self.tile.map_or(-1, i64::from).to_string()),
+            ],
+        )?;
+        write_properties(writer, &self.properties)?;
+        for color in &self.wang_colors {
+            color.write_xml(writer)?;
+        }
+        let mut tile_ids: Vec<&TileId> = self.wang_tiles.keys().collect();
+        tile_ids.sort();
+        for id in tile_ids {
+            self.wang_tiles[id].write_xml(writer, *id)?;
+        }
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(set: &JsonObject) -> Result<WangSet> {
+        let name = json_field(set, "name")?;
+        let wang_set_type = parse_wang_set_type(&json_field::<String>(set, "type")?);
+        let tile = json_field::<i64>(set, "tile")?;
+        let tile = if tile >= 0 { Some(tile as u32) } else { None };
+
+        let wang_colors = json_array(set, "colors")?
+            .iter()
+            .map(|color| WangColor::from_json(json_object(color)?))
+            .collect::<Result<Vec<_>>>()?;
+        let wang_tiles = json_array(set, "wangtiles")?
+            .iter()
+            .map(|tile| WangTile::from_json(json_object(tile)?))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let properties = parse_properties_json(set)?;
+
+        Ok(WangSet {
+            name,
+            wang_set_type,
+            tile,
+            wang_colors,
+            wang_tiles,
+            properties,
+        })
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self) -> JsonObject {
+        let mut tile_ids: Vec<&TileId> = self.wang_tiles.keys().collect();
+        tile_ids.sort();
+        let wang_tiles = tile_ids
+            .into_iter()
+            .map(|id| self.wang_tiles[id].to_json(*id).into())
+            .collect::<Vec<serde_json::Value>>();
+        let wang_colors = self
+            .wang_colors
+            .iter()
+            .map(|color| color.to_json().into())
+            .collect::<Vec<serde_json::Value>>();
+
+        let mut set = JsonObject::new();
+        set.insert("name".to_owned(), self.name.clone().into());
+        set.insert(
+            "type".to_owned(),
+            wang_set_type_name(self.wang_set_type).into(),
+        );
+        set.insert("tile".to_owned(), self.tile.map_or(-1, i64::from).into());
+        set.insert("colors".to_owned(), wang_colors.into());
+        set.insert("wangtiles".to_owned(), wang_tiles.into());
+        write_properties_json(&mut set, &self.properties);
+        set
+    }
+}
+
+fn wang_set_type_name(wang_set_type: WangSetType) -> &'static str {
+    match wang_set_type {
+        WangSetType::Corner => "corner",
+        WangSetType::Edge => "edge",
+        WangSetType::Mixed => "mixed",
+    }
+}
+
+fn parse_wang_set_type(s: &str) -> WangSetType {
+    match s {
+        "corner" => WangSetType::Corner,
+        "edge" => WangSetType::Edge,
+        _ => WangSetType::default(),
+    }
 }
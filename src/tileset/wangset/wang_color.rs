@@ -1,13 +1,20 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::Error,
-    properties::{parse_properties, Color, Properties},
+    properties::{parse_properties, write_properties, Color, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
     Result, TileId,
 };
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{json_field, JsonObject},
+    properties::{parse_properties_json, write_properties_json},
+    write::json_f32,
+};
 
 /// Stores the data of the Wang color.
 #[derive(Debug, PartialEq, Clone)]
@@ -60,4 +67,42 @@ impl WangColor {
             properties,
         })
     }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        writer.start(
+            "wangcolor",
+            &[
+                ("name", self.name.clone()),
+                ("color", self.color.to_string()),
+                ("tile", self.tile.map_or(-1, i64::from).to_string()),
+                ("probability", self.probability.to_string()),
+            ],
+        )?;
+        write_properties(writer, &self.properties)?;
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(color: &JsonObject) -> Result<WangColor> {
+        let tile = json_field::<i64>(color, "tile")?;
+
+        Ok(WangColor {
+            name: json_field(color, "name")?,
+            color: json_field(color, "color")?,
+            tile: if tile >= 0 { Some(tile as u32) } else { None },
+            probability: json_field(color, "probability")?,
+            properties: parse_properties_json(color)?,
+        })
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self) -> JsonObject {
+        let mut color = JsonObject::new();
+        color.insert("name".to_owned(), self.name.clone().into());
+        color.insert("color".to_owned(), self.color.to_string().into());
+        color.insert("tile".to_owned(), self.tile.map_or(-1, i64::from).into());
+        color.insert("probability".to_owned(), json_f32(self.probability));
+        write_properties_json(&mut color, &self.properties);
+        color
+    }
 }
@@ -1,10 +1,13 @@
-use std::str::FromStr;
+use std::{io::Write, str::FromStr};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{json_array, json_field, JsonObject};
 use crate::{
     error::Error,
     util::{get_attrs, XmlEventResult},
+    write::XmlWriter,
     Result, TileId,
 };
 
@@ -59,4 +62,47 @@ impl WangTile {
 
         Ok((tile_id, WangTile { wang_id }))
     }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>, id: TileId) -> Result<()> {
+        let wang_id: Vec<String> = self.wang_id.0.iter().map(u8::to_string).collect();
+        writer.empty(
+            "wangtile",
+            &[("tileid", id.to_string()), ("wangid", wang_id.join(","))],
+        )
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn from_json(tile: &JsonObject) -> Result<(TileId, WangTile)> {
+        use std::convert::TryFrom;
+
+        let tile_id = json_field(tile, "tileid")?;
+        let values = json_array(tile, "wangid")?;
+        if values.len() != 8 {
+            return Err(Error::InvalidWangIdEncoding {
+                read_string: serde_json::Value::from(values.to_vec()).to_string(),
+            });
+        }
+        let mut wang_id = [0u8; 8];
+        for (value, json) in wang_id.iter_mut().zip(values) {
+            *value = json
+                .as_u64()
+                .and_then(|v| u8::try_from(v).ok())
+                .unwrap_or(0);
+        }
+
+        Ok((
+            tile_id,
+            WangTile {
+                wang_id: WangId(wang_id),
+            },
+        ))
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self, id: TileId) -> JsonObject {
+        let mut tile = JsonObject::new();
+        tile.insert("tileid".to_owned(), id.into());
+        tile.insert("wangid".to_owned(), self.wang_id.0.to_vec().into());
+        tile
+    }
 }
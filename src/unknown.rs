@@ -0,0 +1,148 @@
+use std::io::Write;
+
+use xml::{attribute::OwnedAttribute, name::OwnedName, reader::XmlEvent};
+
+use crate::{util::XmlEventResult, write::XmlWriter, Error, Result};
+
+/// The attributes and child elements of a TMX or TSX element that aren't recognized by this
+/// crate.
+///
+/// These are only recorded when [`Loader::set_preserve_unknown_xml`] has been enabled; otherwise,
+/// and for anything loaded from JSON, they are left empty. The TMX and TSX writers emit them again
+/// unchanged, so that tools rewriting files don't lose any data.
+///
+/// [`Loader::set_preserve_unknown_xml`]: crate::Loader::set_preserve_unknown_xml
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct UnknownXml {
+    /// The unrecognized attributes of the element, as `(name, value)` pairs in the order they were
+    /// found in.
+    pub attributes: Vec<(String, String)>,
+    /// The unrecognized child elements, as `(position, element)` pairs in the order they were found
+    /// in. The position is the number of child elements, recognized or not, that came before the
+    /// element; When written back, it is placed at the same position if possible.
+    pub elements: Vec<(usize, XmlElement)>,
+}
+
+impl UnknownXml {
+    /// Returns true if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.elements.is_empty()
+    }
+
+    /// Creates a record of the given attributes, or an empty one if they aren't to be preserved.
+    pub(crate) fn with_attributes(attributes: Vec<(String, String)>, preserve: bool) -> Self {
+        Self {
+            attributes: if preserve { attributes } else { Vec::new() },
+            elements: Vec::new(),
+        }
+    }
+
+    /// Appends the recorded attributes to those of an element about to be written.
+    pub(crate) fn extend_attributes<'a>(&'a self, attrs: &mut Vec<(&'a str, String)>) {
+        attrs.extend(
+            self.attributes
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone())),
+        );
+    }
+}
+
+/// An arbitrary XML element, along with all of its contents.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct XmlElement {
+    /// The local name of the element.
+    pub name: String,
+    /// The namespace prefix of the element's name, if any.
+    pub prefix: Option<String>,
+    /// The URI of the namespace the element is in, if any.
+    pub namespace: Option<String>,
+    /// The attributes of the element, as `(name, value)` pairs in the order they were found in.
+    /// Names include their namespace prefix, such as `xlink:href`.
+    pub attributes: Vec<(String, String)>,
+    /// The child elements and text of the element, in document order. Whitespace between elements
+    /// is not kept.
+    pub children: Vec<XmlNode>,
+}
+
+/// A node contained within an [`XmlElement`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum XmlNode {
+    /// A child element.
+    Element(XmlElement),
+    /// Text content, including that of CDATA sections.
+    Text(String),
+}
+
+impl XmlElement {
+    /// Reads the rest of an element whose start tag has just been consumed, up to and including its
+    /// end tag.
+    pub(crate) fn parse(
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        name: OwnedName,
+        attrs: Vec<OwnedAttribute>,
+    ) -> Result<Self> {
+        let mut children = Vec::new();
+        loop {
+            match parser.next() {
+                Some(Ok(XmlEvent::StartElement {
+                    name, attributes, ..
+                })) => children.push(XmlNode::Element(Self::parse(parser, name, attributes)?)),
+                Some(Ok(XmlEvent::Characters(text) | XmlEvent::CData(text))) => {
+                    children.push(XmlNode::Text(text))
+                }
+                Some(Ok(XmlEvent::EndElement { .. })) => break,
+                Some(Ok(XmlEvent::EndDocument)) | None => {
+                    return Err(Error::PrematureEnd(format!(
+                        "Document ended inside the <{}> element",
+                        name.local_name
+                    )))
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(Error::XmlDecodingError(err)),
+            }
+        }
+
+        Ok(Self {
+            name: name.local_name,
+            prefix: name.prefix,
+            namespace: name.namespace,
+            attributes: attrs
+                .into_iter()
+                .map(|attr| {
+                    let name = qualified_name(attr.name.prefix.as_deref(), &attr.name.local_name);
+                    (name, attr.value)
+                })
+                .collect(),
+            children,
+        })
+    }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let attrs: Vec<(&str, String)> = self
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        writer.start_in_namespace(
+            &qualified_name(self.prefix.as_deref(), &self.name),
+            self.prefix.as_deref(),
+            self.namespace.as_deref(),
+            &attrs,
+        )?;
+        for child in &self.children {
+            match child {
+                XmlNode::Element(element) => element.write_xml(writer)?,
+                XmlNode::Text(text) => writer.characters(text)?,
+            }
+        }
+        writer.end()
+    }
+}
+
+/// Returns a name along with its namespace prefix, such as `xlink:href`.
+fn qualified_name(prefix: Option<&str>, local_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, local_name),
+        None => local_name.to_owned(),
+    }
+}
@@ -52,6 +52,20 @@
 /// Finally, after the `for` block, `$expression_to_return` indicates what to return once the
 /// iteration has finished. It may refer to variables declared previously.
 ///
+/// Attributes that don't match any branch are ignored, unless `unknown => $variable,` is placed
+/// right before `$expression_to_return`. In that case, they are collected into a
+/// `Vec<(String, String)>` of names and values stored in `$variable`, which can then be used in
+/// the expression:
+/// ```ignore
+/// let (name, unknown) = get_attrs!(
+///     for v in attrs {
+///         Some("name") => name = v,
+///     }
+///     unknown => unknown,
+///     (name, unknown)
+/// );
+/// ```
+///
 /// ## Example
 /// ```ignore
 /// let ((c, infinite), (v, o, w, h, tw, th)) = get_attrs!(
@@ -73,14 +87,40 @@ macro_rules! get_attrs {
         for $attr:ident in $attrs:ident {
             $($branches:tt)*
         }
+        unknown => $unknown:ident,
         $ret_expr:expr
     ) => {
         {
             $crate::util::let_attr_branches!($($branches)*);
+            let mut $unknown: Vec<(String, String)> = Vec::new();
 
             for attr in $attrs.iter() {
                 let $attr = attr.value.clone();
-                $crate::util::process_attr_branches!(attr; $($branches)*);
+                $crate::util::process_attr_branches!(
+                    attr;
+                    { $unknown.push((attr.name.local_name.clone(), attr.value.clone())); };
+                    $($branches)*
+                );
+            }
+
+            $crate::util::handle_attr_branches!($($branches)*);
+
+            $ret_expr
+        }
+    };
+
+    (
+        for $attr:ident in $attrs:ident {
+            $($branches:tt)*
+        }
+        $ret_expr:expr
+    ) => {
+        {
+            $crate::util::let_attr_branches!($($branches)*);
+
+            for attr in $attrs.iter() {
+                let $attr = attr.value.clone();
+                $crate::util::process_attr_branches!(attr; {}; $($branches)*);
             }
 
             $crate::util::handle_attr_branches!($($branches)*);
@@ -107,18 +147,18 @@ macro_rules! let_attr_branches {
 pub(crate) use let_attr_branches;
 
 macro_rules! process_attr_branches {
-    ($attr:ident; ) => {};
+    ($attr:ident; $fallback:block; ) => { $fallback };
 
-    ($attr:ident; Some($attr_pat_opt:literal) => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident; $fallback:block; Some($attr_pat_opt:literal) => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
         if(&$attr.name.local_name == $attr_pat_opt) {
             $opt_var = Some($opt_expr);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr; $fallback; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; Some($attr_pat_opt:literal) => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident; $fallback:block; Some($attr_pat_opt:literal) => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
         if(&$attr.name.local_name == $attr_pat_opt) {
             $opt_var = Some($opt_expr.map_err(|_|
                 $crate::Error::MalformedAttributes(
@@ -127,20 +167,20 @@ macro_rules! process_attr_branches {
             )?);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr; $fallback; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; $attr_pat_opt:literal => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident; $fallback:block; $attr_pat_opt:literal => $opt_var:ident = $opt_expr:expr $(, $($tail:tt)*)?) => {
         if(&$attr.name.local_name == $attr_pat_opt) {
             $opt_var = Some($opt_expr);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr; $fallback; $($($tail)*)?);
         }
     };
 
-    ($attr:ident; $attr_pat_opt:literal => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+    ($attr:ident; $fallback:block; $attr_pat_opt:literal => $opt_var:ident ?= $opt_expr:expr $(, $($tail:tt)*)?) => {
         if(&$attr.name.local_name == $attr_pat_opt) {
             $opt_var = Some($opt_expr.map_err(|_|
                 $crate::Error::MalformedAttributes(
@@ -149,7 +189,7 @@ macro_rules! process_attr_branches {
             )?);
         }
         else {
-            $crate::util::process_attr_branches!($attr; $($($tail)*)?);
+            $crate::util::process_attr_branches!($attr; $fallback; $($($tail)*)?);
         }
     }
 }
@@ -179,7 +219,41 @@ pub(crate) use handle_attr_branches;
 
 /// Goes through the children of the tag and will call the correct function for
 /// that child. Closes the tag.
+///
+/// Children that don't match any of the given tags are skipped, unless an `else` function is
+/// given after the tags, in which case it is called with their name, their attributes and the
+/// number of child elements that came before them instead.
 macro_rules! parse_tag {
+    ($parser:expr, $close_tag:expr, {$($open_tag:expr => $open_method:expr),* $(,)*} else $unknown_method:expr) => {
+        let mut position = 0;
+        while let Some(next) = $parser.next() {
+            match next.map_err(Error::XmlDecodingError)? {
+                #[allow(unused_variables)]
+                $(
+                    xml::reader::XmlEvent::StartElement {name, attributes, ..}
+                        if name.local_name == $open_tag => {
+                            $open_method(attributes)?;
+                            position += 1;
+                        }
+                )*
+
+                xml::reader::XmlEvent::StartElement {name, attributes, ..} => {
+                    $unknown_method(name, attributes, position)?;
+                    position += 1;
+                }
+
+                xml::reader::XmlEvent::EndElement {name, ..} => if name.local_name == $close_tag {
+                    break;
+                }
+
+                xml::reader::XmlEvent::EndDocument => {
+                    return Err(Error::PrematureEnd("Document ended before we expected.".to_string()));
+                }
+                _ => {}
+            }
+        }
+    };
+
     ($parser:expr, $close_tag:expr, {$($open_tag:expr => $open_method:expr),* $(,)*}) => {
         while let Some(next) = $parser.next() {
             match next.map_err(Error::XmlDecodingError)? {
@@ -241,7 +315,10 @@ pub(crate) use get_attrs;
 pub(crate) use map_wrapper;
 pub(crate) use parse_tag;
 
-use crate::{Gid, MapTilesetGid};
+use base64::Engine;
+use xml::reader::XmlEvent;
+
+use crate::{Error, Gid, MapTilesetGid, Result};
 
 pub(crate) type XmlEventResult = xml::reader::Result<xml::reader::XmlEvent>;
 
@@ -257,6 +334,37 @@ pub(crate) fn get_tileset_for_gid(
         .find(|(_idx, ts)| ts.first_gid <= gid)
 }
 
+/// Reads and decodes the base64 text content of a `<data>` element.
+pub(crate) fn parse_base64(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<Vec<u8>> {
+    for next in parser {
+        match next.map_err(Error::XmlDecodingError)? {
+            XmlEvent::Characters(s) => return decode_base64(&s),
+            XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
+                return Ok(Vec::new());
+            }
+            _ => {}
+        }
+    }
+    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
+}
+
+pub(crate) fn decode_base64(s: &str) -> Result<Vec<u8>> {
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    )
+    .decode(s.trim().as_bytes())
+    .map_err(Error::Base64DecodingError)
+}
+
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    )
+    .encode(data)
+}
+
 pub fn floor_div(a: i32, b: i32) -> i32 {
     let d = a / b;
     let r = a % b;
@@ -0,0 +1,23 @@
+use std::io::Write;
+
+use serde_json::{Number, Value};
+
+use crate::{parse::json::JsonObject, Error, Result};
+
+/// Converts a float into a JSON number.
+///
+/// The conversion goes through the float's textual representation so that values such as `0.1`
+/// are written as is instead of as the closest `f64` to the `f32` (`0.10000000149011612`).
+pub(crate) fn json_f32(value: f32) -> Value {
+    value
+        .to_string()
+        .parse()
+        .ok()
+        .and_then(Number::from_f64)
+        .map_or(Value::Null, Value::Number)
+}
+
+/// Writes a whole JSON document, indenting it like Tiled does.
+pub(crate) fn write_json_document(writer: impl Write, document: JsonObject) -> Result<()> {
+    serde_json::to_writer_pretty(writer, &Value::Object(document)).map_err(Error::JsonEncodingError)
+}
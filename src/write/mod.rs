@@ -0,0 +1,69 @@
+//! Utilities shared by the writers of the different file formats.
+
+use std::path::{Component, Path};
+
+use crate::{Gid, TileDataEncoding};
+
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "json")]
+pub(crate) use self::json::*;
+mod xml;
+pub(crate) use self::xml::*;
+
+/// Data needed by the writers that isn't stored in the items being written.
+pub(crate) struct WriteContext<'a> {
+    /// The directory every written path is made relative to.
+    pub base_path: &'a Path,
+    /// The first GID of each tileset of the map being written, in the same order as the map's
+    /// tilesets. Empty if there is no map involved.
+    pub first_gids: &'a [Gid],
+    /// The size of the map being written, in tiles.
+    pub map_size: (u32, u32),
+    /// The level to compress tile layer data at, where -1 stands for the default level of the
+    /// compression method.
+    pub compression_level: i32,
+    /// The encoding to write the data of all tile layers with, instead of the one each of them
+    /// was loaded with.
+    pub tile_encoding: Option<TileDataEncoding>,
+}
+
+impl WriteContext<'_> {
+    /// Returns the encoding to write the data of a tile layer loaded with `encoding` with.
+    pub(crate) fn tile_encoding(&self, encoding: TileDataEncoding) -> TileDataEncoding {
+        self.tile_encoding.unwrap_or(encoding)
+    }
+}
+
+/// Returns `path` relative to the `base` directory, using forward slashes as Tiled does.
+///
+/// Both paths are expected to be relative to the same directory (or both absolute), as is the case
+/// for any path obtained through a [`ResourceReader`](crate::ResourceReader). If this is not the
+/// case, `path` is returned as is.
+pub(crate) fn relative_path(base: &Path, path: &Path) -> String {
+    if base.is_absolute() != path.is_absolute() {
+        return path.to_string_lossy().replace('\\', "/");
+    }
+
+    let mut base_components = base
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .peekable();
+    let mut path_components = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .peekable();
+    while let (Some(b), Some(p)) = (base_components.peek(), path_components.peek()) {
+        if b != p {
+            break;
+        }
+        base_components.next();
+        path_components.next();
+    }
+
+    base_components
+        .map(|_| "..".to_owned())
+        .chain(path_components.map(|c| c.as_os_str().to_string_lossy().into_owned()))
+        .collect::<Vec<_>>()
+        .join("/")
+}
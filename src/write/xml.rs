@@ -0,0 +1,125 @@
+use std::io::Write;
+
+use xml::writer::{events::StartElementBuilder, EmitterConfig, EventWriter, XmlEvent};
+
+use crate::{Error, Result, XmlElement};
+
+/// A thin wrapper over [`EventWriter`] that indents its output the same way Tiled does.
+pub(crate) struct XmlWriter<W: Write> {
+    writer: EventWriter<W>,
+    /// For each element currently open, the number of child elements written in it so far and
+    /// the unknown elements that are still to be written in it, last one first.
+    open: Vec<(usize, Vec<(usize, XmlElement)>)>,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub(crate) fn new(writer: W) -> Self {
+        Self {
+            writer: EmitterConfig::new()
+                .perform_indent(true)
+                .indent_string(" ")
+                .create_writer(writer),
+            open: Vec::new(),
+        }
+    }
+
+    /// Opens an element with the given attributes. Must be matched by a call to [`Self::end`].
+    pub(crate) fn start(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<()> {
+        self.start_with_unknown(name, attrs, &[])
+    }
+
+    /// Opens an element like [`Self::start`], and writes the given unknown elements among the
+    /// children written in it afterwards, at their recorded positions.
+    pub(crate) fn start_with_unknown(
+        &mut self,
+        name: &str,
+        attrs: &[(&str, String)],
+        unknown_elements: &[(usize, XmlElement)],
+    ) -> Result<()> {
+        let mut element = XmlEvent::start_element(name);
+        for (attr, value) in attrs {
+            element = element.attr(*attr, value.as_str());
+        }
+        self.write_start(element)?;
+        if let Some((_, pending)) = self.open.last_mut() {
+            pending.extend(unknown_elements.iter().rev().cloned());
+        }
+        Ok(())
+    }
+
+    /// Opens an element whose name is in the given namespace, declaring the namespace if it isn't
+    /// already. Must be matched by a call to [`Self::end`].
+    pub(crate) fn start_in_namespace(
+        &mut self,
+        name: &str,
+        prefix: Option<&str>,
+        namespace: Option<&str>,
+        attrs: &[(&str, String)],
+    ) -> Result<()> {
+        let mut element = XmlEvent::start_element(name);
+        element = match (prefix, namespace) {
+            (Some(prefix), Some(namespace)) => element.ns(prefix, namespace),
+            (None, Some(namespace)) => element.default_ns(namespace),
+            (_, None) => element,
+        };
+        for (attr, value) in attrs {
+            element = element.attr(*attr, value.as_str());
+        }
+        self.write_start(element)
+    }
+
+    /// Writes the start tag of a child of the last element opened, after the unknown elements
+    /// that come before it.
+    fn write_start(&mut self, element: StartElementBuilder) -> Result<()> {
+        self.write_pending(false)?;
+        if let Some((written, _)) = self.open.last_mut() {
+            *written += 1;
+        }
+        self.writer
+            .write(element)
+            .map_err(Error::XmlEncodingError)?;
+        self.open.push((0, Vec::new()));
+        Ok(())
+    }
+
+    /// Writes the unknown elements of the last element opened that belong before its next child,
+    /// or all of them if `all` is true.
+    fn write_pending(&mut self, all: bool) -> Result<()> {
+        loop {
+            let element = match self.open.last_mut() {
+                Some((written, pending))
+                    if pending
+                        .last()
+                        .map(|(position, _)| all || *position <= *written)
+                        == Some(true) =>
+                {
+                    pending.pop().unwrap().1
+                }
+                _ => return Ok(()),
+            };
+            element.write_xml(self)?;
+        }
+    }
+
+    /// Closes the last element opened.
+    pub(crate) fn end(&mut self) -> Result<()> {
+        self.write_pending(true)?;
+        self.open.pop();
+        self.writer
+            .write(XmlEvent::end_element())
+            .map_err(Error::XmlEncodingError)
+    }
+
+    /// Writes an element with the given attributes and no children.
+    pub(crate) fn empty(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<()> {
+        self.start(name, attrs)?;
+        self.end()
+    }
+
+    /// Writes text content inside the last element opened.
+    pub(crate) fn characters(&mut self, text: &str) -> Result<()> {
+        self.writer
+            .write(XmlEvent::characters(text))
+            .map_err(Error::XmlEncodingError)
+    }
+}
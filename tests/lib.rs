@@ -1,9 +1,20 @@
-use std::path::PathBuf;
+#[cfg(feature = "json")]
+use std::collections::HashMap;
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 
 use tiled::{
-    Color, FiniteTileLayer, HorizontalAlignment, LayerType, Loader, Map, ObjectShape,
-    PropertyValue, ResourceCache, TileLayer, TilesetLocation, VerticalAlignment, WangId,
+    CameraRect, Color, Connectivity, DrawOrder, EffectiveLayer, FillMode, FiniteTileLayer, Grid,
+    GridOrientation, HorizontalAlignment, ImageSource, LayerType, LinkedFile, Loader, Map,
+    ObjectAlignment, ObjectData, ObjectShape, PropertyValue, RawEnumValue, RenderOrder,
+    ResourceCache, SourceRect, TileDataCompression, TileDataEncoding, TileFlip, TileLayer,
+    TilePlacement, TileRenderSize, TilesetLocation, Transformations, VerticalAlignment, WangId,
+    XmlElement, XmlNode,
 };
+#[cfg(feature = "json")]
+use tiled::{ClassUsage, EnumStorageType};
 
 fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
     match data {
@@ -29,6 +40,89 @@ fn compare_everything_but_sources(r: &Map, e: &Map) {
         .for_each(|(r, e)| assert_eq!(r, e)); */
 }
 
+/// Writes a map as TMX and loads it back, serving the written file in place of the original one.
+fn reload_written_tmx(map: &Map) -> Map {
+    let mut tmx = Vec::new();
+    map.write_tmx(&mut tmx).unwrap();
+    loader_with_written_file(&map.source, tmx)
+        .load_tmx_map(&map.source)
+        .unwrap()
+}
+
+/// Returns a loader that reads `written` in place of the file at `path`.
+fn loader_with_written_file(
+    path: &Path,
+    written: Vec<u8>,
+) -> Loader<tiled::DefaultResourceCache, impl tiled::ResourceReader> {
+    let path = path.to_owned();
+    Loader::with_reader(move |p: &Path| -> std::io::Result<_> {
+        if p == path {
+            Ok(Cursor::new(written.clone()))
+        } else {
+            std::fs::read(p).map(Cursor::new)
+        }
+    })
+}
+
+/// Every tile data encoding that can be written.
+fn written_tile_encodings() -> Vec<TileDataEncoding> {
+    let mut encodings = vec![
+        TileDataEncoding::Csv,
+        TileDataEncoding::Xml,
+        TileDataEncoding::Base64(None),
+        TileDataEncoding::Base64(Some(TileDataCompression::Zlib)),
+        TileDataEncoding::Base64(Some(TileDataCompression::Gzip)),
+    ];
+    if cfg!(feature = "zstd") {
+        encodings.push(TileDataEncoding::Base64(Some(TileDataCompression::Zstd)));
+    }
+    encodings
+}
+
+/// Checks that the tile layers of `written` have the same tiles as those of `map`, and that they
+/// were written with the given encoding.
+fn assert_tiles_written_with(map: &Map, written: &Map, encoding: TileDataEncoding) {
+    for (layer, written_layer) in map.layers().zip(written.layers()) {
+        match (layer.as_tile_layer(), written_layer.as_tile_layer()) {
+            (Some(TileLayer::Finite(layer)), Some(TileLayer::Finite(written_layer))) => {
+                assert_eq!(written_layer.encoding, encoding);
+                for y in 0..layer.height() as i32 {
+                    for x in 0..layer.width() as i32 {
+                        assert_eq!(written_layer.get_tile_data(x, y), layer.get_tile_data(x, y));
+                    }
+                }
+            }
+            (Some(TileLayer::Infinite(layer)), Some(TileLayer::Infinite(written_layer))) => {
+                assert_eq!(written_layer.encoding, encoding);
+                assert_eq!(written_layer.chunk_data().len(), layer.chunk_data().len());
+                for ((x, y), chunk) in layer.chunk_data() {
+                    assert_eq!(written_layer.get_chunk_data(x, y), Some(chunk));
+                }
+            }
+            (None, None) => {}
+            _ => panic!("layer {} changed type when written", layer.name),
+        }
+    }
+}
+
+/// Loads a 10x10 map with an empty tile layer, using the given attributes to describe its layout.
+fn load_empty_map(layout_attrs: &str) -> Map {
+    let tmx = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" {} width="10" height="10" infinite="0">
+ <layer id="1" name="Tile Layer 1" width="10" height="10">
+  <data encoding="csv">{}</data>
+ </layer>
+</map>"#,
+        layout_attrs,
+        vec!["0"; 100].join(",")
+    );
+    let path = Path::new("assets/tiled_empty_map.tmx");
+    loader_with_written_file(path, tmx.into_bytes())
+        .load_tmx_map(path)
+        .unwrap()
+}
+
 #[test]
 fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
     let mut loader = Loader::new();
@@ -57,6 +151,60 @@ fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
     assert!((0..99).map(|x| layer.get_tile(x, 99)).all(|t| t.is_none()));
 }
 
+#[test]
+fn test_xml_encoded_tile_data() {
+    let map = Loader::new().load_tmx_map("assets/tiled_xml.tmx").unwrap();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.encoding, TileDataEncoding::Xml);
+    assert_eq!((layer.width(), layer.height()), (100, 100));
+    assert_eq!(layer.get_tile(0, 0).unwrap().id(), 29);
+    assert_eq!(layer.get_tile(9, 0).unwrap().id(), 29);
+    assert!(layer.get_tile(10, 0).is_none());
+}
+
+#[test]
+fn test_xml_encoded_chunks() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="32" tileheight="32" infinite="1">
+ <tileset firstgid="1" name="tilesheet" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tileset>
+ <layer id="1" name="Tile Layer 1" width="2" height="2">
+  <data>
+   <chunk x="-16" y="0" width="2" height="2">
+    <tile gid="2147483677"/>
+    <tile/>
+    <tile gid="3"/>
+    <tile gid="1610612739"/>
+   </chunk>
+  </data>
+ </layer>
+</map>"#;
+    let path = Path::new("assets/tiled_xml_chunks.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+    };
+    assert_eq!(layer.encoding, TileDataEncoding::Xml);
+
+    let flipped = layer.get_tile(-16, 0).unwrap();
+    assert_eq!(flipped.id(), 28);
+    assert!(flipped.flip_h && !flipped.flip_v && !flipped.flip_d);
+    assert!(layer.get_tile(-15, 0).is_none());
+    let plain = layer.get_tile(-16, 1).unwrap();
+    assert_eq!(plain.id(), 2);
+    assert!(!plain.flip_h && !plain.flip_v && !plain.flip_d);
+    let flipped = layer.get_tile(-15, 1).unwrap();
+    assert_eq!(flipped.id(), 2);
+    assert!(!flipped.flip_h && flipped.flip_v && flipped.flip_d);
+
+    assert_eq!(reload_written_tmx(&map), map);
+}
+
 #[test]
 fn test_external_tileset() {
     let mut loader = Loader::new();
@@ -68,6 +216,110 @@ fn test_external_tileset() {
     compare_everything_but_sources(&r, &e);
 }
 
+#[cfg(feature = "json")]
+#[test]
+fn test_json_maps_match_xml() {
+    let mut loader = Loader::new();
+
+    for name in [
+        "tiled_csv",
+        "tiled_base64_zlib_infinite",
+        "tiled_group_layers",
+        "tiled_object_template",
+    ] {
+        let x = loader.load_tmx_map(format!("assets/{}.tmx", name)).unwrap();
+        let j = loader.load_tmj_map(format!("assets/{}.tmj", name)).unwrap();
+        compare_everything_but_sources(&x, &j);
+        assert_eq!(x.user_type, j.user_type);
+
+        assert_eq!(x.layers().len(), j.layers().len());
+        for (x, j) in x.layers().zip(j.layers()) {
+            assert_eq!(*x, *j);
+        }
+
+        assert_eq!(x.tilesets().len(), j.tilesets().len());
+        for (x, j) in x.tilesets().iter().zip(j.tilesets()) {
+            // Embedded tilesets have the source of the map they're in
+            let mut j = (**j).clone();
+            j.source = x.source.clone();
+            assert_eq!(**x, j);
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_tilesets_match_xml() {
+    let mut loader = Loader::new();
+
+    for name in ["tilesheet", "tilesheet_wangsets", "tilesheet_animated"] {
+        let x = loader
+            .load_tsx_tileset(format!("assets/{}.tsx", name))
+            .unwrap();
+        let mut j = loader
+            .load_tsj_tileset(format!("assets/{}.tsj", name))
+            .unwrap();
+        assert_eq!(j.source, PathBuf::from(format!("assets/{}.tsj", name)));
+        j.source = x.source.clone();
+        assert_eq!(x, j);
+    }
+
+    let animated = loader
+        .load_tsj_tileset("assets/tilesheet_animated.tsj")
+        .unwrap();
+    let tile = animated.get_tile(3).unwrap();
+    assert_eq!(tile.animation.as_ref().unwrap().len(), 3);
+    assert_eq!(tile.collision.as_ref().unwrap().object_data().len(), 2);
+    assert_eq!(tile.user_type.as_deref(), Some("water"));
+    assert_eq!((animated.offset_x, animated.offset_y), (2, -4));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_references() {
+    let mut loader = Loader::new();
+    let x = loader
+        .load_tmx_map("assets/tiled_json_references.tmx")
+        .unwrap();
+    let j = loader
+        .load_tmj_map("assets/tiled_json_references.tmj")
+        .unwrap();
+
+    // Both maps share the same JSON tileset and template
+    assert_eq!(loader.cache().tilesets.len(), 2);
+    assert_eq!(loader.cache().templates.len(), 1);
+    assert_eq!(
+        x.tilesets()[0].source,
+        PathBuf::from("assets/tilesheet.tsj")
+    );
+    assert_eq!(
+        loader.cache().templates.values().next().unwrap().source,
+        PathBuf::from("assets/tiled_object_template.tj")
+    );
+    for (x, j) in x.layers().zip(j.layers()) {
+        assert_eq!(*x, *j);
+    }
+
+    let object_layer = j.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = object_layer.get_object(0).unwrap();
+    assert_eq!(
+        Some(&PropertyValue::IntValue(1)),
+        object.properties.get("property")
+    );
+    assert_eq!(
+        object.get_tile().unwrap().get_tileset().name,
+        "tilesheet_template"
+    );
+    assert_eq!(object.get_tile().unwrap().id(), 44);
+    assert_eq!(
+        object_layer.get_object(2).unwrap().shape,
+        ObjectShape::Rect {
+            width: 64.0,
+            height: 32.0,
+        }
+    );
+}
+
 #[cfg(feature = "world")]
 #[test]
 fn test_world() {
@@ -137,7 +389,7 @@ fn test_cache() {
     );
     assert_eq!(
         e.tilesets()[0].image.as_ref().unwrap().source,
-        PathBuf::from("assets/tilesheet.png")
+        ImageSource::File(PathBuf::from("assets/tilesheet.png"))
     );
 }
 
@@ -226,183 +478,1297 @@ fn test_infinite_map() {
 }
 
 #[test]
-fn test_image_layers() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_image_layers.tmx")
+fn test_render_order() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="left-up" width="3" height="2" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" source="tilesheet.tsx"/>
+ <layer id="1" name="Tiles" width="3" height="2">
+  <data encoding="csv">
+1,0,2,
+0,3,4
+</data>
+ </layer>
+</map>"#;
+    let path = Path::new("assets/tiled_render_order.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
         .unwrap();
-    assert_eq!(r.layers().len(), 2);
-    let mut image_layers = r.layers().map(|layer| {
-        if let LayerType::Image(img) = layer.layer_type() {
-            (img, layer)
-        } else {
-            panic!("Found layer that isn't an image layer")
-        }
-    });
-    {
-        let first = image_layers.next().unwrap();
-        assert_eq!(first.1.name, "Image Layer 1");
-        assert!(
-            first.0.image.is_none(),
-            "{}'s image should be None",
-            first.1.name
+    assert_eq!(map.render_order, RenderOrder::LeftUp);
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tiles: Vec<_> = layer
+        .tiles_in_render_order()
+        .map(|(x, y, tile)| (x, y, tile.id()))
+        .collect();
+    assert_eq!(tiles, vec![(2, 1, 3), (1, 1, 2), (2, 0, 1), (0, 0, 0)]);
+    assert_eq!(reload_written_tmx(&map).render_order, RenderOrder::LeftUp);
+
+    // Other orientations are drawn back to front, whatever their render order.
+    for (layout_attrs, expected) in [
+        (
+            r#"orientation="isometric""#,
+            [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)],
+        ),
+        (
+            r#"orientation="staggered" staggeraxis="x" staggerindex="odd""#,
+            [(0, 0), (2, 0), (1, 0), (0, 1), (2, 1), (1, 1)],
+        ),
+        (
+            r#"orientation="staggered" staggeraxis="x" staggerindex="even""#,
+            [(1, 0), (0, 0), (2, 0), (1, 1), (0, 1), (2, 1)],
+        ),
+        (
+            r#"orientation="hexagonal" staggeraxis="y" staggerindex="odd" hexsidelength="16""#,
+            [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)],
+        ),
+    ] {
+        let tmx = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" {} renderorder="left-up" width="3" height="2" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" source="tilesheet.tsx"/>
+ <layer id="1" name="Tiles" width="3" height="2">
+  <data encoding="csv">
+1,2,3,
+4,5,6
+</data>
+ </layer>
+</map>"#,
+            layout_attrs
         );
+        let map = loader_with_written_file(path, tmx.into_bytes())
+            .load_tmx_map(path)
+            .unwrap();
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        let positions: Vec<_> = layer
+            .tiles_in_render_order()
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(positions, expected, "{}", layout_attrs);
     }
-    {
-        let second = image_layers.next().unwrap();
-        assert_eq!(second.1.name, "Image Layer 2");
-        let image = second
-            .0
-            .image
-            .as_ref()
-            .unwrap_or_else(|| panic!("{}'s image shouldn't be None", second.1.name));
-        assert_eq!(image.source, PathBuf::from("assets/tilesheet.png"));
-        assert_eq!(image.width, 448);
-        assert_eq!(image.height, 192);
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    assert_eq!(map.render_order, RenderOrder::RightDown);
+    for order in [
+        RenderOrder::RightDown,
+        RenderOrder::RightUp,
+        RenderOrder::LeftDown,
+        RenderOrder::LeftUp,
+    ] {
+        map.render_order = order;
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        let positions: Vec<_> = layer
+            .tiles_in_render_order()
+            .map(|(x, y, tile)| {
+                assert_eq!(layer.get_tile(x, y).unwrap().id(), tile.id());
+                (x, y)
+            })
+            .collect();
+        // The layer covers the tiles from (-16, 0) to (31, 47).
+        assert_eq!(positions.len(), 48 * 48);
+        let first = match order {
+            RenderOrder::RightDown => (-16, 0),
+            RenderOrder::RightUp => (-16, 47),
+            RenderOrder::LeftDown => (31, 0),
+            RenderOrder::LeftUp => (31, 47),
+        };
+        assert_eq!(positions[0], first);
+        assert_eq!(positions[1].1, first.1);
     }
 }
 
 #[test]
-fn test_tile_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_base64.tmx")
+fn test_map_ids_and_versions() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
         .unwrap();
-    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) = r.tilesets()[0]
-        .get_tile(1)
-        .unwrap()
-        .properties
-        .get("a tile property")
-    {
-        v.clone()
-    } else {
-        String::new()
-    };
-    assert_eq!("123", prop_value);
-}
+    assert_eq!(map.tiled_version.as_deref(), Some("1.4.0"));
+    assert_eq!(map.compression_level, -1);
+    assert_eq!(map.next_layer_id(), 3);
+    assert_eq!(map.next_object_id(), 5);
+    assert_eq!(map.allocate_object_id(), 5);
+    assert_eq!(map.allocate_object_id(), 6);
+    assert_eq!(map.allocate_layer_id(), 3);
+    let reloaded = reload_written_tmx(&map);
+    assert_eq!(reloaded.next_layer_id(), 4);
+    assert_eq!(reloaded.next_object_id(), 7);
 
-#[test]
-fn test_layer_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_base64.tmx")
+    let map = Loader::new()
+        .load_tmx_map("assets/ldk_tiled_export.tmx")
         .unwrap();
-    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) =
-        r.get_layer(0).unwrap().properties.get("prop3")
-    {
-        v.clone()
-    } else {
-        String::new()
-    };
-    assert_eq!("Line 1\r\nLine 2\r\nLine 3,\r\n  etc\r\n   ", prop_value);
-}
+    assert_eq!(map.compression_level, 0);
+    assert_eq!(reload_written_tmx(&map).compression_level, 0);
 
-#[test]
-fn test_object_group_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_object_groups.tmx")
+    // The compression level is used when writing compressed tile data.
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
         .unwrap();
-    let group_layer = r.get_layer(1).unwrap();
-    let group_layer = group_layer.as_group_layer().unwrap();
-    let sub_layer = group_layer.get_layer(0).unwrap();
-    let prop_value: bool = if let Some(&PropertyValue::BoolValue(ref v)) =
-        sub_layer.properties.get("an object group property")
-    {
-        *v
-    } else {
-        false
+    let written_size = |map: &Map| {
+        let mut tmx = Vec::new();
+        map.write_tmx(&mut tmx).unwrap();
+        tmx.len()
     };
-    assert!(prop_value);
-}
+    map.compression_level = 0;
+    let uncompressed_size = written_size(&map);
+    map.compression_level = 9;
+    assert!(written_size(&map) < uncompressed_size);
+    assert_eq!(reload_written_tmx(&map), map);
 
-#[test]
-fn test_tileset_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_base64.tmx")
+    // Missing or outdated counters are raised above the IDs in use, including nested ones.
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="32" tileheight="32" infinite="0" nextobjectid="2">
+ <group id="4" name="Group">
+  <objectgroup id="7" name="Objects">
+   <object id="9" x="0" y="0"/>
+  </objectgroup>
+ </group>
+</map>"#;
+    let path = Path::new("assets/tiled_next_ids.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
         .unwrap();
-    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) =
-        r.tilesets()[0].properties.get("tileset property")
-    {
-        v.clone()
-    } else {
-        String::new()
-    };
-    assert_eq!("tsp", prop_value);
+    assert_eq!(map.tiled_version, None);
+    assert_eq!(map.next_layer_id(), 8);
+    assert_eq!(map.next_object_id(), 10);
 }
 
 #[test]
-fn test_flipped() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_flipped.tmx")
+fn test_map_mutation() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
         .unwrap();
-    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
 
-    let t1 = layer.get_tile(0, 0).unwrap();
-    let t2 = layer.get_tile(1, 0).unwrap();
-    let t3 = layer.get_tile(0, 1).unwrap();
-    let t4 = layer.get_tile(1, 1).unwrap();
-    assert_eq!(t1.id(), t2.id());
-    assert_eq!(t2.id(), t3.id());
-    assert_eq!(t3.id(), t4.id());
-    assert!(t1.flip_d);
-    assert!(t1.flip_h);
-    assert!(t1.flip_v);
-    assert!(!t2.flip_d);
-    assert!(!t2.flip_h);
-    assert!(t2.flip_v);
-    assert!(!t3.flip_d);
-    assert!(t3.flip_h);
-    assert!(!t3.flip_v);
-    assert!(t4.flip_d);
-    assert!(!t4.flip_h);
-    assert!(!t4.flip_v);
+    // New objects get the next free ID and can be looked up right away.
+    let mut object = ObjectData::default();
+    object.name = "added".to_owned();
+    object.x = 16.0;
+    assert_eq!(map.push_object(2, object), Some(4));
+    assert_eq!(map.next_object_id(), 5);
+    assert_eq!(map.object_by_id(4).unwrap().object.name, "added");
+    // Objects can only be added to object layers.
+    assert_eq!(map.push_object(1, ObjectData::default()), None);
+    assert_eq!(map.push_object(7, ObjectData::default()), None);
+    assert_eq!(map.next_object_id(), 5);
+
+    // Removed layers can't be looked up anymore, and keep their ID when inserted again.
+    let layer = map.remove_layer(1).unwrap();
+    assert!(map.remove_layer(1).is_none());
+    assert!(map.layer_by_id(2).is_none());
+    assert!(map.object_by_id(4).is_none());
+    map.insert_layer(0, layer);
+    assert_eq!(map.get_layer(0).unwrap().id(), 2);
+    assert_eq!(map.layer_by_id(2).unwrap().name, "Object Layer 1");
+    assert_eq!(map.object_by_id(4).unwrap().object.x, 16.0);
+
+    assert_eq!(reload_written_tmx(&map), map);
 }
 
 #[test]
-fn test_ldk_export() {
-    let r = Loader::new()
-        .load_tmx_map("assets/ldk_tiled_export.tmx")
+fn test_tile_object_placement() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="isometric" width="2" height="2" tilewidth="64" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="aligned" tilewidth="32" tileheight="32" tilecount="84" columns="14" objectalignment="center" tilerendersize="grid" fillmode="preserve-aspect-fit">
+  <tileoffset x="2" y="-3"/>
+  <grid orientation="isometric" width="64" height="32"/>
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tileset>
+ <tileset firstgid="85" source="tilesheet.tsx"/>
+ <objectgroup id="1" name="Objects">
+  <object id="1" gid="1" x="100" y="50" width="64" height="32"/>
+  <object id="2" gid="85" x="10" y="40" width="32" height="32"/>
+  <object id="3" x="0" y="0" width="32" height="32"/>
+ </objectgroup>
+</map>"#;
+    let path = Path::new("assets/tiled_tile_placement.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
         .unwrap();
-    let layer = as_finite(r.get_layer(0).unwrap().as_tile_layer().unwrap());
+
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.object_alignment, ObjectAlignment::Center);
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tileset.fill_mode, FillMode::PreserveAspectFit);
+    assert_eq!(
+        tileset.grid,
+        Some(Grid {
+            orientation: GridOrientation::Isometric,
+            width: 64,
+            height: 32,
+        })
+    );
+    let tileset = &map.tilesets()[1];
+    assert_eq!(tileset.object_alignment, ObjectAlignment::Unspecified);
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Tile);
+    assert_eq!(tileset.fill_mode, FillMode::Stretch);
+    assert_eq!(tileset.grid, None);
+
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    // The object's position (100, 50) is projected to (114, 75) on screen. The image is centered on
+    // it, kept square within its 64x32 area and offset by (2, -3).
+    assert_eq!(
+        layer.get_object(0).unwrap().tile_placement(),
+        Some(TilePlacement {
+            x: 100.0,
+            y: 56.0,
+            width: 32.0,
+            height: 32.0,
+        })
+    );
+    // Unspecified alignment means bottom center on isometric maps.
+    assert_eq!(
+        layer.get_object(1).unwrap().tile_placement(),
+        Some(TilePlacement {
+            x: 18.0,
+            y: -7.0,
+            width: 32.0,
+            height: 32.0,
+        })
+    );
+    assert_eq!(layer.get_object(2).unwrap().tile_placement(), None);
+
+    assert_eq!(reload_written_tmx(&map), map);
+    #[cfg(feature = "json")]
     {
-        assert_eq!(layer.width(), 8);
-        assert_eq!(layer.height(), 8);
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj).unwrap();
+        let reloaded = loader_with_written_file(&map.source, tmj)
+            .load_tmj_map(&map.source)
+            .unwrap();
+        assert_eq!(reloaded, map);
     }
-    assert!(layer.get_tile(0, 0).is_none());
-    assert_eq!(layer.get_tile(0, 1).unwrap().id(), 0);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/ldk_tiled_export.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].object_alignment, ObjectAlignment::TopLeft);
 }
 
 #[test]
-fn test_parallax_layers() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_parallax.tmx")
+fn test_tileset_transformations() {
+    let tsx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="transformed" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <transformations hflip="1" vflip="0" rotate="1" preferuntransformed="1"/>
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>"#;
+    let path = Path::new("assets/tilesheet_transformations.tsx");
+    let tileset = loader_with_written_file(path, tsx.as_bytes().to_vec())
+        .load_tsx_tileset(path)
         .unwrap();
-    for (i, layer) in r.layers().enumerate() {
-        match i {
-            0 => {
-                assert_eq!(layer.name, "Background");
-                assert_eq!(layer.parallax_x, 0.5);
-                assert_eq!(layer.parallax_y, 0.75);
-            }
-            1 => {
-                assert_eq!(layer.name, "Middle");
-                assert_eq!(layer.parallax_x, 1.0);
-                assert_eq!(layer.parallax_y, 1.0);
-            }
-            2 => {
-                assert_eq!(layer.name, "Foreground");
-                assert_eq!(layer.parallax_x, 2.0);
-                assert_eq!(layer.parallax_y, 2.0);
-            }
-            _ => panic!("unexpected layer"),
+    assert_eq!(
+        tileset.transformations,
+        Transformations {
+            flip_h: true,
+            flip_v: false,
+            rotate: true,
+            prefer_untransformed: true,
         }
+    );
+
+    let flip = |flip_h, flip_v, flip_d| TileFlip {
+        flip_h,
+        flip_v,
+        flip_d,
+    };
+    let variants = tileset.transformations.variants();
+    assert_eq!(variants.len(), 8);
+    assert_eq!(variants[0], TileFlip::default());
+    for variant in [
+        flip(true, false, true),  // Rotated 90 degrees clockwise
+        flip(true, true, false),  // Rotated 180 degrees
+        flip(false, true, true),  // Rotated 270 degrees clockwise
+        flip(false, true, false), // Flipped vertically
+    ] {
+        assert!(variants.contains(&variant));
     }
-}
 
-#[test]
-fn test_object_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_object_property.tmx")
-        .unwrap();
-    let layer = r.get_layer(1).unwrap();
-    let prop_value = if let Some(PropertyValue::ObjectValue(v)) = layer
+    let rotate_only = Transformations {
+        rotate: true,
+        ..Transformations::default()
+    };
+    assert_eq!(
+        rotate_only.variants(),
+        vec![
+            TileFlip::default(),
+            flip(true, false, true),
+            flip(true, true, false),
+            flip(false, true, true),
+        ]
+    );
+    assert_eq!(
+        Transformations::default().variants(),
+        vec![TileFlip::default()]
+    );
+
+    let mut tsx = Vec::new();
+    tileset.write_tsx(&mut tsx).unwrap();
+    let reloaded = loader_with_written_file(path, tsx)
+        .load_tsx_tileset(path)
+        .unwrap();
+    assert_eq!(reloaded, tileset);
+
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    assert_eq!(tileset.transformations, Transformations::default());
+}
+
+#[test]
+fn test_tile_source_rects() {
+    let tsx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="collection" tilewidth="64" tileheight="64" tilecount="2" columns="0">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0" x="32" y="64" width="64" height="32">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+ <tile id="3">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+</tileset>"#;
+    let path = Path::new("assets/tilesheet_collection.tsx");
+    let tileset = loader_with_written_file(path, tsx.as_bytes().to_vec())
+        .load_tsx_tileset(path)
+        .unwrap();
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(
+        (tile.x, tile.y, tile.width, tile.height),
+        (32, 64, Some(64), Some(32))
+    );
+    assert_eq!(
+        tileset.tile_source_rect(0),
+        Some(SourceRect {
+            x: 32,
+            y: 64,
+            width: 64,
+            height: 32,
+        })
+    );
+    // Tiles without a sub-rectangle use their whole image.
+    assert_eq!(
+        tileset.tile_source_rect(3),
+        Some(SourceRect {
+            x: 0,
+            y: 0,
+            width: 448,
+            height: 192,
+        })
+    );
+    assert_eq!(tileset.tile_source_rect(1), None);
+
+    let mut tsx = Vec::new();
+    tileset.write_tsx(&mut tsx).unwrap();
+    let reloaded = loader_with_written_file(path, tsx)
+        .load_tsx_tileset(path)
+        .unwrap();
+    assert_eq!(reloaded, tileset);
+
+    let tsx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="spaced" tilewidth="16" tileheight="16" spacing="2" margin="1" tilecount="8" columns="4">
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>"#;
+    let path = Path::new("assets/tilesheet_spaced.tsx");
+    let tileset = loader_with_written_file(path, tsx.as_bytes().to_vec())
+        .load_tsx_tileset(path)
+        .unwrap();
+    assert_eq!(
+        tileset.tile_source_rect(5),
+        Some(SourceRect {
+            x: 19,
+            y: 19,
+            width: 16,
+            height: 16,
+        })
+    );
+    assert_eq!(tileset.tile_source_rect(8), None);
+}
+
+#[test]
+fn test_image_layer_repeat_and_parallax() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32" infinite="0" parallaxoriginx="100" parallaxoriginy="50">
+ <imagelayer id="1" name="Sky" offsetx="10" parallaxx="0.5" repeatx="1">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </imagelayer>
+ <objectgroup id="2" name="Objects"/>
+</map>"#;
+    let path = Path::new("assets/tiled_image_repeat.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    assert_eq!(
+        (map.parallax_origin_x, map.parallax_origin_y),
+        (100.0, 50.0)
+    );
+    let layer = map.get_layer(0).unwrap();
+    let image_layer = layer.as_image_layer().unwrap();
+    assert!(image_layer.repeat_x);
+    assert!(!image_layer.repeat_y);
+
+    // The camera is centered on x = 1250, which moves the layer by (1250 - 100) * 0.5 past its
+    // offset, to x = 585. The image is 448 pixels wide and repeated until it covers the camera.
+    let camera = CameraRect {
+        x: 1000.0,
+        y: 0.0,
+        width: 500.0,
+        height: 200.0,
+    };
+    assert_eq!(
+        layer.image_placements(camera),
+        vec![(-415.0, 0.0), (33.0, 0.0), (481.0, 0.0)]
+    );
+    // The image isn't repeated vertically, so it's out of sight of a camera below it.
+    let camera = CameraRect { y: 300.0, ..camera };
+    assert!(layer.image_placements(camera).is_empty());
+    assert!(map
+        .get_layer(1)
+        .unwrap()
+        .image_placements(camera)
+        .is_empty());
+
+    assert_eq!(reload_written_tmx(&map), map);
+    #[cfg(feature = "json")]
+    {
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj).unwrap();
+        let reloaded = loader_with_written_file(&map.source, tmj)
+            .load_tmj_map(&map.source)
+            .unwrap();
+        assert_eq!(reloaded, map);
+    }
+}
+
+#[test]
+fn test_object_draw_order_and_locked_layers() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" source="tilesheet.tsx"/>
+ <objectgroup id="1" name="Sorted" locked="1">
+  <object id="1" x="0" y="40" width="10" height="10"/>
+  <object id="2" gid="1" x="20" y="40" width="32" height="32"/>
+  <object id="3" x="0" y="10" width="10" height="10"/>
+  <object id="4" gid="1" x="20" y="20" width="32" height="32"/>
+ </objectgroup>
+ <objectgroup id="2" name="Unsorted" draworder="index">
+  <object id="5" x="0" y="40"/>
+  <object id="6" x="0" y="10"/>
+ </objectgroup>
+</map>"#;
+    let path = Path::new("assets/tiled_object_draw_order.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    let ids = |index: usize| -> Vec<u32> {
+        map.get_layer(index)
+            .unwrap()
+            .as_object_layer()
+            .unwrap()
+            .objects_in_draw_order()
+            .map(|object| object.id())
+            .collect()
+    };
+
+    let sorted = map.get_layer(0).unwrap();
+    assert!(sorted.locked);
+    assert_eq!(
+        sorted.as_object_layer().unwrap().draw_order,
+        DrawOrder::TopDown
+    );
+    // Tile objects are anchored at their bottom edge, so object 4 is drawn before object 1 even
+    // though its image reaches higher up. Objects 1 and 2 share a Y coordinate and keep their order.
+    assert_eq!(ids(0), vec![3, 4, 1, 2]);
+
+    let unsorted = map.get_layer(1).unwrap();
+    assert!(!unsorted.locked);
+    assert_eq!(
+        unsorted.as_object_layer().unwrap().draw_order,
+        DrawOrder::Index
+    );
+    assert_eq!(ids(1), vec![5, 6]);
+
+    let infinite = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let locked: Vec<&str> = infinite
+        .layers()
+        .filter(|layer| layer.locked)
+        .map(|layer| layer.name.as_str())
+        .collect();
+    assert_eq!(locked, vec!["Ground"]);
+
+    assert_eq!(reload_written_tmx(&map), map);
+    #[cfg(feature = "json")]
+    {
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj).unwrap();
+        let reloaded = loader_with_written_file(&map.source, tmj)
+            .load_tmj_map(&map.source)
+            .unwrap();
+        assert_eq!(reloaded, map);
+    }
+}
+
+#[test]
+fn test_tile_pixel_conversion() {
+    let orthogonal = load_empty_map(r#"orientation="orthogonal" tilewidth="32" tileheight="16""#);
+    assert_eq!(orthogonal.tile_to_pixel(3, 2), (96.0, 32.0));
+    assert_eq!(orthogonal.pixel_to_tile(-1.0, 20.0), (-1, 1));
+    assert_eq!(orthogonal.pixel_to_tile_fractional(48.0, 24.0), (1.5, 1.5));
+
+    // The top corner of tile (0, 0) is in the middle of the top edge of the map.
+    let isometric = load_empty_map(r#"orientation="isometric" tilewidth="64" tileheight="32""#);
+    assert_eq!(isometric.tile_to_pixel_fractional(0.0, 0.0), (320.0, 0.0));
+    assert_eq!(isometric.tile_to_pixel(0, 0), (288.0, 0.0));
+    assert_eq!(isometric.tile_to_pixel(2, 1), (320.0, 48.0));
+    assert_eq!(isometric.pixel_to_tile(352.0, 60.0), (2, 1));
+    assert_eq!(
+        isometric.pixel_to_tile_fractional(352.0, 60.0),
+        (2.375, 1.375)
+    );
+
+    let staggered = load_empty_map(
+        r#"orientation="staggered" tilewidth="64" tileheight="32" staggeraxis="y" staggerindex="odd""#,
+    );
+    assert_eq!(staggered.tile_to_pixel(0, 1), (32.0, 16.0));
+    assert_eq!(staggered.tile_to_pixel(1, 2), (64.0, 32.0));
+    assert_eq!(staggered.pixel_to_tile(32.0, 16.0), (0, 0));
+    // The corners of a tile's area belong to the diamonds of its neighbors.
+    assert_eq!(staggered.pixel_to_tile(5.0, 3.0), (-1, -1));
+    assert_eq!(staggered.pixel_to_tile(64.0, 40.0), (0, 1));
+    assert_eq!(staggered.pixel_to_tile_fractional(64.0, 40.0), (0.5, 1.75));
+
+    let hexagonal = load_empty_map(
+        r#"orientation="hexagonal" tilewidth="32" tileheight="32" hexsidelength="16" staggeraxis="y" staggerindex="odd""#,
+    );
+    assert_eq!(hexagonal.tile_to_pixel(2, 3), (80.0, 72.0));
+    assert_eq!(hexagonal.pixel_to_tile(96.0, 88.0), (2, 3));
+    assert_eq!(hexagonal.pixel_to_tile(16.0, 22.0), (0, 0));
+    assert_eq!(hexagonal.pixel_to_tile(2.0, 26.0), (-1, 1));
+
+    let hexagonal = load_empty_map(
+        r#"orientation="hexagonal" tilewidth="32" tileheight="32" hexsidelength="16" staggeraxis="x" staggerindex="even""#,
+    );
+    assert_eq!(hexagonal.tile_to_pixel(0, 0), (0.0, 16.0));
+    assert_eq!(hexagonal.tile_to_pixel(1, 0), (24.0, 0.0));
+    assert_eq!(hexagonal.tile_to_pixel(2, 1), (48.0, 48.0));
+    assert_eq!(hexagonal.pixel_to_tile(64.0, 64.0), (2, 1));
+
+    for map in [&orthogonal, &isometric, &staggered, &hexagonal] {
+        let (x, y) = map.pixel_to_tile_fractional(100.0, 75.0);
+        assert_eq!(map.tile_to_pixel_fractional(x, y), (100.0, 75.0));
+    }
+}
+
+#[test]
+fn test_tile_neighbors_and_distances() {
+    let orthogonal = load_empty_map(r#"orientation="orthogonal" tilewidth="32" tileheight="32""#);
+    let mut neighbors = orthogonal.tile_neighbors(2, 2, Connectivity::Eight);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 1),
+            (2, 3),
+            (3, 1),
+            (3, 2),
+            (3, 3)
+        ]
+    );
+    assert_eq!(
+        orthogonal.tile_distance((0, 0), (3, 1), Connectivity::Four),
+        4
+    );
+    assert_eq!(
+        orthogonal.tile_distance((0, 0), (3, 1), Connectivity::Eight),
+        3
+    );
+    assert_eq!(
+        orthogonal
+            .tiles_in_line((0, 0), (3, 1), Connectivity::Four)
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (1, 0), (1, 1), (2, 1), (3, 1)]
+    );
+    assert_eq!(
+        orthogonal
+            .tiles_in_line((0, 0), (3, 1), Connectivity::Eight)
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (1, 0), (2, 1), (3, 1)]
+    );
+    assert_eq!(
+        orthogonal.tiles_in_ring(5, 5, 2, Connectivity::Four).len(),
+        8
+    );
+    assert_eq!(
+        orthogonal.tiles_in_ring(5, 5, 2, Connectivity::Eight).len(),
+        16
+    );
+    assert_eq!(
+        orthogonal
+            .tiles_in_ring(5, 5, 0, Connectivity::Eight)
+            .collect::<Vec<_>>(),
+        vec![(5, 5)]
+    );
+
+    // Odd rows are shifted right, so the neighbors of a tile in an odd row are further right than
+    // those of a tile in an even row.
+    let hexagonal = load_empty_map(
+        r#"orientation="hexagonal" tilewidth="32" tileheight="32" hexsidelength="16" staggeraxis="y" staggerindex="odd""#,
+    );
+    let mut neighbors = hexagonal.tile_neighbors(2, 3, Connectivity::Four);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![(1, 3), (2, 2), (2, 4), (3, 2), (3, 3), (3, 4)]
+    );
+    let mut neighbors = hexagonal.tile_neighbors(2, 2, Connectivity::Four);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 2)]
+    );
+    assert_eq!(
+        hexagonal.tile_distance((0, 0), (3, 4), Connectivity::Four),
+        5
+    );
+    assert_eq!(
+        hexagonal
+            .tiles_in_line((0, 0), (3, 4), Connectivity::Four)
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (0, 1), (1, 2), (2, 2), (2, 3), (3, 4)]
+    );
+    let ring: Vec<_> = hexagonal
+        .tiles_in_ring(2, 2, 2, Connectivity::Four)
+        .collect();
+    assert_eq!(ring.len(), 12);
+    for tile in ring {
+        assert_eq!(hexagonal.tile_distance((2, 2), tile, Connectivity::Four), 2);
+    }
+
+    // Columns are staggered instead of rows, and even ones are shifted down.
+    let hexagonal = load_empty_map(
+        r#"orientation="hexagonal" tilewidth="32" tileheight="32" hexsidelength="16" staggeraxis="x" staggerindex="even""#,
+    );
+    let mut neighbors = hexagonal.tile_neighbors(2, 2, Connectivity::Four);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![(1, 2), (1, 3), (2, 1), (2, 3), (3, 2), (3, 3)]
+    );
+
+    // Staggered maps are laid out like hexagonal maps, with tiles touching the corners of a tile
+    // across the stagger axis counting as neighbors.
+    let staggered = load_empty_map(
+        r#"orientation="staggered" tilewidth="64" tileheight="32" staggeraxis="y" staggerindex="odd""#,
+    );
+    let mut neighbors = staggered.tile_neighbors(2, 2, Connectivity::Four);
+    neighbors.sort();
+    assert_eq!(
+        neighbors,
+        vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 2)]
+    );
+    assert_eq!(
+        staggered.tile_distance((2, 2), (2, 4), Connectivity::Four),
+        2
+    );
+}
+
+#[test]
+fn test_find_path() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="5" height="3" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="terrain" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+  <image source="tilesheet.png" width="448" height="192"/>
+  <tile id="1">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+  <tile id="2">
+   <properties>
+    <property name="cost" type="int" value="5"/>
+   </properties>
+  </tile>
+  <tile id="3">
+   <properties>
+    <property name="cost" type="float" value="2.5"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="5" height="3">
+  <data encoding="csv">
+1,2,1,1,1,
+1,2,3,2,1,
+1,1,1,2,1
+</data>
+ </layer>
+ <layer id="2" name="Mud" width="5" height="3">
+  <data encoding="csv">
+0,0,0,0,0,
+4,0,0,0,0,
+0,0,0,0,0
+</data>
+ </layer>
+</map>"#;
+    let path = Path::new("assets/tiled_pathfinding.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    let layers: Vec<TileLayer> = map
+        .layers()
+        .filter_map(|layer| layer.as_tile_layer())
+        .collect();
+
+    // The solid column can only be passed through the expensive tile below it, and the mud on the
+    // second layer makes the tile below the first one more expensive.
+    let found = map
+        .find_path((0, 0), (4, 0), &layers, Connectivity::Four)
+        .unwrap();
+    assert_eq!(
+        found.tiles,
+        vec![
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+            (2, 0),
+            (3, 0),
+            (4, 0)
+        ]
+    );
+    assert_eq!(found.cost, 13.5);
+    assert_eq!(
+        map.find_path((0, 0), (1, 0), &layers, Connectivity::Four),
+        None
+    );
+    assert_eq!(
+        map.find_path((0, 0), (5, 0), &layers, Connectivity::Four),
+        None
+    );
+
+    // Ignoring the properties of the tiles allows walking straight through.
+    let found = map
+        .find_path_by(
+            (0, 0),
+            (4, 0),
+            &layers,
+            Connectivity::Four,
+            1.0,
+            |_, tiles| tiles[0].map(|_| 1.0),
+        )
+        .unwrap();
+    assert_eq!(found.tiles, vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+    assert_eq!(found.cost, 4.0);
+
+    // Costs below 1 are kept, so a longer path over cheap tiles is preferred, while tiles with a
+    // negative or infinite cost are impassable.
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="5" height="3" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="terrain" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+  <image source="tilesheet.png" width="448" height="192"/>
+  <tile id="0">
+   <properties>
+    <property name="cost" type="int" value="-3"/>
+   </properties>
+  </tile>
+  <tile id="1">
+   <properties>
+    <property name="cost" type="float" value="0.5"/>
+   </properties>
+  </tile>
+  <tile id="2">
+   <properties>
+    <property name="cost" type="float" value="inf"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="5" height="3">
+  <data encoding="csv">
+4,4,4,4,4,
+2,2,2,2,2,
+1,3,4,4,4
+</data>
+ </layer>
+</map>"#;
+    let path = Path::new("assets/tiled_pathfinding_costs.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    let layers: Vec<TileLayer> = map
+        .layers()
+        .filter_map(|layer| layer.as_tile_layer())
+        .collect();
+    let found = map
+        .find_path((0, 0), (4, 0), &layers, Connectivity::Four)
+        .unwrap();
+    assert_eq!(
+        found.tiles,
+        vec![(0, 0), (0, 1), (1, 1), (2, 1), (3, 1), (4, 1), (4, 0)]
+    );
+    assert_eq!(found.cost, 3.5);
+    assert_eq!(
+        map.find_path((0, 1), (0, 2), &layers, Connectivity::Four),
+        None
+    );
+    assert_eq!(
+        map.find_path((2, 2), (1, 2), &layers, Connectivity::Four),
+        None
+    );
+    assert_eq!(
+        map.find_path_by((0, 0), (3, 1), &layers, Connectivity::Four, 0.0, |_, _| {
+            Some(-1.0)
+        }),
+        None
+    );
+
+    // Paths on infinite maps can cross chunks, but not leave them.
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32" infinite="1">
+ <tileset firstgid="1" source="tilesheet.tsx"/>
+ <layer id="1" name="Ground" width="10" height="10">
+  <data encoding="csv">
+   <chunk x="-4" y="0" width="8" height="2">
+1,1,1,1,1,1,1,1,
+1,0,0,0,0,0,0,0
+</chunk>
+  </data>
+ </layer>
+</map>"#;
+    let path = Path::new("assets/tiled_pathfinding_infinite.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    let layers: Vec<TileLayer> = map
+        .layers()
+        .filter_map(|layer| layer.as_tile_layer())
+        .collect();
+    let found = map
+        .find_path((-4, 1), (3, 0), &layers, Connectivity::Eight)
+        .unwrap();
+    assert_eq!(found.tiles.len(), 8);
+    assert_eq!(found.cost, 7.0);
+    assert_eq!(
+        map.find_path((-4, 1), (3, 1), &layers, Connectivity::Eight),
+        None
+    );
+}
+
+#[test]
+fn test_leaf_layers() {
+    let tmx = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32" infinite="0">
+ <objectgroup id="1" name="Top" offsetx="5"/>
+ <group id="2" name="Outer" offsetx="10" offsety="-4" opacity="0.5" tintcolor="#ff8000" parallaxx="0.5">
+  <objectgroup id="3" name="Hidden" visible="0" opacity="0.5"/>
+  <group id="4" name="Inner" offsety="6" tintcolor="#80ffffff" parallaxx="0.5" parallaxy="2">
+   <imagelayer id="5" name="Sky" offsetx="1" repeatx="1">
+    <image source="tilesheet.png" width="448" height="192"/>
+   </imagelayer>
+  </group>
+  <group id="6" name="Empty"/>
+ </group>
+</map>"##;
+    let path = Path::new("assets/tiled_leaf_layers.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+    let leaves: Vec<EffectiveLayer> = map.leaf_layers().collect();
+    let names: Vec<&str> = leaves.iter().map(|leaf| leaf.layer.name.as_str()).collect();
+    assert_eq!(names, vec!["Top", "Hidden", "Sky"]);
+
+    // Layers that aren't in a group are unaffected.
+    let top = &leaves[0];
+    assert_eq!((top.offset_x, top.offset_y, top.opacity), (5.0, 0.0, 1.0));
+    assert!(top.visible);
+    assert_eq!(top.tint_color, None);
+
+    let hidden = &leaves[1];
+    assert_eq!((hidden.offset_x, hidden.offset_y), (10.0, -4.0));
+    assert_eq!(hidden.opacity, 0.25);
+    assert!(!hidden.visible);
+    assert_eq!((hidden.parallax_x, hidden.parallax_y), (0.5, 1.0));
+    assert_eq!(
+        hidden.tint_color,
+        Some(Color {
+            alpha: 255,
+            red: 255,
+            green: 128,
+            blue: 0
+        })
+    );
+
+    let sky = &leaves[2];
+    assert_eq!((sky.offset_x, sky.offset_y), (11.0, 2.0));
+    assert_eq!(sky.opacity, 0.5);
+    assert!(sky.visible);
+    assert_eq!((sky.parallax_x, sky.parallax_y), (0.25, 2.0));
+    // Each channel is multiplied separately, including the alpha channel.
+    assert_eq!(
+        sky.tint_color,
+        Some(Color {
+            alpha: 128,
+            red: 255,
+            green: 128,
+            blue: 0
+        })
+    );
+
+    // The camera is centered on (160, 120), so the image moves by (1 - 0.25) * 160 = 120 pixels
+    // to the right of its offset and by (1 - 2) * 120 = -120 pixels down.
+    let camera = CameraRect {
+        x: 0.0,
+        y: 0.0,
+        width: 320.0,
+        height: 240.0,
+    };
+    assert_eq!(
+        sky.image_placements(camera),
+        vec![(-317.0, -118.0), (131.0, -118.0)]
+    );
+    assert_eq!(
+        sky.layer.image_placements(camera),
+        vec![(-447.0, 0.0), (1.0, 0.0)]
+    );
+}
+
+#[test]
+fn test_image_layers() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert_eq!(r.layers().len(), 2);
+    let mut image_layers = r.layers().map(|layer| {
+        if let LayerType::Image(img) = layer.layer_type() {
+            (img, layer)
+        } else {
+            panic!("Found layer that isn't an image layer")
+        }
+    });
+    {
+        let first = image_layers.next().unwrap();
+        assert_eq!(first.1.name, "Image Layer 1");
+        assert!(
+            first.0.image.is_none(),
+            "{}'s image should be None",
+            first.1.name
+        );
+    }
+    {
+        let second = image_layers.next().unwrap();
+        assert_eq!(second.1.name, "Image Layer 2");
+        let image = second
+            .0
+            .image
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}'s image shouldn't be None", second.1.name));
+        assert_eq!(
+            image.source,
+            ImageSource::File(PathBuf::from("assets/tilesheet.png"))
+        );
+        assert_eq!(image.width, 448);
+        assert_eq!(image.height, 192);
+    }
+}
+
+#[test]
+fn test_embedded_images() {
+    // "iVBORw0KGgo=" is the PNG signature.
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <tileset firstgid="1" name="embedded" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image format="png" width="32" height="32">
+   <data encoding="base64">iVBORw0KGgo=</data>
+  </image>
+ </tileset>
+ <tileset firstgid="2" name="collection" tilewidth="32" tileheight="32" tilecount="1" columns="0">
+  <tile id="0">
+   <image format="png" width="32" height="32">
+    <data encoding="base64">iVBORw0KGgo=</data>
+   </image>
+  </tile>
+ </tileset>
+ <imagelayer id="1" name="Image Layer 1">
+  <image format="png" width="32" height="32">
+   <data encoding="base64">iVBORw0KGgo=</data>
+  </image>
+ </imagelayer>
+</map>"#;
+    let path = Path::new("assets/tiled_embedded_images.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+
+    let png_signature = ImageSource::Embedded(b"\x89PNG\r\n\x1a\n".to_vec());
+    let tileset_image = map.tilesets()[0].image.as_ref().unwrap();
+    assert_eq!(tileset_image.source, png_signature);
+    assert_eq!(tileset_image.format.as_deref(), Some("png"));
+    let tile_image = map.tilesets()[1]
+        .get_tile(0)
+        .unwrap()
+        .image
+        .clone()
+        .unwrap();
+    assert_eq!(tile_image.source, png_signature);
+    let layer_image = match map.get_layer(0).unwrap().layer_type() {
+        LayerType::Image(layer) => layer.image.clone().unwrap(),
+        _ => panic!("Not an image layer"),
+    };
+    assert_eq!(layer_image.source, png_signature);
+    assert_eq!(layer_image.source.path(), None);
+
+    assert_eq!(reload_written_tmx(&map), map);
+
+    #[cfg(feature = "json")]
+    assert!(map.write_tmj(Vec::new()).is_err());
+}
+
+#[test]
+fn test_preserve_unknown_xml() {
+    let tmx = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0" foo="bar">
+ <editorsettings>
+  <export target="out.tmj" format="json"/>
+ </editorsettings>
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1" extra="1">
+  <image source="tilesheet.png" width="32" height="32"/>
+  <tile id="0" note="first">
+   <custom>text</custom>
+  </tile>
+ </tileset>
+ <ed:meta xmlns:ed="urn:example:editor" ed:flag="1"/>
+ <objectgroup id="1" name="Objects" color="#ff0000" hint="x">
+  <notes>Remember this</notes>
+  <object id="1" x="0" y="0" mark="1">
+   <extra a="b"/>
+  </object>
+ </objectgroup>
+</map>"##;
+    let path = Path::new("assets/tiled_unknown_xml.tmx");
+    let mut loader = loader_with_written_file(path, tmx.as_bytes().to_vec());
+    assert!(loader.load_tmx_map(path).unwrap().unknown.is_empty());
+
+    loader.set_preserve_unknown_xml(true);
+    let map = loader.load_tmx_map(path).unwrap();
+    let attr = |name: &str, value: &str| (name.to_owned(), value.to_owned());
+    assert_eq!(map.unknown.attributes, vec![attr("foo", "bar")]);
+    assert_eq!(
+        map.unknown.elements,
+        vec![
+            (
+                0,
+                XmlElement {
+                    name: "editorsettings".to_owned(),
+                    prefix: None,
+                    namespace: None,
+                    attributes: Vec::new(),
+                    children: vec![XmlNode::Element(XmlElement {
+                        name: "export".to_owned(),
+                        prefix: None,
+                        namespace: None,
+                        attributes: vec![attr("target", "out.tmj"), attr("format", "json")],
+                        children: Vec::new(),
+                    })],
+                }
+            ),
+            (
+                2,
+                XmlElement {
+                    name: "meta".to_owned(),
+                    prefix: Some("ed".to_owned()),
+                    namespace: Some("urn:example:editor".to_owned()),
+                    attributes: vec![attr("ed:flag", "1")],
+                    children: Vec::new(),
+                }
+            ),
+        ]
+    );
+
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.unknown.attributes, vec![attr("extra", "1")]);
+    assert!(tileset.unknown.elements.is_empty());
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(tile.unknown.attributes, vec![attr("note", "first")]);
+    assert_eq!(
+        tile.unknown.elements[0].1.children,
+        vec![XmlNode::Text("text".to_owned())]
+    );
+
+    let layer = map.get_layer(0).unwrap();
+    assert_eq!(layer.unknown.attributes, vec![attr("hint", "x")]);
+    assert_eq!(layer.unknown.elements[0].0, 0);
+    assert_eq!(layer.unknown.elements[0].1.name, "notes");
+    let object = match layer.layer_type() {
+        LayerType::Objects(layer) => layer.get_object(0).unwrap(),
+        _ => panic!("Not an object layer"),
+    };
+    assert_eq!(object.unknown.attributes, vec![attr("mark", "1")]);
+    assert_eq!(
+        object.unknown.elements[0].1.attributes,
+        vec![attr("a", "b")]
+    );
+
+    let mut tmx = Vec::new();
+    map.write_tmx(&mut tmx).unwrap();
+    let written = String::from_utf8(tmx.clone()).unwrap();
+    assert!(written.contains(r#"foo="bar""#));
+    assert!(written.contains("<editorsettings>"));
+    assert!(written.contains(r#"<export target="out.tmj" format="json""#));
+    assert!(written.contains(r#"<ed:meta xmlns:ed="urn:example:editor" ed:flag="1""#));
+    // Unknown elements are written back among the known ones, where they were.
+    let position = |text: &str| written.find(text).unwrap();
+    assert!(position("<editorsettings>") < position("<tileset"));
+    assert!(position("</tileset>") < position("<ed:meta"));
+    assert!(position("<ed:meta") < position("<objectgroup"));
+    assert!(position("<notes>") < position("<object "));
+    let mut loader = loader_with_written_file(path, tmx);
+    loader.set_preserve_unknown_xml(true);
+    assert_eq!(loader.load_tmx_map(path).unwrap(), map);
+}
+
+#[test]
+fn test_tile_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) = r.tilesets()[0]
+        .get_tile(1)
+        .unwrap()
+        .properties
+        .get("a tile property")
+    {
+        v.clone()
+    } else {
+        String::new()
+    };
+    assert_eq!("123", prop_value);
+}
+
+#[test]
+fn test_layer_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) =
+        r.get_layer(0).unwrap().properties.get("prop3")
+    {
+        v.clone()
+    } else {
+        String::new()
+    };
+    assert_eq!("Line 1\r\nLine 2\r\nLine 3,\r\n  etc\r\n   ", prop_value);
+}
+
+#[test]
+fn test_object_group_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_object_groups.tmx")
+        .unwrap();
+    let group_layer = r.get_layer(1).unwrap();
+    let group_layer = group_layer.as_group_layer().unwrap();
+    let sub_layer = group_layer.get_layer(0).unwrap();
+    let prop_value: bool = if let Some(&PropertyValue::BoolValue(ref v)) =
+        sub_layer.properties.get("an object group property")
+    {
+        *v
+    } else {
+        false
+    };
+    assert!(prop_value);
+}
+
+#[test]
+fn test_tileset_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) =
+        r.tilesets()[0].properties.get("tileset property")
+    {
+        v.clone()
+    } else {
+        String::new()
+    };
+    assert_eq!("tsp", prop_value);
+}
+
+#[test]
+fn test_flipped() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
+
+    let t1 = layer.get_tile(0, 0).unwrap();
+    let t2 = layer.get_tile(1, 0).unwrap();
+    let t3 = layer.get_tile(0, 1).unwrap();
+    let t4 = layer.get_tile(1, 1).unwrap();
+    assert_eq!(t1.id(), t2.id());
+    assert_eq!(t2.id(), t3.id());
+    assert_eq!(t3.id(), t4.id());
+    assert!(t1.flip_d);
+    assert!(t1.flip_h);
+    assert!(t1.flip_v);
+    assert!(!t2.flip_d);
+    assert!(!t2.flip_h);
+    assert!(t2.flip_v);
+    assert!(!t3.flip_d);
+    assert!(t3.flip_h);
+    assert!(!t3.flip_v);
+    assert!(t4.flip_d);
+    assert!(!t4.flip_h);
+    assert!(!t4.flip_v);
+}
+
+#[test]
+fn test_ldk_export() {
+    let r = Loader::new()
+        .load_tmx_map("assets/ldk_tiled_export.tmx")
+        .unwrap();
+    let layer = as_finite(r.get_layer(0).unwrap().as_tile_layer().unwrap());
+    {
+        assert_eq!(layer.width(), 8);
+        assert_eq!(layer.height(), 8);
+    }
+    assert!(layer.get_tile(0, 0).is_none());
+    assert_eq!(layer.get_tile(0, 1).unwrap().id(), 0);
+}
+
+#[test]
+fn test_parallax_layers() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_parallax.tmx")
+        .unwrap();
+    for (i, layer) in r.layers().enumerate() {
+        match i {
+            0 => {
+                assert_eq!(layer.name, "Background");
+                assert_eq!(layer.parallax_x, 0.5);
+                assert_eq!(layer.parallax_y, 0.75);
+            }
+            1 => {
+                assert_eq!(layer.name, "Middle");
+                assert_eq!(layer.parallax_x, 1.0);
+                assert_eq!(layer.parallax_y, 1.0);
+            }
+            2 => {
+                assert_eq!(layer.name, "Foreground");
+                assert_eq!(layer.parallax_x, 2.0);
+                assert_eq!(layer.parallax_y, 2.0);
+            }
+            _ => panic!("unexpected layer"),
+        }
+    }
+}
+
+#[test]
+fn test_object_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let layer = r.get_layer(1).unwrap();
+    let prop_value = if let Some(PropertyValue::ObjectValue(v)) = layer
         .as_object_layer()
         .unwrap()
         .get_object(0)
@@ -418,30 +1784,218 @@ fn test_object_property() {
 }
 
 #[test]
-fn test_class_property() {
-    let r = Loader::new()
-        .load_tmx_map("assets/tiled_class_property.tmx")
+fn test_class_property() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_class_property.tmx")
+        .unwrap();
+    let layer = r.get_layer(1).unwrap();
+    if let Some(PropertyValue::ClassValue {
+        property_type,
+        properties,
+    }) = layer
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap()
+        .properties
+        .get("class property")
+    {
+        assert_eq!(property_type, "test_type");
+        assert_eq!(
+            properties.get("test_property_1").unwrap(),
+            &PropertyValue::IntValue(3)
+        );
+    } else {
+        panic!("Expected class property");
+    };
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_project() {
+    let project = Loader::new()
+        .load_project("assets/tiled_project.tiled-project")
+        .unwrap();
+    assert_eq!(project.folders, vec![PathBuf::from("assets")]);
+    assert_eq!(project.property_types.len(), 6);
+
+    let stats = project.class("Stats").unwrap();
+    assert_eq!(stats.id, 1);
+    assert_eq!(stats.use_as, vec![ClassUsage::Property, ClassUsage::Object]);
+    assert_eq!(stats.members.len(), 6);
+    assert_eq!(stats.members["health"], PropertyValue::IntValue(100));
+    assert_eq!(
+        stats.members["inner"],
+        PropertyValue::ClassValue {
+            property_type: "Inner".to_owned(),
+            properties: HashMap::new(),
+        }
+    );
+    assert_eq!(
+        stats.members["kind"],
+        PropertyValue::EnumValue {
+            property_type: "Kind".to_owned(),
+            value: RawEnumValue::String("Fire".to_owned()),
+            names: None,
+        }
+    );
+    assert!(!project.class("Inner").unwrap().draw_fill);
+
+    let kind = project.enum_type("Kind").unwrap();
+    assert_eq!(kind.storage_type, EnumStorageType::String);
+    assert_eq!(kind.values, ["Fire", "Water", "Earth"]);
+    assert!(!kind.values_as_flags);
+    assert!(project.class("Kind").is_none());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_project_class_defaults() {
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/tiled_project.tiled-project")
+        .unwrap();
+
+    // Without a project, only the members set in the file are present.
+    let map = loader
+        .load_tmx_map("assets/tiled_project_classes.tmx")
+        .unwrap();
+    match &map.properties["stats"] {
+        PropertyValue::ClassValue { properties, .. } => assert_eq!(properties.len(), 3),
+        _ => panic!("Expected class property"),
+    }
+
+    loader.set_project(Some(project));
+    let expected_inner = PropertyValue::ClassValue {
+        property_type: "Inner".to_owned(),
+        properties: HashMap::from([
+            (
+                "label".to_owned(),
+                PropertyValue::StringValue("inner default".to_owned()),
+            ),
+            ("target".to_owned(), PropertyValue::ObjectValue(3)),
+        ]),
+    };
+    for map in [
+        loader
+            .load_tmx_map("assets/tiled_project_classes.tmx")
+            .unwrap(),
+        loader
+            .load_tmj_map("assets/tiled_project_classes.tmj")
+            .unwrap(),
+    ] {
+        let stats = match &map.properties["stats"] {
+            PropertyValue::ClassValue { properties, .. } => properties,
+            _ => panic!("Expected class property"),
+        };
+        assert_eq!(stats.len(), 6);
+        assert_eq!(stats["enabled"], PropertyValue::BoolValue(true));
+        assert_eq!(stats["health"], PropertyValue::IntValue(50));
+        assert_eq!(stats["speed"], PropertyValue::FloatValue(3.0));
+        assert_eq!(
+            stats["tint"],
+            PropertyValue::ColorValue(Color {
+                alpha: 0xFF,
+                red: 0x00,
+                green: 0xFF,
+                blue: 0x00,
+            })
+        );
+        assert_eq!(stats["inner"], expected_inner);
+        assert_eq!(
+            stats["kind"],
+            PropertyValue::EnumValue {
+                property_type: "Kind".to_owned(),
+                value: RawEnumValue::String("Fire".to_owned()),
+                names: Some(vec!["Fire".to_owned()]),
+            }
+        );
+
+        // Class properties left entirely to their defaults get all of their members.
+        let layer = map.get_layer(0).unwrap();
+        let object = layer.as_object_layer().unwrap().get_object(0).unwrap();
+        let stats = match &object.properties["stats"] {
+            PropertyValue::ClassValue { properties, .. } => properties,
+            _ => panic!("Expected class property"),
+        };
+        assert_eq!(stats["health"], PropertyValue::IntValue(100));
+        assert_eq!(stats["speed"], PropertyValue::FloatValue(2.5));
+    }
+}
+
+#[test]
+fn test_enum_properties() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_enum_properties.tmx")
         .unwrap();
-    let layer = r.get_layer(1).unwrap();
-    if let Some(PropertyValue::ClassValue {
-        property_type,
-        properties,
-    }) = layer
-        .as_object_layer()
-        .unwrap()
-        .get_object(0)
-        .unwrap()
-        .properties
-        .get("class property")
-    {
-        assert_eq!(property_type, "test_type");
-        assert_eq!(
-            properties.get("test_property_1").unwrap(),
-            &PropertyValue::IntValue(3)
-        );
-    } else {
-        panic!("Expected class property");
+    assert_eq!(
+        map.properties["kind"],
+        PropertyValue::EnumValue {
+            property_type: "Kind".to_owned(),
+            value: RawEnumValue::String("Water".to_owned()),
+            names: None,
+        }
+    );
+    assert_eq!(
+        map.properties["door"],
+        PropertyValue::EnumValue {
+            property_type: "DoorFlags".to_owned(),
+            value: RawEnumValue::Int(5),
+            names: None,
+        }
+    );
+    match &map.properties["stats"] {
+        PropertyValue::ClassValue { properties, .. } => assert_eq!(
+            properties["kind"],
+            PropertyValue::EnumValue {
+                property_type: "Kind".to_owned(),
+                value: RawEnumValue::String("Earth".to_owned()),
+                names: None,
+            }
+        ),
+        _ => panic!("Expected class property"),
+    }
+
+    assert_eq!(reload_written_tmx(&map), map);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_project_enums() {
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/tiled_project.tiled-project")
+        .unwrap();
+    loader.set_project(Some(project));
+    let map = loader
+        .load_tmx_map("assets/tiled_enum_properties.tmx")
+        .unwrap();
+
+    let names = |name: &str| match &map.properties[name] {
+        PropertyValue::EnumValue { names, .. } => names.clone(),
+        _ => panic!("Expected enum property"),
     };
+    let owned = |names: &[&str]| Some(names.iter().map(|n| n.to_string()).collect::<Vec<_>>());
+    assert_eq!(names("kind"), owned(&["Water"]));
+    assert_eq!(names("state"), owned(&["Chase"]));
+    assert_eq!(names("door"), owned(&["Locked", "Trapped"]));
+    assert_eq!(names("tags"), owned(&["Red", "Blue"]));
+    assert_eq!(names("no flags"), owned(&[]));
+    assert_eq!(names("unknown"), None);
+
+    match &map.properties["stats"] {
+        PropertyValue::ClassValue { properties, .. } => {
+            assert_eq!(
+                properties["kind"],
+                PropertyValue::EnumValue {
+                    property_type: "Kind".to_owned(),
+                    value: RawEnumValue::String("Earth".to_owned()),
+                    names: Some(vec!["Earth".to_owned()]),
+                }
+            );
+        }
+        _ => panic!("Expected class property"),
+    }
 }
 
 #[test]
@@ -521,6 +2075,523 @@ fn test_group_layers() {
     );
 }
 
+#[test]
+fn test_layer_lookup() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let names = |layers: Vec<tiled::Layer>| -> Vec<String> {
+        layers.iter().map(|layer| layer.name.clone()).collect()
+    };
+
+    let tile_3 = map.layer_by_id(9).unwrap();
+    assert_eq!(tile_3.name, "tile-3");
+    assert_eq!(names(tile_3.parents()), vec!["group-2", "group-3"]);
+    assert_eq!(map.layer_by_path("group-2/group-3/tile-3"), Some(tile_3));
+
+    let group_3 = map.layer_by_path("group-2/group-3").unwrap();
+    assert_eq!(group_3.id(), 8);
+    assert_eq!(names(group_3.parents()), vec!["group-2"]);
+    assert_eq!(map.layer_by_id(1).unwrap().name, "tile-1");
+    assert!(map.layer_by_id(1).unwrap().parents().is_empty());
+
+    // Layers can only be found by their full path, and IDs that aren't used don't match anything.
+    assert_eq!(map.layer_by_path("tile-3"), None);
+    assert_eq!(map.layer_by_path("group-3/tile-3"), None);
+    assert_eq!(map.layer_by_id(2), None);
+}
+
+#[test]
+fn test_object_lookup() {
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="10" height="10" tilewidth="32" tileheight="32" infinite="0">
+ <objectgroup id="1" name="Switches">
+  <object id="1" name="Switch" x="0" y="0">
+   <properties>
+    <property name="door" type="object" value="2"/>
+    <property name="missing" type="object" value="7"/>
+    <property name="unset" type="object" value="0"/>
+    <property name="label" value="Open"/>
+   </properties>
+  </object>
+ </objectgroup>
+ <group id="2" name="Building">
+  <group id="3" name="Inside">
+   <objectgroup id="4" name="Doors">
+    <object id="3" name="Back door" x="64" y="0"/>
+    <object id="2" name="Front door" x="32" y="0"/>
+   </objectgroup>
+  </group>
+ </group>
+</map>"#;
+    let path = Path::new("assets/tiled_object_lookup.tmx");
+    let map = loader_with_written_file(path, tmx.as_bytes().to_vec())
+        .load_tmx_map(path)
+        .unwrap();
+
+    let found = map.object_by_id(2).unwrap();
+    let door = found.object;
+    assert_eq!(door.name, "Front door");
+    assert_eq!(found.object_layer.get_object(1).unwrap(), door);
+    let layer = found.object_layer.layer();
+    assert_eq!(layer.name, "Doors");
+    assert_eq!(layer.id(), 4);
+    assert_eq!(layer.as_object_layer(), Some(found.object_layer));
+    assert!(map.object_by_id(7).is_none());
+
+    let switch = map.object_by_id(1).unwrap().object;
+    let linked = map.resolve_object_properties(&switch.properties);
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked["door"], door);
+
+    // Members of class properties are named after their path.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_project_classes.tmx")
+        .unwrap();
+    let linked = map.resolve_object_properties(&map.properties);
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked["stats.inner.target"].id(), 3);
+}
+
+#[test]
+fn test_file_properties() {
+    let path = Path::new("assets/tiled_linked_files.tmx");
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <properties>
+  <property name="next_level" type="file" value="tiled_base64_zlib.tmx"/>
+  <property name="tileset" type="file" value="tilesheet.tsx"/>
+  <property name="template" type="file" value="tiled_object_template.tx"/>
+  <property name="image" type="file" value="folder/../tilesheet.png"/>
+  <property name="unset" type="file" value=""/>
+  <property name="name" value="tiled_base64_zlib.tmx"/>
+ </properties>
+</map>"#;
+    let mut loader = loader_with_written_file(path, tmx.as_bytes().to_vec());
+    let map = loader.load_tmx_map(path).unwrap();
+    let resolve = |name: &str| map.resolve_file_property(&map.properties[name]);
+
+    assert_eq!(
+        resolve("next_level"),
+        Some(PathBuf::from("assets/tiled_base64_zlib.tmx"))
+    );
+    assert_eq!(
+        resolve("image"),
+        Some(PathBuf::from("assets/folder/../tilesheet.png"))
+    );
+    assert_eq!(resolve("unset"), None);
+    assert_eq!(resolve("name"), None);
+
+    match loader.load_linked_file(resolve("next_level").unwrap()) {
+        Ok(Some(LinkedFile::Map(level))) => assert_eq!(level.layers().len(), 2),
+        other => panic!("expected a map, got {:?}", other),
+    }
+    let tileset = match loader.load_linked_file(resolve("tileset").unwrap()) {
+        Ok(Some(LinkedFile::Tileset(tileset))) => tileset,
+        other => panic!("expected a tileset, got {:?}", other),
+    };
+    assert_eq!(
+        tileset.resolve_file_property(&PropertyValue::FileValue("level.tmx".to_owned())),
+        Some(PathBuf::from("assets/level.tmx"))
+    );
+    let template = match loader.load_linked_file(resolve("template").unwrap()) {
+        Ok(Some(LinkedFile::Template(template))) => template,
+        other => panic!("expected a template, got {:?}", other),
+    };
+    // Templates are shared through the cache.
+    assert!(std::sync::Arc::ptr_eq(
+        &template,
+        &loader
+            .cache()
+            .get_template("assets/tiled_object_template.tx")
+            .unwrap()
+    ));
+    assert_eq!(
+        template.resolve_file_property(&PropertyValue::FileValue("../level.tmx".to_owned())),
+        Some(PathBuf::from("assets/../level.tmx"))
+    );
+    assert!(loader
+        .load_linked_file(resolve("image").unwrap())
+        .unwrap()
+        .is_none());
+
+    // File properties inherited from a template in another directory are kept as they are, and
+    // resolved against the template.
+    let template_path = Path::new("assets/templates/tiled_linked_files.tx");
+    let tx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<template>
+ <object width="32" height="32">
+  <properties>
+   <property name="level" type="file" value="../tiled_base64_zlib.tmx"/>
+   <property name="door" type="class" propertytype="Door">
+    <properties>
+     <property name="target" type="file" value="tilesheet.tsx"/>
+    </properties>
+   </property>
+   <property name="overridden" type="file" value="template.tmx"/>
+  </properties>
+ </object>
+</template>"#;
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <objectgroup id="1" name="Objects">
+  <object id="1" template="templates/tiled_linked_files.tx" x="0" y="0">
+   <properties>
+    <property name="overridden" type="file" value="map.tmx"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"#;
+    let mut loader = Loader::with_reader(move |p: &Path| -> std::io::Result<_> {
+        if p == path {
+            Ok(Cursor::new(tmx.as_bytes().to_vec()))
+        } else if p == template_path {
+            Ok(Cursor::new(tx.as_bytes().to_vec()))
+        } else {
+            std::fs::read(p).map(Cursor::new)
+        }
+    });
+    let map = loader.load_tmx_map(path).unwrap();
+    let object = map
+        .get_layer(0)
+        .unwrap()
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap();
+    assert_eq!(
+        object.properties["level"],
+        PropertyValue::FileValue("../tiled_base64_zlib.tmx".to_owned())
+    );
+    let level = object.resolve_file_property("level").unwrap();
+    assert_eq!(
+        level,
+        PathBuf::from("assets/templates/../tiled_base64_zlib.tmx")
+    );
+    assert_eq!(
+        level,
+        object
+            .template()
+            .unwrap()
+            .resolve_file_property(&object.template().unwrap().object.properties["level"])
+            .unwrap()
+    );
+    assert_eq!(
+        object.resolve_file_property("door.target"),
+        Some(PathBuf::from("assets/templates/tilesheet.tsx"))
+    );
+    assert_eq!(object.resolve_file_property("door.missing"), None);
+    assert_eq!(
+        object.resolve_file_property("overridden"),
+        Some(PathBuf::from("assets/map.tmx"))
+    );
+
+    // Only the overridden property is written back, since the others are inherited.
+    let mut written = Vec::new();
+    map.write_tmx(&mut written).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.contains(r#"value="map.tmx""#));
+    assert!(!written.contains(r#"name="level""#));
+
+    match loader.load_linked_file(level) {
+        Ok(Some(LinkedFile::Map(level))) => assert_eq!(level.layers().len(), 2),
+        other => panic!("expected a map, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_linked_files_with_project() {
+    let stats = r#"<properties>
+  <property name="stats" type="class" propertytype="Stats">
+   <properties>
+    <property name="health" type="int" value="50"/>
+   </properties>
+  </property>
+ </properties>"#;
+    let files = HashMap::from([
+        (
+            PathBuf::from("assets/linked/level.tmx"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ {}
+ <objectgroup id="1" name="Objects">
+  <object id="1" template="door.tx" x="0" y="0"/>
+ </objectgroup>
+</map>"#,
+                stats
+            ),
+        ),
+        (
+            PathBuf::from("assets/linked/tiles.tsx"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="tiles" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ {}
+ <image source="../tilesheet.png" width="448" height="192"/>
+</tileset>"#,
+                stats
+            ),
+        ),
+        (
+            PathBuf::from("assets/linked/door.tx"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<template>
+ <object width="32" height="32">
+ {}
+ </object>
+</template>"#,
+                stats
+            ),
+        ),
+    ]);
+    let mut loader = Loader::with_reader(move |p: &Path| -> std::io::Result<_> {
+        match files.get(p) {
+            Some(file) => Ok(Cursor::new(file.clone().into_bytes())),
+            None => std::fs::read(p).map(Cursor::new),
+        }
+    });
+    let project = loader
+        .load_project("assets/tiled_project.tiled-project")
+        .unwrap();
+    loader.set_project(Some(project));
+
+    // Every kind of linked file has the missing members of its class properties filled in.
+    let stats_members = |properties: &HashMap<String, PropertyValue>| match &properties["stats"] {
+        PropertyValue::ClassValue { properties, .. } => {
+            assert_eq!(properties["health"], PropertyValue::IntValue(50));
+            properties.len()
+        }
+        _ => panic!("Expected class property"),
+    };
+    match loader.load_linked_file("assets/linked/level.tmx") {
+        Ok(Some(LinkedFile::Map(map))) => {
+            assert_eq!(stats_members(&map.properties), 6);
+            let object = map.object_by_id(1).unwrap().object;
+            assert_eq!(stats_members(&object.properties), 6);
+            assert_eq!(
+                stats_members(&object.template().unwrap().object.properties),
+                6
+            );
+        }
+        other => panic!("expected a map, got {:?}", other),
+    }
+    match loader.load_linked_file("assets/linked/tiles.tsx") {
+        Ok(Some(LinkedFile::Tileset(tileset))) => {
+            assert_eq!(stats_members(&tileset.properties), 6)
+        }
+        other => panic!("expected a tileset, got {:?}", other),
+    }
+    match loader.load_linked_file("assets/linked/door.tx") {
+        Ok(Some(LinkedFile::Template(template))) => {
+            assert_eq!(stats_members(&template.object.properties), 6);
+            // The template in the cache is the one that was read, as shared with maps.
+            let cached = loader
+                .cache()
+                .get_template("assets/linked/door.tx")
+                .unwrap();
+            assert_eq!(stats_members(&cached.object.properties), 1);
+        }
+        other => panic!("expected a template, got {:?}", other),
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "json"))]
+#[test]
+fn test_deserialize_project_properties() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Kind {
+        Fire,
+        Water,
+        Earth,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum AiState {
+        Idle,
+        Patrol,
+        Chase,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum DoorFlag {
+        Locked,
+        Hidden,
+        Trapped,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Tag {
+        Red,
+        Green,
+        Blue,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Inner<'a> {
+        label: &'a str,
+        target: u32,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Stats<'a> {
+        enabled: bool,
+        health: u8,
+        #[serde(borrow)]
+        inner: Inner<'a>,
+        kind: Kind,
+        speed: f64,
+        tint: Color,
+        armor: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Level<'a> {
+        kind: Kind,
+        state: AiState,
+        door: Vec<DoorFlag>,
+        tags: Vec<Tag>,
+        #[serde(rename = "no flags")]
+        no_flags: Vec<Tag>,
+        #[serde(borrow)]
+        stats: Stats<'a>,
+    }
+
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/tiled_project.tiled-project")
+        .unwrap();
+    loader.set_project(Some(project));
+    let map = loader
+        .load_tmx_map("assets/tiled_enum_properties.tmx")
+        .unwrap();
+
+    let level: Level = tiled::from_properties(&map.properties).unwrap();
+    assert_eq!(
+        level,
+        Level {
+            kind: Kind::Water,
+            state: AiState::Chase,
+            door: vec![DoorFlag::Locked, DoorFlag::Trapped],
+            tags: vec![Tag::Red, Tag::Blue],
+            no_flags: vec![],
+            stats: Stats {
+                enabled: true,
+                health: 100,
+                inner: Inner {
+                    label: "inner default",
+                    target: 0,
+                },
+                kind: Kind::Earth,
+                speed: 2.5,
+                tint: Color {
+                    alpha: 0xFF,
+                    red: 0,
+                    green: 0xFF,
+                    blue: 0,
+                },
+                armor: None,
+            },
+        }
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_properties() {
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum AiState {
+        Idle,
+        Patrol,
+        Chase,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Tag {
+        Red,
+        Green,
+        Blue,
+    }
+
+    // Without a project, enums are deserialized from the values stored in the file.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_enum_properties.tmx")
+        .unwrap();
+    let state: AiState = tiled::from_property_value(&map.properties["state"]).unwrap();
+    assert_eq!(state, AiState::Chase);
+    let tags: Vec<Tag> = tiled::from_property_value(&map.properties["tags"]).unwrap();
+    assert_eq!(tags, vec![Tag::Red, Tag::Blue]);
+    let raw: i32 = tiled::from_property_value(&map.properties["door"]).unwrap();
+    assert_eq!(raw, 5);
+
+    // Errors name the property they are about.
+    #[derive(Debug, Deserialize)]
+    struct BadStats {
+        #[allow(dead_code)]
+        kind: AiState,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct BadLevel {
+        #[allow(dead_code)]
+        stats: BadStats,
+    }
+
+    let err = tiled::from_properties::<BadLevel>(&map.properties).unwrap_err();
+    assert_eq!(err.property.as_deref(), Some("stats.kind"));
+    assert!(err
+        .to_string()
+        .starts_with("invalid property `stats.kind`: unknown variant `Earth`"));
+
+    #[derive(Debug, Deserialize)]
+    struct MissingField {
+        #[allow(dead_code)]
+        missing: u32,
+    }
+
+    let err = tiled::from_properties::<MissingField>(&map.properties).unwrap_err();
+    assert_eq!(err.property, None);
+
+    // Unset object references and missing members are `None`.
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Target {
+        #[serde(rename = "object property")]
+        target: Option<u32>,
+        missing: Option<u32>,
+    }
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let set: Target = tiled::from_properties(&objects.get_object(0).unwrap().properties).unwrap();
+    assert_eq!(
+        set,
+        Target {
+            target: Some(3),
+            missing: None,
+        }
+    );
+    let unset: Target = tiled::from_properties(&objects.get_object(1).unwrap().properties).unwrap();
+    assert_eq!(
+        unset,
+        Target {
+            target: None,
+            missing: None,
+        }
+    );
+    let raw: u32 =
+        tiled::from_property_value(&objects.get_object(1).unwrap().properties["object property"])
+            .unwrap();
+    assert_eq!(raw, 0);
+}
+
 #[test]
 fn test_object_template_property() {
     let r = Loader::new()
@@ -596,6 +2667,8 @@ fn test_templates() {
         .as_ref()
         .unwrap()
         .source
+        .path()
+        .unwrap()
         .canonicalize()
         .unwrap(),
         PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/tilesheet.png"))
@@ -673,3 +2746,315 @@ fn test_text_object() {
         _ => panic!(),
     };
 }
+
+#[test]
+fn test_tmx_writer_roundtrip() {
+    for path in [
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_base64_gzip.tmx",
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_base64_zstandard.tmx",
+        "assets/tiled_class_property.tmx",
+        "assets/tiled_csv.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_enum_properties.tmx",
+        "assets/tiled_flipped.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_image_layers.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_object_property.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_parallax.tmx",
+        "assets/tiled_text_object.tmx",
+        "assets/tiled_xml.tmx",
+        "assets/folder/tiled_relative_paths.tmx",
+        "assets/templates/example.tmx",
+    ]
+    .iter()
+    {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        assert_eq!(
+            reload_written_tmx(&map),
+            map,
+            "{} changed when rewritten",
+            path
+        );
+    }
+}
+
+#[test]
+fn test_tmx_writer_output() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let mut tmx = Vec::new();
+    map.write_tmx(&mut tmx).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+
+    // External tilesets are referenced relative to the map, and the tile data keeps its encoding.
+    assert!(tmx.contains(r#"source="tilesheet.tsx""#));
+    assert!(tmx.contains(r#"<data encoding="base64">"#));
+}
+
+#[test]
+fn test_tmx_writer_tile_encoding() {
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ]
+    .iter()
+    {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        for encoding in written_tile_encodings() {
+            let mut tmx = Vec::new();
+            map.write_tmx_with_encoding(&mut tmx, encoding).unwrap();
+            let written = loader_with_written_file(&map.source, tmx)
+                .load_tmx_map(&map.source)
+                .unwrap();
+            assert_tiles_written_with(&map, &written, encoding);
+        }
+    }
+}
+
+#[test]
+fn test_tmx_writer_template_objects() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let mut tmx = Vec::new();
+    map.write_tmx(&mut tmx).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    // The written text of an object, up to the start of the next one.
+    let object_element = |id: u32| {
+        let start = tmx.find(&format!(r#"<object id="{}""#, id)).unwrap();
+        let rest = &tmx[start..];
+        &rest[..rest[1..]
+            .find("<object id=")
+            .map_or(rest.len(), |end| end + 1)]
+    };
+
+    // Nothing that is inherited from the template is written again.
+    let templated = object_element(1);
+    assert!(templated.contains(r#"template="tiled_object_template.tx""#));
+    for copied in [
+        "gid=",
+        "name=",
+        "type=",
+        "width=",
+        "height=",
+        "rotation=",
+        "visible=",
+    ]
+    .iter()
+    {
+        assert!(!templated.contains(copied), "{} was copied", copied);
+    }
+    assert!(!templated.contains("<properties>"));
+
+    // Only the size is written for the resized instance.
+    let resized = object_element(3);
+    assert!(resized.contains(r#"width="64""#));
+    assert!(!resized.contains("gid="));
+}
+
+#[test]
+fn test_tsx_writer_roundtrip() {
+    for path in [
+        "assets/tilesheet.tsx",
+        "assets/tilesheet_animated.tsx",
+        "assets/tilesheet_template.tsx",
+        "assets/tilesheet_wangsets.tsx",
+        "assets/templates/grass_walls.tsx",
+        "assets/templates/simple_figure.tsx",
+    ]
+    .iter()
+    {
+        let tileset = Loader::new().load_tsx_tileset(path).unwrap();
+        let mut tsx = Vec::new();
+        tileset.write_tsx(&mut tsx).unwrap();
+        let reloaded = loader_with_written_file(&tileset.source, tsx)
+            .load_tsx_tileset(&tileset.source)
+            .unwrap();
+        assert_eq!(reloaded, tileset, "{} changed when rewritten", path);
+    }
+}
+
+#[test]
+fn test_tsx_writer_relative_paths() {
+    let mut tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    tileset.source = PathBuf::from("assets/folder/tilesheet.tsx");
+    let mut tsx = Vec::new();
+    tileset.write_tsx(&mut tsx).unwrap();
+
+    let reloaded = loader_with_written_file(&tileset.source, tsx)
+        .load_tsx_tileset(&tileset.source)
+        .unwrap();
+    assert_eq!(
+        reloaded.image.unwrap().source,
+        ImageSource::File(PathBuf::from("assets/folder/../tilesheet.png"))
+    );
+}
+
+#[test]
+fn test_tx_writer_roundtrip() {
+    for (map_path, template_path) in [
+        (
+            "assets/tiled_object_template.tmx",
+            "assets/tiled_object_template.tx",
+        ),
+        (
+            "assets/templates/example.tmx",
+            "assets/templates/simple_figure.tx",
+        ),
+    ]
+    .iter()
+    {
+        let mut loader = Loader::new();
+        let map = loader.load_tmx_map(map_path).unwrap();
+        let template = loader
+            .cache()
+            .get_template(Path::new(template_path))
+            .unwrap();
+
+        // Templates can only be loaded through the objects that use them.
+        let mut tx = Vec::new();
+        template.write_tx(&mut tx).unwrap();
+        let reloaded = loader_with_written_file(&template.source, tx)
+            .load_tmx_map(map_path)
+            .unwrap();
+        assert_eq!(reloaded, map, "{} changed when rewritten", template_path);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_tmj_writer_roundtrip() {
+    for path in [
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_base64_gzip.tmx",
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_base64_zstandard.tmx",
+        "assets/tiled_class_property.tmx",
+        "assets/tiled_csv.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_flipped.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_image_layers.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_object_property.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_parallax.tmx",
+        "assets/tiled_text_object.tmx",
+        "assets/folder/tiled_relative_paths.tmx",
+        "assets/templates/example.tmx",
+        "assets/tiled_json_references.tmj",
+    ]
+    .iter()
+    {
+        let mut loader = Loader::new();
+        let map = if path.ends_with(".tmj") {
+            loader.load_tmj_map(path).unwrap()
+        } else {
+            loader.load_tmx_map(path).unwrap()
+        };
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj).unwrap();
+        let reloaded = loader_with_written_file(&map.source, tmj)
+            .load_tmj_map(&map.source)
+            .unwrap();
+        assert_eq!(reloaded, map, "{} changed when written as JSON", path);
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_tmj_writer_output() {
+    let mut loader = Loader::new();
+
+    // CSV data is written as a plain array of GIDs.
+    let map = loader.load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let mut tmj = Vec::new();
+    map.write_tmj(&mut tmj).unwrap();
+    let tmj = String::from_utf8(tmj).unwrap();
+    assert!(tmj.contains(r#""data": ["#));
+    assert!(tmj.contains(r#""encoding": "csv""#));
+
+    // Infinite maps are written as chunks, keeping their encoding and compression.
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let mut tmj = Vec::new();
+    map.write_tmj(&mut tmj).unwrap();
+    let tmj = String::from_utf8(tmj).unwrap();
+    assert!(tmj.contains(r#""chunks": ["#));
+    assert!(tmj.contains(r#""compression": "zlib""#));
+    assert!(tmj.contains(r#""infinite": true"#));
+
+    // Objects that are instances of a template only have their overrides written.
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let mut tmj = Vec::new();
+    map.write_tmj(&mut tmj).unwrap();
+    let tmj: serde_json::Value = serde_json::from_slice(&tmj).unwrap();
+    let objects = tmj["layers"][1]["objects"].as_array().unwrap();
+    let templated = objects[0].as_object().unwrap();
+    let mut members = templated.keys().map(String::as_str).collect::<Vec<_>>();
+    members.sort_unstable();
+    assert_eq!(members, ["id", "template", "x", "y"]);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_tmj_writer_tile_encoding() {
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ]
+    .iter()
+    {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        for encoding in written_tile_encodings() {
+            let mut tmj = Vec::new();
+            map.write_tmj_with_encoding(&mut tmj, encoding).unwrap();
+            let written = loader_with_written_file(&map.source, tmj)
+                .load_tmj_map(&map.source)
+                .unwrap();
+            // JSON files store XML encoded data as plain arrays of GIDs, like CSV data.
+            let expected = match encoding {
+                TileDataEncoding::Xml => TileDataEncoding::Csv,
+                encoding => encoding,
+            };
+            assert_tiles_written_with(&map, &written, expected);
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_tsj_writer_roundtrip() {
+    for path in [
+        "assets/tilesheet.tsx",
+        "assets/tilesheet_animated.tsx",
+        "assets/tilesheet_template.tsx",
+        "assets/tilesheet_wangsets.tsx",
+        "assets/templates/grass_walls.tsx",
+        "assets/templates/simple_figure.tsx",
+    ]
+    .iter()
+    {
+        let tileset = Loader::new().load_tsx_tileset(path).unwrap();
+        let mut tsj = Vec::new();
+        tileset.write_tsj(&mut tsj).unwrap();
+        let reloaded = loader_with_written_file(&tileset.source, tsj)
+            .load_tsj_tileset(&tileset.source)
+            .unwrap();
+        assert_eq!(reloaded, tileset, "{} changed when written as JSON", path);
+    }
+}